@@ -0,0 +1,2 @@
+-- Index for the recently updated products feed
+CREATE INDEX IF NOT EXISTS idx_products_updated_at ON products(updated_at DESC);
@@ -0,0 +1,3 @@
+-- Add optional stock keeping unit, unique when present
+ALTER TABLE products ADD COLUMN IF NOT EXISTS sku VARCHAR(100);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_products_sku ON products(sku);
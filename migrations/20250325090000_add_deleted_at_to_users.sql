@@ -0,0 +1,2 @@
+-- Soft delete support for users
+ALTER TABLE users ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
@@ -0,0 +1,16 @@
+-- Free-form tags, created on demand and shared between products
+CREATE TABLE IF NOT EXISTS tags (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(50) NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Many-to-many link between products and tags
+CREATE TABLE IF NOT EXISTS product_tags (
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    PRIMARY KEY (product_id, tag_id)
+);
+
+-- Lookups by tag (the ?tag= list filter)
+CREATE INDEX IF NOT EXISTS idx_product_tags_tag_id ON product_tags(tag_id);
@@ -0,0 +1,8 @@
+-- Normalized category used for case-insensitive filtering; `category` keeps the display value
+ALTER TABLE products ADD COLUMN IF NOT EXISTS category_slug VARCHAR(100);
+
+UPDATE products
+SET category_slug = LOWER(REGEXP_REPLACE(BTRIM(category), '\s+', '-', 'g'))
+WHERE category IS NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_products_category_slug ON products (category_slug);
@@ -0,0 +1,5 @@
+-- Storefront highlight flag, independent of is_active
+ALTER TABLE products ADD COLUMN IF NOT EXISTS is_featured BOOLEAN NOT NULL DEFAULT false;
+
+-- The featured list only reads active featured products, newest update first
+CREATE INDEX IF NOT EXISTS idx_products_featured ON products(updated_at DESC) WHERE is_featured AND is_active;
@@ -0,0 +1,12 @@
+-- Deleted products kept for a short undo window
+CREATE TABLE IF NOT EXISTS deleted_products (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    product_id UUID NOT NULL,
+    snapshot JSONB NOT NULL,
+    tags TEXT[] NOT NULL DEFAULT '{}',
+    deleted_by UUID NOT NULL,
+    deleted_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_deleted_products_expires_at ON deleted_products(expires_at);
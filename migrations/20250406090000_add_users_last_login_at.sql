@@ -0,0 +1,15 @@
+-- Time of the last successful login, for sorting the admin user list
+ALTER TABLE users ADD COLUMN IF NOT EXISTS last_login_at TIMESTAMPTZ;
+
+-- Backfill from the login audit log
+UPDATE users u SET last_login_at = e.last_login
+FROM (
+    SELECT user_id, MAX(created_at) AS last_login
+    FROM auth_events
+    WHERE event_type = 'login' AND success AND user_id IS NOT NULL
+    GROUP BY user_id
+) e
+WHERE e.user_id = u.id AND u.last_login_at IS NULL;
+
+-- Registration-window searches filter on created_at
+CREATE INDEX IF NOT EXISTS idx_users_created_at ON users(created_at);
@@ -0,0 +1,13 @@
+-- Deleted products stay in the table, so undo and restore keep their audit
+-- history, favorites and order links instead of losing them to the cascades
+ALTER TABLE products ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+
+-- Identifies one delete; cleared on undo/restore so an undo token works only once
+ALTER TABLE products ADD COLUMN IF NOT EXISTS delete_id UUID;
+
+-- Only live products hold their SKU
+DROP INDEX IF EXISTS idx_products_sku;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_products_sku ON products(sku) WHERE deleted_at IS NULL;
+
+-- Replaced by the columns above
+DROP TABLE IF EXISTS deleted_products;
@@ -0,0 +1,48 @@
+use learning_rust::core::config::Config;
+use learning_rust::core::db::{init_db, migration_states, run_migrations};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    // Load environment variables
+    dotenv::dotenv().ok();
+
+    // `--check` only reports migration status and never changes the schema
+    let check_only = std::env::args().skip(1).any(|arg| arg == "--check");
+
+    if check_only {
+        println!("Checking migrations...");
+    } else {
+        println!("Starting migrations...");
+    }
+
+    // Load config
+    let config = Config::from_env()?;
+
+    // Initialize database
+    println!("Connecting to database...");
+    let db_pool = init_db(&config.database_url).await?;
+    println!("Database connection established");
+
+    if check_only {
+        let states = migration_states(&db_pool).await?;
+        for state in &states {
+            let status = if state.applied { "applied" } else { "pending" };
+            println!("{:<8} {} {}", status, state.version, state.description);
+        }
+
+        let pending = states.iter().filter(|state| !state.applied).count();
+        if pending > 0 {
+            eprintln!("{} pending migration(s)", pending);
+            std::process::exit(1);
+        }
+
+        println!("All {} migrations applied", states.len());
+        return Ok(());
+    }
+
+    // Run migrations regardless of RUN_MIGRATIONS
+    run_migrations(&db_pool).await?;
+
+    println!("Migrations completed successfully");
+    Ok(())
+}
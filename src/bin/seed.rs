@@ -1,6 +1,6 @@
 use learning_rust::core::config::Config;
 use learning_rust::core::db::init_db;
-use learning_rust::seeder;
+use learning_rust::seeder::{self, SeedMode};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -10,7 +10,14 @@ async fn main() -> anyhow::Result<()> {
     println!("Starting seeder...");
 
     // Load config
-    let config = Config::from_env();
+    let config = Config::from_env()?;
+
+    // Refuse to wipe a production database
+    let mode = SeedMode::from_env()?;
+    if mode == SeedMode::Force && config.is_production() {
+        anyhow::bail!("SEED_MODE=force is not allowed when APP_ENV=production");
+    }
+    println!("Seed mode: {:?}", mode);
 
     // Initialize database
     println!("Connecting to database...");
@@ -18,9 +25,20 @@ async fn main() -> anyhow::Result<()> {
     println!("Database connection established");
 
     // Run all seeders
-    println!("Running product seeder...");
-    seeder::run_all_seeders(&db_pool).await?;
+    println!("Running seeders...");
+    let summaries = seeder::run_all_seeders(&db_pool, &config.features).await?;
+
+    let mut has_failures = false;
+    for (name, summary) in &summaries {
+        println!("{}: {}", name, summary);
+        has_failures |= summary.has_failures();
+    }
+
+    if has_failures && seeder::strict_mode() {
+        eprintln!("Seeding failed in strict mode");
+        std::process::exit(1);
+    }
 
-    println!("Seeding completed successfully");
+    println!("Seeding completed");
     Ok(())
 }
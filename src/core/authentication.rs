@@ -1,59 +1,554 @@
 use crate::core::error::ApiError;
-use crate::utils::verify_token;
+use crate::utils::{TOKEN_TTL_HOURS, verify_token};
 use axum::{
-    extract::Request,
-    http::header,
+    extract::{FromRequestParts, OptionalFromRequestParts, Request},
+    http::{HeaderMap, Method, header, request::Parts},
     middleware::Next,
     response::{IntoResponse, Response},
 };
 use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
 use tracing::info;
+use uuid::Uuid;
 
-/// Struktur klaim JWT untuk dekoding token
-#[derive(Debug, Serialize, Deserialize)]
-struct Claims {
-    sub: String, // User ID
-    exp: usize,  // Waktu kedaluwarsa
-    iat: usize,  // Waktu diterbitkan
+/// Aturan autentikasi untuk satu prefix path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuthRule {
+    /// Prefix path yang diatur rule ini
+    pub prefix: &'static str,
+    /// Method yang boleh diakses tanpa token; method lain wajib login
+    pub public_methods: &'static [Method],
 }
 
-/// Middleware untuk autentikasi token JWT
+/// Aturan autentikasi yang dipakai `auth_middleware`, dicek berurutan
+///
+/// Katalog produk bisa dibaca tanpa login, tapi POST/PUT/PATCH/DELETE tetap
+/// wajib token. Path yang tidak cocok dengan rule mana pun tidak dilindungi.
+pub const AUTH_RULES: &[AuthRule] = &[
+    AuthRule {
+        prefix: "/api/products",
+        public_methods: &[Method::GET, Method::HEAD],
+    },
+    AuthRule {
+        prefix: "/api/outbox",
+        public_methods: &[],
+    },
+    AuthRule {
+        prefix: "/api/me",
+        public_methods: &[],
+    },
+];
+
+/// Apakah request perlu token menurut `rules`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Access {
+    /// Tidak diatur rule mana pun
+    Unprotected,
+    /// Boleh tanpa token; kalau token valid dikirim, pengguna tetap dikenali
+    Public,
+    /// Wajib token yang valid
+    Required,
+}
+
+/// Cari aturan akses untuk method dan path dari rule pertama yang cocok
+fn access_for(rules: &[AuthRule], method: &Method, path: &str) -> Access {
+    match rules.iter().find(|rule| path.starts_with(rule.prefix)) {
+        None => Access::Unprotected,
+        Some(rule) if rule.public_methods.contains(method) => Access::Public,
+        Some(_) => Access::Required,
+    }
+}
+
+/// Nama cookie yang menyimpan token JWT untuk klien browser
+pub const TOKEN_COOKIE: &str = "token";
+
+/// Role pengguna yang menentukan hak akses
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    #[default]
+    User,
+    Admin,
+}
+
+impl Role {
+    /// Nama role seperti yang disimpan di database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::User => "user",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "user" => Ok(Role::User),
+            "admin" => Ok(Role::Admin),
+            other => Err(format!("Unknown role: {}", other)),
+        }
+    }
+}
+
+/// Pengguna yang sedang login, disisipkan ke extensions oleh middleware
+#[derive(Debug, Clone, Copy)]
+pub struct CurrentUser {
+    pub id: Uuid,
+    pub role: Role,
+}
+
+impl CurrentUser {
+    /// Cek apakah pengguna punya role admin
+    pub fn is_admin(&self) -> bool {
+        self.role == Role::Admin
+    }
+}
+
+/// Extractor untuk pengguna yang sudah login
+///
+/// Membaca `CurrentUser` yang disisipkan `auth_middleware`, `require_user` atau
+/// `require_admin`, jadi handler tidak perlu `Extension<CurrentUser>` lagi.
+/// Kalau tidak ada (route tidak dilindungi middleware), request ditolak dengan
+/// 401, bukan 500 seperti `Extension`.
+#[derive(Debug, Clone, Copy)]
+pub struct AuthUser(pub CurrentUser);
+
+impl<S: Send + Sync> FromRequestParts<S> for AuthUser {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<CurrentUser>()
+            .copied()
+            .map(AuthUser)
+            .ok_or_else(|| ApiError::Unauthorized("Authentication required".to_string()))
+    }
+}
+
+/// `Option<AuthUser>` untuk route publik: `None` kalau request anonim
+impl<S: Send + Sync> OptionalFromRequestParts<S> for AuthUser {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> Result<Option<Self>, Self::Rejection> {
+        Ok(parts.extensions.get::<CurrentUser>().copied().map(AuthUser))
+    }
+}
+
+/// Middleware untuk autentikasi token JWT sesuai `AUTH_RULES`
 pub async fn auth_middleware(req: Request, next: Next) -> Response {
-    // Cek path request - kalo gak dimulai dengan /api/products, skip auth
-    let path = req.uri().path();
-    if !path.starts_with("/api/products") {
-        return next.run(req).await;
-    }
-
-    // Ambil token dari header Authorization
-    let auth_header = req.headers().get(header::AUTHORIZATION);
-
-    let token = match auth_header {
-        Some(value) => {
-            let auth_value = value.to_str().unwrap_or_default();
-            // Token biasanya dalam format "Bearer {token}"
-            if let Some(token) = auth_value.strip_prefix("Bearer ") {
-                token
-            } else {
-                return ApiError::Unauthorized("Invalid authorization format".to_string())
-                    .into_response();
+    match access_for(AUTH_RULES, req.method(), req.uri().path()) {
+        // Path di luar rule - skip auth
+        Access::Unprotected => next.run(req).await,
+        // Token opsional: token yang tidak valid diabaikan, request diteruskan sebagai anonim
+        Access::Public => match authenticate(req.headers()) {
+            Ok(current_user) => next.run(with_user(req, current_user)).await,
+            Err(_) => next.run(req).await,
+        },
+        Access::Required => authenticate_required(req, next).await,
+    }
+}
+
+/// Masukkan pengguna ke extensions biar bisa diakses handler
+fn with_user(mut req: Request, current_user: CurrentUser) -> Request {
+    req.extensions_mut().insert(current_user.id);
+    req.extensions_mut().insert(current_user);
+    req
+}
+
+/// Tolak request tanpa token yang valid
+async fn authenticate_required(req: Request, next: Next) -> Response {
+    // Verifikasi token dari header atau cookie
+    match authenticate(req.headers()) {
+        Ok(current_user) => {
+            info!(
+                "Authorized user: {} ({})",
+                current_user.id, current_user.role
+            );
+            next.run(with_user(req, current_user)).await
+        }
+        Err(error) => error.into_response(),
+    }
+}
+
+/// Middleware yang hanya mengizinkan pengguna yang sudah login
+///
+/// Dipasang per route (via `route_layer`) untuk path di luar `AUTH_RULES`.
+pub async fn require_user(mut req: Request, next: Next) -> Response {
+    let current_user = match request_user(&req) {
+        Ok(user) => user,
+        Err(error) => return error.into_response(),
+    };
+
+    req.extensions_mut().insert(current_user.id);
+    req.extensions_mut().insert(current_user);
+    next.run(req).await
+}
+
+/// Middleware yang hanya mengizinkan pengguna dengan role admin
+///
+/// Dipasang per route (via `route_layer`), jadi bisa dipakai juga di path yang
+/// tidak termasuk `AUTH_RULES`.
+pub async fn require_admin(mut req: Request, next: Next) -> Response {
+    let current_user = match request_user(&req) {
+        Ok(user) => user,
+        Err(error) => return error.into_response(),
+    };
+
+    if !current_user.is_admin() {
+        return ApiError::Forbidden("Admin access required".to_string()).into_response();
+    }
+
+    req.extensions_mut().insert(current_user.id);
+    req.extensions_mut().insert(current_user);
+    next.run(req).await
+}
+
+/// Middleware yang mengenali pengguna kalau token valid dikirim, tanpa mewajibkannya
+///
+/// Untuk route di luar `AUTH_RULES` yang sebagian perilakunya butuh pengguna;
+/// handler memakai `Option<AuthUser>` dan memutuskan sendiri.
+pub async fn identify_user(req: Request, next: Next) -> Response {
+    match request_user(&req) {
+        Ok(current_user) => next.run(with_user(req, current_user)).await,
+        Err(_) => next.run(req).await,
+    }
+}
+
+/// Pengguna dari extensions (sudah diautentikasi middleware lain) atau dari token
+fn request_user(req: &Request) -> Result<CurrentUser, ApiError> {
+    match req.extensions().get::<CurrentUser>() {
+        Some(user) => Ok(*user),
+        None => authenticate(req.headers()),
+    }
+}
+
+/// Ambil dan verifikasi token dari request
+fn authenticate(headers: &HeaderMap) -> Result<CurrentUser, ApiError> {
+    let token = extract_token(headers)?;
+    verify_token(&token).map_err(|error| ApiError::Unauthorized(error.to_string()))
+}
+
+/// Ambil token dari header `Authorization`, fallback ke cookie `token`
+///
+/// Header selalu diutamakan kalo ada, walaupun cookie juga dikirim.
+fn extract_token(headers: &HeaderMap) -> Result<String, ApiError> {
+    if let Some(value) = headers.get(header::AUTHORIZATION) {
+        // Byte non-ASCII bikin header tidak bisa dibaca sama sekali, beda dengan format yang salah
+        let auth_value = value
+            .to_str()
+            .map_err(|_| ApiError::BadRequest("Malformed Authorization header".to_string()))?;
+        return bearer_token(auth_value)
+            .map(str::to_string)
+            .ok_or_else(|| ApiError::Unauthorized("Invalid authorization format".to_string()));
+    }
+
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|cookies| cookies.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(name, value)| *name == TOKEN_COOKIE && !value.is_empty())
+        .map(|(_, value)| value.to_string())
+        .ok_or_else(|| ApiError::Unauthorized("Missing authorization header".to_string()))
+}
+
+/// Token dari nilai header dengan format "Bearer {token}"
+///
+/// Spasi di sekitar nilai diabaikan dan skema "Bearer" tidak case-sensitive.
+fn bearer_token(auth_value: &str) -> Option<&str> {
+    let (scheme, token) = auth_value.trim().split_once(' ')?;
+    let token = token.trim_start();
+    (scheme.eq_ignore_ascii_case("bearer") && !token.is_empty()).then_some(token)
+}
+
+/// Nilai header `Set-Cookie` untuk menyimpan token di cookie HttpOnly
+pub fn token_cookie(token: &str) -> String {
+    format!(
+        "{}={}; HttpOnly; Secure; SameSite=Lax; Path=/; Max-Age={}",
+        TOKEN_COOKIE,
+        token,
+        TOKEN_TTL_HOURS * 60 * 60
+    )
+}
+
+/// Nilai header `Set-Cookie` untuk menghapus cookie token
+pub fn clear_token_cookie() -> String {
+    format!(
+        "{}=; HttpOnly; Secure; SameSite=Lax; Path=/; Max-Age=0",
+        TOKEN_COOKIE
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_role_from_str() {
+        assert_eq!("admin".parse::<Role>(), Ok(Role::Admin));
+        assert_eq!("user".parse::<Role>(), Ok(Role::User));
+        assert!("superuser".parse::<Role>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_require_admin_rejects_regular_users() {
+        use axum::{Router, body::Body, http::StatusCode, middleware, routing::get};
+        use tower::ServiceExt;
+
+        let app = |role| {
+            Router::new()
+                .route(
+                    "/admin",
+                    get(|| async { "ok" }).route_layer(middleware::from_fn(require_admin)),
+                )
+                .layer(axum::Extension(CurrentUser {
+                    id: Uuid::new_v4(),
+                    role,
+                }))
+        };
+        let request = || {
+            Request::builder()
+                .uri("/admin")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = app(Role::User).oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = app(Role::Admin).oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_auth_middleware_protects_me_routes() {
+        use axum::{Router, body::Body, http::StatusCode, middleware, routing::get};
+        use tower::ServiceExt;
+
+        let app = Router::new()
+            .route("/api/me/summary", get(|| async { "ok" }))
+            .route("/health", get(|| async { "ok" }))
+            .layer(middleware::from_fn(auth_middleware));
+        let request = |uri| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+        let response = app
+            .clone()
+            .oneshot(request("/api/me/summary"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = app.oneshot(request("/health")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn test_access_for_uses_method_rules() {
+        let get = |path| access_for(AUTH_RULES, &Method::GET, path);
+        let post = |path| access_for(AUTH_RULES, &Method::POST, path);
+
+        assert_eq!(get("/api/products/123"), Access::Public);
+        assert_eq!(post("/api/products"), Access::Required);
+        assert_eq!(
+            access_for(AUTH_RULES, &Method::DELETE, "/api/products/123"),
+            Access::Required
+        );
+        assert_eq!(get("/api/me/favorites"), Access::Required);
+        assert_eq!(get("/api/outbox"), Access::Required);
+        assert_eq!(post("/api/auth/login"), Access::Unprotected);
+    }
+
+    #[tokio::test]
+    async fn test_auth_middleware_allows_anonymous_product_reads_only() {
+        use axum::{Router, body::Body, http::StatusCode, middleware, routing::get};
+        use tower::ServiceExt;
+
+        // Handler melaporkan apakah pengguna dikenali
+        let app = Router::new()
+            .route(
+                "/api/products",
+                get(|user: Option<AuthUser>| async move {
+                    if user.is_some() { "user" } else { "anonymous" }
+                })
+                .post(|| async { "created" }),
+            )
+            .layer(middleware::from_fn(auth_middleware));
+        let request = |method, token: Option<&str>| {
+            let mut builder = Request::builder().method(method).uri("/api/products");
+            if let Some(token) = token {
+                builder = builder.header(header::AUTHORIZATION, format!("Bearer {}", token));
             }
+            builder.body(Body::empty()).unwrap()
+        };
+
+        // GET anonim (juga dengan token tidak valid) diteruskan tanpa pengguna
+        for token in [None, Some("not-a-jwt")] {
+            let response = app
+                .clone()
+                .oneshot(request(Method::GET, token))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            assert_eq!(&body[..], b"anonymous");
         }
-        None => {
-            return ApiError::Unauthorized("Missing authorization header".to_string())
-                .into_response();
+
+        // POST anonim ditolak
+        let response = app.oneshot(request(Method::POST, None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_auth_user_extractor_requires_authenticated_user() {
+        use axum::{Router, body::Body, http::StatusCode, middleware, routing::get};
+        use tower::ServiceExt;
+
+        let app = Router::new()
+            .route(
+                "/api/me/id",
+                get(|AuthUser(user): AuthUser| async move { user.id.to_string() }),
+            )
+            .route(
+                "/open",
+                get(|AuthUser(user): AuthUser| async move { user.id.to_string() }),
+            )
+            .layer(middleware::from_fn(auth_middleware));
+        let request = |uri, token: Option<&str>| {
+            let mut builder = Request::builder().uri(uri);
+            if let Some(token) = token {
+                builder = builder.header(header::AUTHORIZATION, format!("Bearer {}", token));
+            }
+            builder.body(Body::empty()).unwrap()
+        };
+
+        // Tanpa token dan dengan token yang tidak valid ditolak middleware
+        for token in [None, Some("not-a-jwt")] {
+            let response = app
+                .clone()
+                .oneshot(request("/api/me/id", token))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
         }
-    };
 
-    // Verifikasi token dgn fungsi dari utils
-    match verify_token(token) {
-        Ok(user_id) => {
-            info!("Authorized user: {}", user_id);
-            // Kita bisa nambah user_id ke extensions kalo perlu akses di handler
-            let mut modified_req = req;
-            modified_req.extensions_mut().insert(user_id);
-            next.run(modified_req).await
+        // Route tanpa middleware auth: extractor sendiri yang menolak dengan 401
+        let response = app.clone().oneshot(request("/open", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        // Pengguna yang sudah diautentikasi middleware diteruskan ke handler
+        let user = CurrentUser {
+            id: Uuid::new_v4(),
+            role: Role::User,
+        };
+        let mut authenticated = request("/open", None);
+        authenticated.extensions_mut().insert(user);
+        let response = app.oneshot(authenticated).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body, user.id.to_string());
+    }
+
+    #[test]
+    fn test_extract_token_from_cookie() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::COOKIE,
+            HeaderValue::from_static("theme=dark; token=cookie-token"),
+        );
+
+        assert_eq!(extract_token(&headers).unwrap(), "cookie-token");
+    }
+
+    #[test]
+    fn test_extract_token_prefers_authorization_header() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer header-token"),
+        );
+        headers.insert(
+            header::COOKIE,
+            HeaderValue::from_static("token=cookie-token"),
+        );
+
+        assert_eq!(extract_token(&headers).unwrap(), "header-token");
+    }
+
+    #[test]
+    fn test_extract_token_missing() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::COOKIE, HeaderValue::from_static("token="));
+
+        assert!(matches!(
+            extract_token(&headers),
+            Err(ApiError::Unauthorized(_))
+        ));
+    }
+
+    #[test]
+    fn test_extract_token_accepts_lowercase_bearer_and_surrounding_spaces() {
+        for value in [
+            "bearer header-token",
+            "  BEARER  header-token  ",
+            "Bearer header-token\t",
+        ] {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::AUTHORIZATION, HeaderValue::from_str(value).unwrap());
+            assert_eq!(
+                extract_token(&headers).unwrap(),
+                "header-token",
+                "{value:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_extract_token_rejects_other_schemes() {
+        for value in [
+            "Basic dXNlcjpwYXNz",
+            "Bearer",
+            "Bearer   ",
+            "Bearerheader-token",
+        ] {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::AUTHORIZATION, HeaderValue::from_str(value).unwrap());
+            assert!(
+                matches!(extract_token(&headers), Err(ApiError::Unauthorized(_))),
+                "{value:?}"
+            );
         }
-        Err(error) => ApiError::Unauthorized(error.to_string()).into_response(),
+    }
+
+    #[test]
+    fn test_extract_token_rejects_non_ascii_header() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_bytes("Bearer tökén".as_bytes()).unwrap(),
+        );
+
+        assert!(matches!(
+            extract_token(&headers),
+            Err(ApiError::BadRequest(message)) if message == "Malformed Authorization header"
+        ));
     }
 }
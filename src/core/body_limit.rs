@@ -0,0 +1,72 @@
+use crate::core::error::ApiError;
+use axum::{
+    extract::{DefaultBodyLimit, Request},
+    http::{StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+/// Default maximum request body size (1 MB)
+pub const DEFAULT_MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// Default maximum request body size for bulk endpoints (10 MB)
+pub const DEFAULT_MAX_BULK_BODY_BYTES: usize = 10 * 1024 * 1024;
+
+/// Body limit layer allowing at most `max_bytes`
+///
+/// Applied to the whole router with `MAX_BODY_BYTES`; individual routes can
+/// add their own layer (e.g. `MAX_BULK_BODY_BYTES`) to override it.
+pub fn body_limit(max_bytes: usize) -> DefaultBodyLimit {
+    DefaultBodyLimit::max(max_bytes)
+}
+
+/// Rewrite plain-text 413 rejections into the API's `{"error": ...}` shape
+pub async fn payload_too_large_middleware(req: Request, next: Next) -> Response {
+    let response = next.run(req).await;
+
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+
+    if response.status() == StatusCode::PAYLOAD_TOO_LARGE && !is_json {
+        return ApiError::PayloadTooLarge("Request body is too large".to_string()).into_response();
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Json, Router, body::Body, middleware, routing::post};
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_oversized_body_returns_json_413() {
+        let app = Router::new()
+            .route(
+                "/items",
+                post(|Json(body): Json<Value>| async { Json(body) }),
+            )
+            .layer(DefaultBodyLimit::max(16))
+            .layer(middleware::from_fn(payload_too_large_middleware));
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/items")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(format!(r#"{{"name":"{}"}}"#, "x".repeat(64))))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"], "Request body is too large");
+    }
+}
@@ -0,0 +1,109 @@
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::HeaderMap,
+    middleware::Next,
+    response::Response,
+};
+use std::net::{IpAddr, SocketAddr};
+
+/// Resolved IP address of the client that sent the request
+///
+/// Inserted into request extensions by `client_ip_middleware`; handlers can
+/// read it with `Extension<ClientIp>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub IpAddr);
+
+/// Middleware that resolves the client IP and stores it as a request extension
+///
+/// The state flag (`TRUST_PROXY`) controls whether proxy headers are honoured.
+pub async fn client_ip_middleware(
+    State(trust_proxy): State<bool>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let peer = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| *addr);
+
+    if let Some(ip) = resolve_client_ip(req.headers(), peer, trust_proxy) {
+        req.extensions_mut().insert(ClientIp(ip));
+    }
+
+    next.run(req).await
+}
+
+/// Determine the client IP from proxy headers (when trusted) or the socket peer
+///
+/// With `trust_proxy`, the left-most `X-Forwarded-For` entry wins, then
+/// `X-Real-IP`. Without it, headers are ignored since clients can forge them.
+pub fn resolve_client_ip(
+    headers: &HeaderMap,
+    peer: Option<SocketAddr>,
+    trust_proxy: bool,
+) -> Option<IpAddr> {
+    if trust_proxy {
+        let forwarded = headers
+            .get("x-forwarded-for")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(',').next())
+            .and_then(|ip| ip.trim().parse().ok());
+
+        let real_ip = || {
+            headers
+                .get("x-real-ip")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|ip| ip.trim().parse().ok())
+        };
+
+        if let Some(ip) = forwarded.or_else(real_ip) {
+            return Some(ip);
+        }
+    }
+
+    peer.map(|addr| addr.ip())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn peer() -> Option<SocketAddr> {
+        Some("10.0.0.1:54321".parse().unwrap())
+    }
+
+    fn forwarded_headers() -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("203.0.113.7, 10.0.0.2"),
+        );
+        headers.insert("x-real-ip", HeaderValue::from_static("198.51.100.4"));
+        headers
+    }
+
+    #[test]
+    fn test_trusted_proxy_uses_left_most_forwarded_for() {
+        let ip = resolve_client_ip(&forwarded_headers(), peer(), true);
+        assert_eq!(ip, Some("203.0.113.7".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_trusted_proxy_falls_back_to_real_ip() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-real-ip", HeaderValue::from_static("198.51.100.4"));
+
+        let ip = resolve_client_ip(&headers, peer(), true);
+        assert_eq!(ip, Some("198.51.100.4".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_untrusted_proxy_uses_socket_peer() {
+        let ip = resolve_client_ip(&forwarded_headers(), peer(), false);
+        assert_eq!(ip, Some("10.0.0.1".parse().unwrap()));
+
+        let ip = resolve_client_ip(&HeaderMap::new(), peer(), true);
+        assert_eq!(ip, Some("10.0.0.1".parse().unwrap()));
+    }
+}
@@ -1,14 +1,366 @@
-use sqlx::postgres::{PgPool, PgPoolOptions};
-use std::time::Duration;
+use futures::future::BoxFuture;
+use once_cell::sync::OnceCell;
+use sqlx::{
+    Postgres, Transaction,
+    postgres::{PgPool, PgPoolOptions},
+};
+use std::{
+    collections::HashSet,
+    future::Future,
+    marker::PhantomData,
+    time::{Duration, Instant},
+};
+
+use crate::core::error::ApiError;
 
 /// Type alias for database connection pool
 pub type DbPool = PgPool;
 
-/// Initialize database connection pool
+/// Default threshold above which a query is logged as slow
+pub const DEFAULT_SLOW_QUERY_MS: u64 = 500;
+
+/// Slow query threshold (`SLOW_QUERY_MS`), set once when the server starts
+static SLOW_QUERY_THRESHOLD: OnceCell<Duration> = OnceCell::new();
+
+/// Set the slow query threshold; later calls are ignored
+pub fn set_slow_query_threshold(threshold: Duration) {
+    let _ = SLOW_QUERY_THRESHOLD.set(threshold);
+}
+
+/// The configured slow query threshold, or the default when none was set (e.g. in tools)
+fn slow_query_threshold() -> Duration {
+    SLOW_QUERY_THRESHOLD
+        .get()
+        .copied()
+        .unwrap_or(Duration::from_millis(DEFAULT_SLOW_QUERY_MS))
+}
+
+/// Primary pool for writes plus a pool for read-only queries
+///
+/// Without a replica both fields share the same underlying pool.
+#[derive(Clone, Debug)]
+pub struct DbPools {
+    pub write: DbPool,
+    pub read: DbPool,
+}
+
+impl DbPools {
+    /// Use a single pool for both reads and writes
+    pub fn single(pool: DbPool) -> Self {
+        Self {
+            write: pool.clone(),
+            read: pool,
+        }
+    }
+
+    /// Connect to the primary and, when a read URL is given, to the replica
+    pub async fn connect(
+        database_url: &str,
+        read_url: Option<&str>,
+        settings: PoolSettings,
+    ) -> Result<Self, sqlx::Error> {
+        let write = init_pool(database_url, settings).await?;
+
+        match read_url {
+            Some(read_url) => Ok(Self {
+                write,
+                read: init_pool(read_url, settings).await?,
+            }),
+            None => Ok(Self::single(write)),
+        }
+    }
+}
+
+/// Maximum number of connections in each pool
+pub const MAX_DB_CONNECTIONS: u32 = 10;
+
+/// Longest startup may wait for the pool warmup before serving anyway
+pub const DB_WARMUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Pool sizing and startup behavior from `DB_MIN_CONNECTIONS` and `DB_WARMUP`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PoolSettings {
+    pub min_connections: u32, // Idle connections the pool keeps open (at most MAX_DB_CONNECTIONS)
+    pub warmup: bool,         // Open `min_connections` connections before serving
+}
+
+/// Initialize database connection pool with default settings
 pub async fn init_db(database_url: &str) -> Result<DbPool, sqlx::Error> {
-    PgPoolOptions::new()
-        .max_connections(10)
+    init_pool(database_url, PoolSettings::default()).await
+}
+
+/// Initialize a database connection pool, warming it up first when configured
+pub async fn init_pool(database_url: &str, settings: PoolSettings) -> Result<DbPool, sqlx::Error> {
+    let pool = PgPoolOptions::new()
+        .max_connections(MAX_DB_CONNECTIONS)
+        .min_connections(settings.min_connections)
         .acquire_timeout(Duration::from_secs(5))
         .connect(database_url)
-        .await
+        .await?;
+
+    if settings.warmup {
+        warm_up(&pool, settings.min_connections, DB_WARMUP_TIMEOUT).await;
+    }
+
+    Ok(pool)
+}
+
+/// Open `connections` connections at once and hand them back to the pool as idle ones
+///
+/// Gives up after `timeout` so a slow database delays startup instead of blocking it;
+/// connections that were not opened in time are left to the pool to open on demand.
+/// Returns the number of idle connections afterwards.
+pub async fn warm_up(pool: &DbPool, connections: u32, timeout: Duration) -> usize {
+    let started = Instant::now();
+    let acquire_all = futures::future::join_all((0..connections).map(|_| pool.acquire()));
+
+    let opened = match tokio::time::timeout(timeout, acquire_all).await {
+        Ok(results) => {
+            let opened = results.iter().filter(|result| result.is_ok()).count();
+            if opened < results.len() {
+                tracing::warn!(
+                    "Database pool warmup could not open {} of {} connections",
+                    results.len() - opened,
+                    connections
+                );
+            }
+            opened
+        }
+        Err(_) => {
+            tracing::warn!(
+                "Database pool warmup timed out after {} ms",
+                timeout.as_millis()
+            );
+            0
+        }
+    };
+
+    // Dropped connections are handed back by background tasks, so wait for them to show up
+    while pool.num_idle() < opened && started.elapsed() < timeout {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+
+    let idle = pool.num_idle();
+    tracing::info!(
+        "Database pool warmed up: {} idle connections in {} ms",
+        idle,
+        started.elapsed().as_millis()
+    );
+    idle
+}
+
+/// Apply pending migrations from the `migrations` directory
+pub async fn run_migrations(pool: &DbPool) -> Result<(), sqlx::migrate::MigrateError> {
+    sqlx::migrate!("./migrations").run(pool).await
+}
+
+/// Await a query, logging a warning with `label` when it exceeds `SLOW_QUERY_MS`
+pub async fn timed<F: Future>(label: &str, query: F) -> F::Output {
+    let start = Instant::now();
+    let output = query.await;
+    warn_if_slow(label, start.elapsed(), slow_query_threshold());
+    output
+}
+
+/// Marker tying a transaction borrow `'c` to the caller's borrows `'a`
+///
+/// It lets the boxed future of a `with_transaction` operation capture
+/// references from the surrounding function; the closure can ignore it.
+pub type TxScope<'c, 'a> = PhantomData<&'c &'a ()>;
+
+/// Run `operation` inside a transaction on `pool`
+///
+/// Commits when the operation returns `Ok` and rolls back when it returns
+/// `Err`, so none of its writes are kept. Errors from `begin`/`commit` are
+/// mapped to `ApiError::Database`; a failed rollback is logged and the
+/// operation's error is returned. Operations are written as
+/// `|tx, _| Box::pin(async move { ... })`.
+pub async fn with_transaction<'a, 't, T, F>(pool: &DbPool, operation: F) -> Result<T, ApiError>
+where
+    F: for<'c> FnOnce(
+        &'c mut Transaction<'t, Postgres>,
+        TxScope<'c, 'a>,
+    ) -> BoxFuture<'c, Result<T, ApiError>>,
+{
+    // A generic transaction lifetime (rather than 'static) keeps closure inference working
+    let mut tx: Transaction<'t, Postgres> = pool.begin().await.map_err(ApiError::Database)?;
+
+    match operation(&mut tx, PhantomData).await {
+        Ok(value) => {
+            tx.commit().await.map_err(ApiError::Database)?;
+            Ok(value)
+        }
+        Err(err) => {
+            if let Err(rollback_err) = tx.rollback().await {
+                tracing::warn!("Failed to roll back transaction: {}", rollback_err);
+            }
+            Err(err)
+        }
+    }
+}
+
+/// Log a slow query warning, returning whether the threshold was exceeded
+fn warn_if_slow(label: &str, elapsed: Duration, threshold: Duration) -> bool {
+    if elapsed < threshold {
+        return false;
+    }
+
+    tracing::warn!(
+        query = label,
+        elapsed_ms = elapsed.as_millis() as u64,
+        threshold_ms = threshold.as_millis() as u64,
+        "Slow query"
+    );
+    true
+}
+
+/// A migration bundled with the binary and whether the database has applied it
+#[derive(Debug, Clone, PartialEq)]
+pub struct MigrationState {
+    pub version: i64,
+    pub description: String,
+    pub applied: bool,
+}
+
+/// Report every bundled migration as applied or pending, without changing anything
+pub async fn migration_states(pool: &DbPool) -> Result<Vec<MigrationState>, sqlx::Error> {
+    // A fresh database has no migration table yet, so everything is pending
+    let has_table: bool = sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+        .fetch_one(pool)
+        .await?;
+
+    let applied: HashSet<i64> = if has_table {
+        sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success")
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .collect()
+    } else {
+        HashSet::new()
+    };
+
+    let migrator = sqlx::migrate!("./migrations");
+    let known = migrator
+        .iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+        .map(|migration| (migration.version, migration.description.to_string()));
+
+    Ok(compare_migrations(known, &applied))
+}
+
+/// Mark each known migration as applied when its version is in the applied set
+pub fn compare_migrations(
+    known: impl IntoIterator<Item = (i64, String)>,
+    applied: &HashSet<i64>,
+) -> Vec<MigrationState> {
+    known
+        .into_iter()
+        .map(|(version, description)| MigrationState {
+            applied: applied.contains(&version),
+            version,
+            description,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_single_pool_is_shared_for_reads_and_writes() {
+        let pool = PgPoolOptions::new()
+            .connect_lazy("postgres://postgres@127.0.0.1:1/primary")
+            .unwrap();
+        let pools = DbPools::single(pool);
+
+        assert_eq!(pools.read.connect_options().get_database(), Some("primary"));
+        assert_eq!(
+            pools.write.connect_options().get_database(),
+            Some("primary")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_with_transaction_does_not_run_operation_without_connection() {
+        let pool = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(200))
+            .connect_lazy("postgres://postgres@127.0.0.1:1/unreachable")
+            .unwrap();
+        let mut ran = false;
+
+        let result = with_transaction(&pool, |_tx, _| {
+            ran = true;
+            Box::pin(async { Ok(()) })
+        })
+        .await;
+
+        assert!(matches!(result, Err(ApiError::Database(_))));
+        assert!(!ran);
+    }
+
+    #[tokio::test]
+    async fn test_warm_up_gives_up_after_timeout() {
+        let pool = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_secs(30))
+            .connect_lazy("postgres://postgres@127.0.0.1:1/unreachable")
+            .unwrap();
+
+        let started = Instant::now();
+        let idle = warm_up(&pool, 3, Duration::from_millis(200)).await;
+
+        assert_eq!(idle, 0);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_warn_if_slow_uses_threshold() {
+        let threshold = Duration::from_millis(500);
+
+        assert!(warn_if_slow(
+            "products.list",
+            Duration::from_millis(750),
+            threshold
+        ));
+        assert!(warn_if_slow("products.list", threshold, threshold));
+        assert!(!warn_if_slow(
+            "products.list",
+            Duration::from_millis(20),
+            threshold
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_timed_returns_query_output() {
+        let output = timed("sleep", async {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            42
+        })
+        .await;
+
+        assert_eq!(output, 42);
+    }
+
+    #[test]
+    fn test_compare_migrations_marks_pending() {
+        let known = vec![
+            (20250314205320, "create products table".to_string()),
+            (20250314235157, "create users table".to_string()),
+            (20250321090000, "add version to products".to_string()),
+        ];
+        let applied = HashSet::from([20250314205320, 20250314235157]);
+
+        let states = compare_migrations(known, &applied);
+
+        assert_eq!(states.len(), 3);
+        assert!(states[0].applied && states[1].applied);
+        assert_eq!(
+            states[2],
+            MigrationState {
+                version: 20250321090000,
+                description: "add version to products".to_string(),
+                applied: false,
+            }
+        );
+    }
 }
@@ -1,11 +1,16 @@
 use axum::{
     Json,
-    http::StatusCode,
+    http::{StatusCode, header},
     response::{IntoResponse, Response},
 };
 use serde_json::json;
 use thiserror::Error;
 
+use crate::core::request_id::current_request_id;
+
+/// Seconds clients should wait before retrying after the pool was exhausted
+const POOL_TIMEOUT_RETRY_AFTER_SECS: u64 = 5;
+
 /// API error types
 #[derive(Error, Debug)]
 #[allow(dead_code)] // Suppress warnings about unused variants
@@ -30,11 +35,28 @@ pub enum ApiError {
 
     #[error("Conflict: {0}")]
     Conflict(String),
+
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(String),
+
+    #[error("Service unavailable: {0}")]
+    ServiceUnavailable(String),
 }
 
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
         let (status, error_message) = match self {
+            // Every connection was busy: transient and retryable, not a server bug
+            Self::Database(sqlx::Error::PoolTimedOut) => {
+                tracing::warn!("Database pool exhausted, returning 503");
+                let mut response =
+                    Self::ServiceUnavailable("Service temporarily unavailable".to_string())
+                        .into_response();
+                response
+                    .headers_mut()
+                    .insert(header::RETRY_AFTER, POOL_TIMEOUT_RETRY_AFTER_SECS.into());
+                return response;
+            }
             Self::Database(ref err) => {
                 tracing::error!("Database error: {:?}", err);
 
@@ -51,6 +73,9 @@ impl IntoResponse for ApiError {
                     {
                         return (StatusCode::CONFLICT, "Username already in use".to_string())
                             .into_response();
+                    } else if err_string.contains("idx_products_sku") {
+                        return (StatusCode::CONFLICT, "SKU already in use".to_string())
+                            .into_response();
                     }
                 }
 
@@ -64,6 +89,8 @@ impl IntoResponse for ApiError {
             Self::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
             Self::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
             Self::Conflict(msg) => (StatusCode::CONFLICT, msg),
+            Self::PayloadTooLarge(msg) => (StatusCode::PAYLOAD_TOO_LARGE, msg),
+            Self::ServiceUnavailable(msg) => (StatusCode::SERVICE_UNAVAILABLE, msg),
             Self::Internal(msg) => {
                 tracing::error!("Internal error: {}", msg);
                 (
@@ -73,10 +100,50 @@ impl IntoResponse for ApiError {
             }
         };
 
-        let body = Json(json!({
+        let mut body = json!({
             "error": error_message
-        }));
+        });
+        // Let clients quote the request ID when reporting a failure
+        if let Some(request_id) = current_request_id() {
+            body["request_id"] = json!(request_id);
+        }
+
+        (status, Json(body)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::postgres::PgPoolOptions;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_pool_timeout_returns_503_with_retry_after() {
+        // A single-connection pool that can never hand out a connection times out on acquire
+        let pool = PgPoolOptions::new()
+            .max_connections(1)
+            .acquire_timeout(Duration::from_millis(200))
+            .connect_lazy("postgres://postgres@127.0.0.1:1/unreachable")
+            .unwrap();
+        let err = pool.acquire().await.unwrap_err();
+        assert!(matches!(err, sqlx::Error::PoolTimedOut));
+
+        let response = ApiError::Database(err).into_response();
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "5");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"], "Service temporarily unavailable");
+    }
 
-        (status, body).into_response()
+    #[test]
+    fn test_other_database_errors_stay_500() {
+        let response = ApiError::Database(sqlx::Error::RowNotFound).into_response();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(response.headers().get(header::RETRY_AFTER).is_none());
     }
 }
@@ -0,0 +1,400 @@
+use axum::{
+    Json,
+    body::Bytes,
+    extract::{FromRequest, Request, rejection::JsonRejection},
+    http::{HeaderMap, StatusCode, header},
+};
+use serde::{
+    Deserializer,
+    de::{self, DeserializeOwned, Visitor},
+    forward_to_deserialize_any,
+};
+use serde_json::Value;
+
+use crate::core::{config::FeatureFlags, error::ApiError};
+
+/// JSON body extractor that reports bad payloads in the crate's error shape
+///
+/// Works like `axum::Json`, but deserialization failures are rejected with
+/// `ApiError::BadRequest` instead of axum's plain-text 422. With
+/// `STRICT_PAYLOADS=true`, fields the DTO doesn't declare (such as `prize`
+/// instead of `price`) are rejected too, naming the offending field; by
+/// default they are ignored. The flag is read from the `FeatureFlags`
+/// extension the router installs.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ApiJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for ApiJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let strict = req
+            .extensions()
+            .get::<FeatureFlags>()
+            .is_some_and(|flags| flags.strict_payloads);
+        parse_body(req, state, strict).await.map(Self)
+    }
+}
+
+/// Body extractor for endpoints legacy clients call with HTML-style forms
+///
+/// JSON bodies are handled exactly like `ApiJson`. With `ACCEPT_FORM_BODIES=true`,
+/// a `Content-Type: application/x-www-form-urlencoded` body is decoded into the
+/// same DTO instead, following the same `STRICT_PAYLOADS` rule for unknown
+/// fields. Forms are off by default because browsers send them cross-site
+/// without a CORS preflight.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ApiJsonOrForm<T>(pub T);
+
+impl<T, S> FromRequest<S> for ApiJsonOrForm<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let (strict, forms) = req
+            .extensions()
+            .get::<FeatureFlags>()
+            .map_or((false, false), |flags| {
+                (flags.strict_payloads, flags.accept_form_bodies)
+            });
+
+        if forms && is_form(req.headers()) {
+            parse_form(req, state, strict).await.map(Self)
+        } else {
+            parse_body(req, state, strict).await.map(Self)
+        }
+    }
+}
+
+fn is_form(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|mime| {
+            mime.trim()
+                .eq_ignore_ascii_case("application/x-www-form-urlencoded")
+        })
+}
+
+async fn parse_form<T, S>(req: Request, state: &S, strict: bool) -> Result<T, ApiError>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    let bytes = Bytes::from_request(req, state).await.map_err(|rejection| {
+        if rejection.status() == StatusCode::PAYLOAD_TOO_LARGE {
+            ApiError::PayloadTooLarge("Request body is too large".to_string())
+        } else {
+            ApiError::BadRequest(rejection.body_text())
+        }
+    })?;
+
+    if strict {
+        let pairs: Vec<(String, String)> = serde_urlencoded::from_bytes(&bytes)
+            .map_err(|e| ApiError::BadRequest(format!("Failed to parse form body: {}", e)))?;
+        reject_unknown_keys::<T>(pairs.iter().map(|(key, _)| key.as_str()))?;
+    }
+
+    serde_urlencoded::from_bytes(&bytes)
+        .map_err(|e| ApiError::BadRequest(format!("Failed to deserialize form body: {}", e)))
+}
+
+async fn parse_body<T, S>(req: Request, state: &S, strict: bool) -> Result<T, ApiError>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    if !strict {
+        return Json::<T>::from_request(req, state)
+            .await
+            .map(|Json(value)| value)
+            .map_err(rejection_error);
+    }
+
+    let Json(value) = Json::<Value>::from_request(req, state)
+        .await
+        .map_err(rejection_error)?;
+    reject_unknown_fields::<T>(&value)?;
+
+    // Go through axum again so type errors read the same as in lenient mode
+    let bytes = serde_json::to_vec(&value)
+        .map_err(|e| ApiError::Internal(format!("Error re-encoding JSON body: {}", e)))?;
+    Json::<T>::from_bytes(&bytes)
+        .map(|Json(value)| value)
+        .map_err(rejection_error)
+}
+
+fn rejection_error(rejection: JsonRejection) -> ApiError {
+    if rejection.status() == StatusCode::PAYLOAD_TOO_LARGE {
+        return ApiError::PayloadTooLarge("Request body is too large".to_string());
+    }
+    ApiError::BadRequest(rejection.body_text())
+}
+
+/// Reject top-level object keys that `T` doesn't declare
+///
+/// Only applies when `T` is a plain struct; other shapes are left to serde.
+fn reject_unknown_fields<T: DeserializeOwned>(value: &Value) -> Result<(), ApiError> {
+    match value {
+        Value::Object(object) => reject_unknown_keys::<T>(object.keys().map(String::as_str)),
+        _ => Ok(()),
+    }
+}
+
+// Shared by JSON objects and form pairs
+fn reject_unknown_keys<'a, T: DeserializeOwned>(
+    mut keys: impl Iterator<Item = &'a str>,
+) -> Result<(), ApiError> {
+    let Some(fields) = struct_fields::<T>() else {
+        return Ok(());
+    };
+
+    match keys.find(|key| !fields.contains(key)) {
+        Some(unknown) => Err(ApiError::BadRequest(format!(
+            "Unknown field `{}`, expected one of: {}",
+            unknown,
+            fields.join(", ")
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Field names serde derived for `T`, or `None` if `T` doesn't deserialize as a struct
+fn struct_fields<T: DeserializeOwned>() -> Option<&'static [&'static str]> {
+    let mut fields = None;
+    let _ = T::deserialize(FieldNames(&mut fields));
+    fields
+}
+
+/// Deserializer that only records the field list a derived struct asks for
+struct FieldNames<'a>(&'a mut Option<&'static [&'static str]>);
+
+impl<'de> Deserializer<'de> for FieldNames<'_> {
+    type Error = de::value::Error;
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        *self.0 = Some(fields);
+        Err(de::Error::custom("field names recorded"))
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::custom("not a struct"))
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map enum identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::{
+        product::model::{CreateProduct, UpdateProduct},
+        user::model::LoginUser,
+    };
+    use axum::body::Body;
+
+    fn request(body: &str) -> Request {
+        request_with_type("application/json", body)
+    }
+
+    fn request_with_type(content_type: &str, body: &str) -> Request {
+        Request::builder()
+            .method("POST")
+            .header(header::CONTENT_TYPE, content_type)
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    // A request as the server sees it, with the feature flags extension installed
+    fn flagged(mut request: Request, flags: FeatureFlags) -> Request {
+        request.extensions_mut().insert(flags);
+        request
+    }
+
+    fn forms_enabled() -> FeatureFlags {
+        FeatureFlags {
+            accept_form_bodies: true,
+            ..FeatureFlags::default()
+        }
+    }
+
+    async fn login_from(request: Request) -> Result<LoginUser, ApiError> {
+        ApiJsonOrForm::<LoginUser>::from_request(request, &())
+            .await
+            .map(|ApiJsonOrForm(login)| login)
+    }
+
+    const TYPO_PAYLOAD: &str = r#"{"name": "Laptop", "price": 100.0, "prize": 90.0}"#;
+
+    #[test]
+    fn test_struct_fields_lists_dto_fields() {
+        let fields = struct_fields::<CreateProduct>().unwrap();
+        assert!(fields.contains(&"price"));
+        assert!(!fields.contains(&"prize"));
+        assert_eq!(struct_fields::<Vec<String>>(), None);
+    }
+
+    #[tokio::test]
+    async fn test_lenient_mode_ignores_unknown_fields() {
+        let product: CreateProduct = parse_body(request(TYPO_PAYLOAD), &(), false).await.unwrap();
+        assert_eq!(product.price, 100.0);
+    }
+
+    #[tokio::test]
+    async fn test_extractor_reads_strict_flag_from_feature_flags() {
+        let lenient = ApiJson::<CreateProduct>::from_request(request(TYPO_PAYLOAD), &()).await;
+        assert!(lenient.is_ok());
+
+        let mut strict_request = request(TYPO_PAYLOAD);
+        strict_request.extensions_mut().insert(FeatureFlags {
+            strict_payloads: true,
+            ..FeatureFlags::default()
+        });
+        let strict = ApiJson::<CreateProduct>::from_request(strict_request, &()).await;
+        assert!(matches!(strict, Err(ApiError::BadRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn test_strict_mode_rejects_unknown_fields() {
+        let result = parse_body::<CreateProduct, _>(request(TYPO_PAYLOAD), &(), true).await;
+        assert!(matches!(
+            result,
+            Err(ApiError::BadRequest(message)) if message.starts_with("Unknown field `prize`")
+        ));
+
+        let product: CreateProduct =
+            parse_body(request(r#"{"name": "Laptop", "price": 100.0}"#), &(), true)
+                .await
+                .unwrap();
+        assert_eq!(product.name, "Laptop");
+    }
+
+    #[tokio::test]
+    async fn test_strict_mode_keeps_nullable_fields_and_type_errors() {
+        let update: UpdateProduct =
+            parse_body(request(r#"{"description": null, "version": 1}"#), &(), true)
+                .await
+                .unwrap();
+        assert_eq!(update.description, Some(None));
+
+        let result =
+            parse_body::<UpdateProduct, _>(request(r#"{"version": "one"}"#), &(), true).await;
+        assert!(matches!(result, Err(ApiError::BadRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn test_login_form_and_json_bodies_parse_the_same() {
+        let json = login_from(flagged(
+            request(r#"{"email": "a+b@example.com", "password": "p@ss word"}"#),
+            forms_enabled(),
+        ))
+        .await
+        .unwrap();
+        let form = login_from(flagged(
+            request_with_type(
+                "application/x-www-form-urlencoded; charset=UTF-8",
+                "email=a%2Bb%40example.com&password=p%40ss+word",
+            ),
+            forms_enabled(),
+        ))
+        .await
+        .unwrap();
+
+        assert_eq!(form.email, json.email);
+        assert_eq!(form.password, json.password);
+        assert_eq!(form.email, "a+b@example.com");
+        assert_eq!(form.password, "p@ss word");
+    }
+
+    #[tokio::test]
+    async fn test_login_form_and_json_reject_missing_field_alike() {
+        let json = login_from(flagged(
+            request(r#"{"email": "a@example.com"}"#),
+            forms_enabled(),
+        ))
+        .await;
+        let form = login_from(flagged(
+            request_with_type("application/x-www-form-urlencoded", "email=a%40example.com"),
+            forms_enabled(),
+        ))
+        .await;
+
+        assert!(
+            matches!(json, Err(ApiError::BadRequest(message)) if message.contains("missing field `password`"))
+        );
+        assert!(
+            matches!(form, Err(ApiError::BadRequest(message)) if message.contains("missing field `password`"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_form_bodies_are_rejected_unless_enabled() {
+        let body = "email=a%40example.com&password=secret";
+        let form = || request_with_type("application/x-www-form-urlencoded", body);
+
+        assert!(matches!(
+            login_from(form()).await,
+            Err(ApiError::BadRequest(_))
+        ));
+        assert!(matches!(
+            login_from(flagged(form(), FeatureFlags::default())).await,
+            Err(ApiError::BadRequest(_))
+        ));
+        assert!(login_from(flagged(form(), forms_enabled())).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_form_bodies_parse_numbers_and_follow_strict_mode() {
+        let product = ApiJsonOrForm::<CreateProduct>::from_request(
+            flagged(
+                request_with_type(
+                    "application/x-www-form-urlencoded",
+                    "name=Laptop&price=100.5&stock=3&prize=90",
+                ),
+                forms_enabled(),
+            ),
+            &(),
+        )
+        .await
+        .unwrap()
+        .0;
+        assert_eq!(product.price, 100.5);
+        assert_eq!(product.stock, Some(3));
+
+        let strict = ApiJsonOrForm::<CreateProduct>::from_request(
+            flagged(
+                request_with_type(
+                    "application/x-www-form-urlencoded",
+                    "name=Laptop&price=1&prize=90",
+                ),
+                FeatureFlags {
+                    strict_payloads: true,
+                    ..forms_enabled()
+                },
+            ),
+            &(),
+        )
+        .await;
+        assert!(matches!(
+            strict,
+            Err(ApiError::BadRequest(message)) if message.starts_with("Unknown field `prize`")
+        ));
+    }
+}
@@ -0,0 +1,91 @@
+use crate::core::{read_only::READ_ONLY_TOGGLE_PATH, write_switch::WriteSwitchSpec};
+
+/// Admin route that turns maintenance mode on or off
+pub const MAINTENANCE_TOGGLE_PATH: &str = "/api/admin/maintenance";
+
+/// Maintenance mode: writes are rejected during deploys and database work
+///
+/// Login stays open so an admin can sign in and turn it off again.
+pub static MAINTENANCE_MODE: WriteSwitchSpec = WriteSwitchSpec {
+    name: "Maintenance mode",
+    toggle_path: MAINTENANCE_TOGGLE_PATH,
+    state_key: "maintenance",
+    message: "Service is under maintenance, please try again later",
+    exempt_paths: &[
+        "/api/auth/login",
+        MAINTENANCE_TOGGLE_PATH,
+        READ_ONLY_TOGGLE_PATH,
+    ],
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::write_switch::{WriteSwitch, write_switch_middleware};
+    use axum::{
+        Router,
+        body::Body,
+        extract::Request,
+        http::{Method, StatusCode},
+        middleware,
+        routing::{get, post},
+    };
+    use tower::ServiceExt;
+
+    fn app(mode: WriteSwitch) -> Router {
+        Router::new()
+            .route(
+                "/api/products",
+                get(|| async { "list" }).post(|| async { "created" }),
+            )
+            .route("/api/auth/login", post(|| async { "token" }))
+            .route("/health", get(|| async { "ok" }))
+            .layer(middleware::from_fn_with_state(
+                mode,
+                write_switch_middleware,
+            ))
+    }
+
+    async fn status(app: Router, method: Method, uri: &str) -> StatusCode {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap();
+        app.oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_maintenance_blocks_writes_but_allows_reads() {
+        let mode = WriteSwitch::new(&MAINTENANCE_MODE, true);
+
+        assert_eq!(
+            status(app(mode.clone()), Method::POST, "/api/products").await,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        assert_eq!(
+            status(app(mode.clone()), Method::GET, "/api/products").await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(app(mode.clone()), Method::GET, "/health").await,
+            StatusCode::OK
+        );
+
+        mode.set(false);
+        assert_eq!(
+            status(app(mode), Method::POST, "/api/products").await,
+            StatusCode::OK
+        );
+    }
+
+    #[tokio::test]
+    async fn test_maintenance_keeps_login_open() {
+        let mode = WriteSwitch::new(&MAINTENANCE_MODE, true);
+
+        assert_eq!(
+            status(app(mode), Method::POST, "/api/auth/login").await,
+            StatusCode::OK
+        );
+    }
+}
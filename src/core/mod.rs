@@ -1,5 +1,17 @@
 pub mod authentication;
+pub mod body_limit; // Export request body limit module
+pub mod client_ip; // Export client IP resolution module
 pub mod config; // Export configuration module
 pub mod db; // Export database module
 pub mod error; // Export error handling module
+pub mod health; // Export health check module
+pub mod json; // Export JSON body extractor
+pub mod maintenance; // Export maintenance mode module
+pub mod pagination; // Export shared pagination types
+pub mod query; // Export query string extractor
+pub mod read_only; // Export read-only mode module
+pub mod request_id; // Export request ID module
+pub mod retention; // Export history retention purge
 pub mod server; // Export server module
+pub mod startup; // Export startup self-check
+pub mod write_switch; // Export shared maintenance/read-only switch
@@ -0,0 +1,217 @@
+use axum::{extract::FromRequestParts, http::request::Parts};
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::core::{error::ApiError, query::ApiQuery};
+
+/// Page size used when the client does not send `limit`
+pub const DEFAULT_PAGE_LIMIT: i64 = 50;
+
+/// Largest page size a client may request
+pub const MAX_PAGE_LIMIT: i64 = 500;
+
+/// Raw `limit`/`offset` query parameters before clamping
+#[derive(Debug, Deserialize)]
+struct RawPageParams {
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+/// Validated `limit`/`offset` pagination parameters
+///
+/// Extract it in a handler alongside other query extractors; missing values
+/// fall back to the defaults and out-of-range values are clamped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageParams {
+    pub limit: i64,
+    pub offset: i64,
+}
+
+impl PageParams {
+    /// Apply the default and clamp to `1..=MAX_PAGE_LIMIT` (offset is never negative)
+    pub fn new(limit: Option<i64>, offset: Option<i64>) -> Self {
+        Self {
+            limit: limit.unwrap_or(DEFAULT_PAGE_LIMIT).clamp(1, MAX_PAGE_LIMIT),
+            offset: offset.unwrap_or(0).max(0),
+        }
+    }
+}
+
+impl<S> FromRequestParts<S> for PageParams
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let ApiQuery(raw) = ApiQuery::<RawPageParams>::from_request_parts(parts, state).await?;
+
+        Ok(Self::new(raw.limit, raw.offset))
+    }
+}
+
+/// A page of results together with the pagination that produced it
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>, // Items on this page
+    pub limit: i64,    // Page size that was applied
+    pub offset: i64,   // Number of items skipped
+}
+
+impl<T> Page<T> {
+    /// Wrap items fetched with the given pagination
+    pub fn new(items: Vec<T>, params: PageParams) -> Self {
+        Self {
+            items,
+            limit: params.limit,
+            offset: params.offset,
+        }
+    }
+}
+
+/// Column and direction used to order a listing, e.g. `created_at:desc`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortOrder {
+    pub column: &'static str,
+    pub descending: bool,
+}
+
+/// Shown as `column:asc` or `column:desc`, the same form it is configured in
+impl Serialize for SortOrder {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let direction = if self.descending { "desc" } else { "asc" };
+        serializer.collect_str(&format_args!("{}:{}", self.column, direction))
+    }
+}
+
+impl SortOrder {
+    /// Ascending order on `column`
+    pub const fn asc(column: &'static str) -> Self {
+        Self {
+            column,
+            descending: false,
+        }
+    }
+
+    /// Parse `column[:asc|desc]`, accepting only columns from `allowed`
+    pub fn parse(value: &str, allowed: &[&'static str]) -> Result<Self, String> {
+        let (column, direction) = value
+            .trim()
+            .split_once(':')
+            .unwrap_or((value.trim(), "asc"));
+
+        let column = allowed
+            .iter()
+            .find(|allowed| allowed.eq_ignore_ascii_case(column.trim()))
+            .ok_or_else(|| format!("unknown sort column '{}'", column.trim()))?;
+
+        let descending = match direction.trim().to_ascii_lowercase().as_str() {
+            "asc" => false,
+            "desc" => true,
+            other => return Err(format!("unknown sort direction '{}'", other)),
+        };
+
+        Ok(Self { column, descending })
+    }
+
+    /// Parse the value of the `name` setting, falling back to `default` when unset or invalid
+    pub(crate) fn from_setting(
+        name: &str,
+        value: Option<String>,
+        allowed: &[&'static str],
+        default: Self,
+    ) -> Self {
+        match value {
+            Some(value) if !value.trim().is_empty() => {
+                Self::parse(&value, allowed).unwrap_or_else(|err| {
+                    tracing::warn!("Invalid {} '{}' ({}), using default", name, value, err);
+                    default
+                })
+            }
+            _ => default,
+        }
+    }
+
+    /// `ORDER BY` clause body, with `id` as a tiebreaker for stable pages
+    pub fn to_sql(self) -> String {
+        let direction = if self.descending { "DESC" } else { "ASC" };
+        format!("{} {}, id ASC", self.column, direction)
+    }
+
+    /// Like `to_sql`, but rows where the column is NULL come last in both directions
+    pub fn to_sql_nulls_last(self) -> String {
+        let direction = if self.descending { "DESC" } else { "ASC" };
+        format!("{} {} NULLS LAST, id ASC", self.column, direction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Request;
+
+    async fn extract(uri: &str) -> Result<PageParams, ApiError> {
+        let (mut parts, _) = Request::builder().uri(uri).body(()).unwrap().into_parts();
+        PageParams::from_request_parts(&mut parts, &()).await
+    }
+
+    #[tokio::test]
+    async fn test_page_params_defaults() {
+        let params = extract("/items").await.unwrap();
+        assert_eq!(
+            params,
+            PageParams {
+                limit: DEFAULT_PAGE_LIMIT,
+                offset: 0
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_page_params_clamping() {
+        let params = extract("/items?limit=100000&offset=-5").await.unwrap();
+        assert_eq!(params.limit, MAX_PAGE_LIMIT);
+        assert_eq!(params.offset, 0);
+
+        let params = extract("/items?limit=0&offset=20").await.unwrap();
+        assert_eq!(params.limit, 1);
+        assert_eq!(params.offset, 20);
+    }
+
+    #[tokio::test]
+    async fn test_page_params_rejects_non_numeric_values() {
+        assert!(matches!(
+            extract("/items?limit=ten").await,
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+
+    const COLUMNS: [&str; 3] = ["name", "price", "created_at"];
+
+    #[test]
+    fn test_sort_order_parses_column_and_direction() {
+        let sort = SortOrder::parse("created_at:desc", &COLUMNS).unwrap();
+        assert_eq!(
+            sort,
+            SortOrder {
+                column: "created_at",
+                descending: true
+            }
+        );
+        assert_eq!(sort.to_sql(), "created_at DESC, id ASC");
+
+        assert_eq!(
+            SortOrder::parse("price", &COLUMNS).unwrap(),
+            SortOrder::asc("price")
+        );
+    }
+
+    #[test]
+    fn test_sort_order_rejects_invalid_column_and_direction() {
+        assert_eq!(
+            SortOrder::parse("password_hash:asc", &COLUMNS),
+            Err("unknown sort column 'password_hash'".to_string())
+        );
+        assert!(SortOrder::parse("name:sideways", &COLUMNS).is_err());
+        assert!(SortOrder::parse("name; DROP TABLE products", &COLUMNS).is_err());
+    }
+}
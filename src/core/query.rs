@@ -0,0 +1,108 @@
+use axum::{
+    extract::{FromRequestParts, Query},
+    http::request::Parts,
+};
+use serde::de::DeserializeOwned;
+
+use crate::core::error::ApiError;
+
+/// Query string extractor that reports bad parameters in the crate's error shape
+///
+/// Works like `axum::extract::Query`, but a value that fails to deserialize
+/// (such as `?min_price=abc`) is rejected with `ApiError::BadRequest` naming
+/// the offending parameter instead of axum's plain-text rejection.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ApiQuery<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for ApiQuery<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        Query::<T>::from_request_parts(parts, state)
+            .await
+            .map(|Query(value)| Self(value))
+            .map_err(|err| ApiError::BadRequest(err.body_text()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::product::model::ProductFilter;
+    use axum::{
+        Router,
+        body::Body,
+        http::{Request, StatusCode},
+        routing::get,
+    };
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        Router::new().route(
+            "/products",
+            get(|ApiQuery(filter): ApiQuery<ProductFilter>| async move {
+                format!("{:?}", filter.min_price)
+            }),
+        )
+    }
+
+    async fn error_for(uri: &str) -> serde_json::Value {
+        let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        let response = app().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_non_numeric_min_price_names_the_parameter() {
+        let body = error_for("/products?min_price=abc").await;
+
+        let message = body["error"].as_str().unwrap();
+        assert!(message.contains("min_price"), "{}", message);
+    }
+
+    #[tokio::test]
+    async fn test_min_price_accepts_comma_decimal_separator() {
+        for uri in ["/products?min_price=19,99", "/products?min_price=19.99"] {
+            let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+            let response = app().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            assert_eq!(&body[..], b"Some(19.99)");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_non_boolean_is_active_names_the_parameter() {
+        let body = error_for("/products?is_active=maybe").await;
+
+        let message = body["error"].as_str().unwrap();
+        assert!(message.contains("is_active"), "{}", message);
+    }
+
+    #[tokio::test]
+    async fn test_valid_query_is_extracted() {
+        let request = Request::builder()
+            .uri("/products?min_price=9.5&is_active=true")
+            .body(Body::empty())
+            .unwrap();
+        let response = app().oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"Some(9.5)");
+    }
+}
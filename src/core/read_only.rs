@@ -0,0 +1,107 @@
+use crate::core::{maintenance::MAINTENANCE_TOGGLE_PATH, write_switch::WriteSwitchSpec};
+
+/// Admin route that turns read-only mode on or off
+pub const READ_ONLY_TOGGLE_PATH: &str = "/api/admin/read-only";
+
+/// Read-only mode: the data can be read but not changed right now
+///
+/// Unlike maintenance mode, login is rejected too because it writes to the
+/// database (`last_login_at`, failed-login counters and `auth_events`); existing
+/// tokens keep working and signing out only clears the cookie.
+pub static READ_ONLY_MODE: WriteSwitchSpec = WriteSwitchSpec {
+    name: "Read-only mode",
+    toggle_path: READ_ONLY_TOGGLE_PATH,
+    state_key: "read_only",
+    message: "Service is in read-only mode, changes are temporarily disabled",
+    exempt_paths: &[
+        "/api/auth/logout",
+        READ_ONLY_TOGGLE_PATH,
+        MAINTENANCE_TOGGLE_PATH,
+    ],
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::write_switch::{WriteSwitch, write_switch_middleware};
+    use axum::{
+        Router,
+        body::Body,
+        extract::Request,
+        http::{Method, StatusCode},
+        middleware,
+        routing::{get, post},
+    };
+    use tower::ServiceExt;
+
+    fn app(mode: WriteSwitch) -> Router {
+        Router::new()
+            .route(
+                "/api/products",
+                get(|| async { "list" }).post(|| async { "created" }),
+            )
+            .route("/api/auth/login", post(|| async { "token" }))
+            .route("/api/auth/logout", post(|| async { "logged out" }))
+            .route("/health", get(|| async { "ok" }))
+            .layer(middleware::from_fn_with_state(
+                mode,
+                write_switch_middleware,
+            ))
+    }
+
+    async fn send(app: Router, method: Method, uri: &str) -> (StatusCode, String) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_read_only_blocks_writes() {
+        let mode = WriteSwitch::new(&READ_ONLY_MODE, true);
+
+        let (status, body) = send(app(mode.clone()), Method::POST, "/api/products").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(body.contains("read-only mode"));
+
+        for method in [Method::PUT, Method::PATCH, Method::DELETE] {
+            let (status, _) = send(app(mode.clone()), method, "/api/products").await;
+            assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        }
+
+        // Login records the attempt, so it is a write as well
+        let (status, _) = send(app(mode.clone()), Method::POST, "/api/auth/login").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+
+        // Signing out only clears the cookie
+        let (status, _) = send(app(mode), Method::POST, "/api/auth/logout").await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_read_only_allows_reads() {
+        let mode = WriteSwitch::new(&READ_ONLY_MODE, true);
+
+        assert_eq!(
+            send(app(mode.clone()), Method::GET, "/api/products").await,
+            (StatusCode::OK, "list".to_string())
+        );
+        assert_eq!(
+            send(app(mode.clone()), Method::GET, "/health").await.0,
+            StatusCode::OK
+        );
+
+        mode.set(false);
+        assert_eq!(
+            send(app(mode), Method::POST, "/api/products").await.0,
+            StatusCode::OK
+        );
+    }
+}
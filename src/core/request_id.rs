@@ -0,0 +1,138 @@
+use axum::{
+    extract::Request,
+    http::{HeaderMap, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use tracing::Instrument;
+use uuid::Uuid;
+
+/// Header used to receive and echo the request ID
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest incoming request ID that is accepted as-is
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+tokio::task_local! {
+    static CURRENT_REQUEST_ID: String;
+}
+
+/// ID of the request being handled
+///
+/// Inserted into request extensions by `request_id_middleware`; handlers can
+/// read it with `Extension<RequestId>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+/// Middleware that assigns every request an ID
+///
+/// Honours a well-formed incoming `X-Request-Id`, otherwise generates a UUID.
+/// The ID is recorded on a tracing span, echoed in the response header and
+/// exposed to `ApiError` through `current_request_id`.
+pub async fn request_id_middleware(mut req: Request, next: Next) -> Response {
+    let request_id =
+        incoming_request_id(req.headers()).unwrap_or_else(|| Uuid::new_v4().to_string());
+    req.extensions_mut().insert(RequestId(request_id.clone()));
+
+    let span = tracing::info_span!("request", request_id = %request_id);
+    let mut response = CURRENT_REQUEST_ID
+        .scope(request_id.clone(), next.run(req))
+        .instrument(span)
+        .await;
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+
+    response
+}
+
+/// Request ID of the request currently being handled, if any
+pub fn current_request_id() -> Option<String> {
+    CURRENT_REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// Read the client-supplied request ID, rejecting empty, oversized or non-printable values
+fn incoming_request_id(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|value| !value.is_empty() && value.len() <= MAX_REQUEST_ID_LENGTH)
+        .filter(|value| value.chars().all(|c| c.is_ascii_graphic()))
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::error::ApiError;
+    use axum::{Router, body::Body, middleware, routing::get};
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        Router::new()
+            .route(
+                "/fail",
+                get(|| async { ApiError::NotFound("Product not found".to_string()) }),
+            )
+            .layer(middleware::from_fn(request_id_middleware))
+    }
+
+    async fn json_body(response: Response) -> serde_json::Value {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_error_body_and_header_share_generated_request_id() {
+        let request = Request::builder().uri("/fail").body(Body::empty()).unwrap();
+        let response = app().oneshot(request).await.unwrap();
+
+        let header = response.headers()[REQUEST_ID_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert!(Uuid::parse_str(&header).is_ok());
+
+        let body = json_body(response).await;
+        assert_eq!(body["error"], "Product not found");
+        assert_eq!(body["request_id"], header);
+    }
+
+    #[tokio::test]
+    async fn test_incoming_request_id_is_honoured() {
+        let request = Request::builder()
+            .uri("/fail")
+            .header(REQUEST_ID_HEADER, "client-abc-123")
+            .body(Body::empty())
+            .unwrap();
+        let response = app().oneshot(request).await.unwrap();
+
+        assert_eq!(response.headers()[REQUEST_ID_HEADER], "client-abc-123");
+        assert_eq!(json_body(response).await["request_id"], "client-abc-123");
+    }
+
+    #[test]
+    fn test_incoming_request_id_rejects_invalid_values() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(incoming_request_id(&headers), None);
+
+        headers.insert(REQUEST_ID_HEADER, HeaderValue::from_static("   "));
+        assert_eq!(incoming_request_id(&headers), None);
+
+        headers.insert(REQUEST_ID_HEADER, HeaderValue::from_static("has space"));
+        assert_eq!(incoming_request_id(&headers), None);
+
+        let too_long = "a".repeat(MAX_REQUEST_ID_LENGTH + 1);
+        headers.insert(REQUEST_ID_HEADER, HeaderValue::from_str(&too_long).unwrap());
+        assert_eq!(incoming_request_id(&headers), None);
+    }
+
+    #[test]
+    fn test_current_request_id_is_none_outside_a_request() {
+        assert_eq!(current_request_id(), None);
+    }
+}
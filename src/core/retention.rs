@@ -0,0 +1,242 @@
+use std::time::Duration;
+use tokio::{sync::watch, task::JoinHandle, time::MissedTickBehavior};
+use tracing::Instrument;
+
+use crate::core::db::DbPool;
+
+/// Default time between purge runs when `RETENTION_PURGE_INTERVAL_SECS` is not set
+const DEFAULT_PURGE_INTERVAL_SECS: u64 = 60 * 60;
+
+/// History tables whose old rows are purged
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetainedTable {
+    ProductAudit,
+    Outbox,
+    AuthEvents,
+}
+
+impl RetainedTable {
+    pub const ALL: [Self; 3] = [Self::ProductAudit, Self::Outbox, Self::AuthEvents];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::ProductAudit => "product_audit",
+            Self::Outbox => "outbox",
+            Self::AuthEvents => "auth_events",
+        }
+    }
+
+    /// Env var overriding `HISTORY_RETENTION_DAYS` for this table
+    fn retention_var(self) -> &'static str {
+        match self {
+            Self::ProductAudit => "PRODUCT_AUDIT_RETENTION_DAYS",
+            Self::Outbox => "OUTBOX_RETENTION_DAYS",
+            Self::AuthEvents => "AUTH_EVENTS_RETENTION_DAYS",
+        }
+    }
+
+    /// Delete rows older than `$1` days
+    ///
+    /// Outbox events are only purged once processed, so a stalled consumer never loses events.
+    fn purge_sql(self) -> &'static str {
+        match self {
+            Self::ProductAudit => {
+                "DELETE FROM product_audit WHERE changed_at < NOW() - make_interval(days => $1)"
+            }
+            Self::Outbox => {
+                "DELETE FROM outbox WHERE processed_at IS NOT NULL AND processed_at < NOW() - make_interval(days => $1)"
+            }
+            Self::AuthEvents => {
+                "DELETE FROM auth_events WHERE created_at < NOW() - make_interval(days => $1)"
+            }
+        }
+    }
+}
+
+/// How long each history table keeps its rows and how often they are purged
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    pub interval: Duration,
+    pub tables: Vec<(RetainedTable, i32)>, // Tables to purge with their retention in days
+}
+
+impl RetentionPolicy {
+    /// Build the policy from a variable lookup, as part of `Config`
+    ///
+    /// `HISTORY_RETENTION_DAYS` applies to every table; `PRODUCT_AUDIT_RETENTION_DAYS`,
+    /// `OUTBOX_RETENTION_DAYS` and `AUTH_EVENTS_RETENTION_DAYS` override it per table.
+    /// Unset, invalid or `0` keeps a table's rows forever.
+    pub(crate) fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let days = |name: &str| lookup(name).and_then(|value| value.trim().parse::<i32>().ok());
+        let default_days = days("HISTORY_RETENTION_DAYS");
+
+        let tables = RetainedTable::ALL
+            .into_iter()
+            .filter_map(|table| {
+                let days = days(table.retention_var()).or(default_days)?;
+                (days > 0).then_some((table, days))
+            })
+            .collect();
+
+        let interval_secs = lookup("RETENTION_PURGE_INTERVAL_SECS")
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .filter(|secs| *secs > 0)
+            .unwrap_or(DEFAULT_PURGE_INTERVAL_SECS);
+
+        Self {
+            interval: Duration::from_secs(interval_secs),
+            tables,
+        }
+    }
+
+    /// Whether any table has a retention period
+    pub fn is_enabled(&self) -> bool {
+        !self.tables.is_empty()
+    }
+}
+
+/// Delete expired rows from every table in the policy, returning the deleted counts
+pub async fn purge_expired(
+    pool: &DbPool,
+    policy: &RetentionPolicy,
+) -> Result<Vec<(RetainedTable, u64)>, sqlx::Error> {
+    let mut deleted = Vec::with_capacity(policy.tables.len());
+
+    for (table, days) in &policy.tables {
+        let result = sqlx::query(table.purge_sql())
+            .bind(days)
+            .execute(pool)
+            .await?;
+        deleted.push((*table, result.rows_affected()));
+    }
+
+    Ok(deleted)
+}
+
+/// Run `purge_expired` every `policy.interval` until `shutdown` turns true
+///
+/// Returns `None` when no table has a retention period. A failed run is
+/// logged and retried on the next tick.
+pub fn spawn_purge_task(
+    pool: DbPool,
+    policy: RetentionPolicy,
+    mut shutdown: watch::Receiver<bool>,
+) -> Option<JoinHandle<()>> {
+    if !policy.is_enabled() {
+        return None;
+    }
+
+    tracing::info!(
+        "History purge enabled every {}s for {}",
+        policy.interval.as_secs(),
+        policy
+            .tables
+            .iter()
+            .map(|(table, days)| format!("{} ({} days)", table.name(), days))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+
+    // Keep the `app` span (and its `env` field) on the task's log lines
+    Some(tokio::spawn(
+        async move {
+            let mut ticker = tokio::time::interval(policy.interval);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+            loop {
+                tokio::select! {
+                    biased;
+                    _ = shutdown.wait_for(|stop| *stop) => break,
+                    _ = ticker.tick() => {}
+                }
+
+                match purge_expired(&pool, &policy).await {
+                    Ok(deleted) => {
+                        for (table, count) in deleted {
+                            tracing::info!("Purged {} expired rows from {}", count, table.name());
+                        }
+                    }
+                    Err(e) => tracing::error!("History purge failed: {}", e),
+                }
+            }
+
+            tracing::info!("History purge stopped");
+        }
+        .instrument(tracing::Span::current()),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn policy_from(vars: &[(&str, &str)]) -> RetentionPolicy {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        RetentionPolicy::from_lookup(|name| vars.get(name).cloned())
+    }
+
+    #[test]
+    fn test_policy_disabled_by_default() {
+        let policy = policy_from(&[]);
+        assert!(!policy.is_enabled());
+        assert_eq!(
+            policy.interval,
+            Duration::from_secs(DEFAULT_PURGE_INTERVAL_SECS)
+        );
+    }
+
+    #[test]
+    fn test_policy_per_table_overrides_default() {
+        let policy = policy_from(&[
+            ("HISTORY_RETENTION_DAYS", "90"),
+            ("OUTBOX_RETENTION_DAYS", "7"),
+            ("AUTH_EVENTS_RETENTION_DAYS", "0"),
+            ("RETENTION_PURGE_INTERVAL_SECS", "600"),
+        ]);
+
+        assert_eq!(
+            policy.tables,
+            vec![
+                (RetainedTable::ProductAudit, 90),
+                (RetainedTable::Outbox, 7)
+            ]
+        );
+        assert_eq!(policy.interval, Duration::from_secs(600));
+    }
+
+    #[test]
+    fn test_purge_sql_only_targets_rows_past_the_cutoff() {
+        for table in RetainedTable::ALL {
+            let sql = table.purge_sql();
+            assert!(sql.starts_with(&format!("DELETE FROM {} WHERE", table.name())));
+            assert!(sql.contains("< NOW() - make_interval(days => $1)"));
+        }
+        assert!(
+            RetainedTable::Outbox
+                .purge_sql()
+                .contains("processed_at IS NOT NULL")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_purge_task_stops_on_shutdown() {
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .connect_lazy("postgres://postgres@127.0.0.1:1/unreachable")
+            .unwrap();
+        let (shutdown_tx, shutdown_rx) = watch::channel(true);
+
+        assert!(spawn_purge_task(pool.clone(), policy_from(&[]), shutdown_rx.clone()).is_none());
+
+        let policy = policy_from(&[("HISTORY_RETENTION_DAYS", "30")]);
+        let task = spawn_purge_task(pool, policy, shutdown_rx).unwrap();
+        tokio::time::timeout(Duration::from_secs(5), task)
+            .await
+            .expect("purge task should stop")
+            .unwrap();
+        drop(shutdown_tx);
+    }
+}
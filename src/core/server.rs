@@ -1,23 +1,66 @@
 use anyhow::Result;
 use axum::{
-    Router,
-    http::{HeaderName, HeaderValue, Method},
-    middleware,
+    Extension, Json, Router,
+    extract::Request,
+    http::{HeaderName, HeaderValue, Method, StatusCode, Uri},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
 };
-use sqlx::PgPool;
-use std::{env, str::FromStr};
-use tower_http::{cors::CorsLayer, trace::TraceLayer};
+use serde_json::json;
+use std::{
+    net::SocketAddr,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+use tokio::sync::watch;
+use tower_http::{compression::CompressionLayer, cors::CorsLayer, trace::TraceLayer};
+use tracing::{Instrument, Span};
 
 use crate::{
-    core::{authentication::auth_middleware, config::Config},
-    modules::{product::routes::product_routes, user::routes::user_routes},
+    core::{
+        authentication::auth_middleware,
+        body_limit::{body_limit, payload_too_large_middleware},
+        client_ip::client_ip_middleware,
+        config::{Config, config_routes},
+        db::{DbPools, set_slow_query_threshold},
+        health::health_routes,
+        maintenance::MAINTENANCE_MODE,
+        read_only::READ_ONLY_MODE,
+        request_id::{REQUEST_ID_HEADER, current_request_id, request_id_middleware},
+        retention::spawn_purge_task,
+        write_switch::{WriteSwitch, write_switch_middleware},
+    },
+    modules::{
+        auth_event::routes::auth_event_routes,
+        order::routes::order_routes,
+        outbox::routes::outbox_routes,
+        product::{cache::ProductCache, routes::product_routes},
+        user::routes::user_routes,
+    },
+    utils::{number::set_number_locale, set_default_currency},
 };
 
-const ALLOWED_HEADERS: [&str; 2] = ["content-type", "authorization"];
+const ALLOWED_HEADERS: [&str; 3] = ["content-type", "authorization", REQUEST_ID_HEADER];
+
+/// How long open connections (such as SSE streams) may keep running after a shutdown signal
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(10);
+
+/// Start the HTTP server and background tasks, stopping them on Ctrl+C or SIGTERM
+pub async fn run_server(config: Config, pools: DbPools) -> Result<()> {
+    // Settings read by code without access to the config (query timing, query string
+    // parsing, formatted prices)
+    set_slow_query_threshold(Duration::from_millis(config.features.slow_query_ms));
+    set_number_locale(config.features.number_locale);
+    set_default_currency(config.features.default_currency.clone());
+
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let purge_task = spawn_purge_task(
+        pools.write.clone(),
+        config.retention.clone(),
+        shutdown_rx.clone(),
+    );
 
-/// Start the HTTP server
-pub async fn run_server(config: Config, pool: PgPool) -> Result<()> {
-    let app = create_router(pool);
+    let app = create_router(&config, pools);
 
     tracing::info!(
         "Starting server on {}:{}",
@@ -26,33 +69,189 @@ pub async fn run_server(config: Config, pool: PgPool) -> Result<()> {
     );
 
     let listener = tokio::net::TcpListener::bind(config.server_addr).await?;
-    axum::serve(listener, app).await?;
+    let server = axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(async move {
+        shutdown_signal().await;
+        tracing::info!("Shutdown signal received, draining connections");
+        let _ = shutdown_tx.send(true);
+    });
 
+    // Long-lived connections would otherwise hold shutdown open forever
+    let mut shutdown = shutdown_rx;
+    tokio::select! {
+        result = server => result?,
+        _ = async {
+            let _ = shutdown.wait_for(|stop| *stop).await;
+            tokio::time::sleep(SHUTDOWN_GRACE).await;
+        } => tracing::warn!("Connections still open after {}s, shutting down", SHUTDOWN_GRACE.as_secs()),
+    }
+
+    if let Some(purge_task) = purge_task {
+        purge_task.await?;
+    }
+
+    tracing::info!("Server stopped");
     Ok(())
 }
 
+/// Resolve on Ctrl+C or, on Unix, SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
 /// Create API router with all routes
-fn create_router(pool: PgPool) -> Router {
-    let cors = create_cors_layer();
+fn create_router(config: &Config, pools: DbPools) -> Router {
+    let flags = &config.features;
+    let cors = build_cors_layer(
+        &flags.allowed_origins,
+        Duration::from_secs(flags.cors_max_age_secs),
+    );
+    let maintenance = WriteSwitch::new(&MAINTENANCE_MODE, flags.maintenance_mode_on_start);
+    let read_only = WriteSwitch::new(&READ_ONLY_MODE, flags.read_only_on_start);
 
-    Router::new()
-        .merge(product_routes(pool.clone()))
-        .merge(user_routes(pool))
+    let product_cache = ProductCache::from_flags(flags);
+
+    let router = Router::new()
+        .merge(product_routes(pools.clone(), product_cache.clone(), flags))
+        .merge(user_routes(pools.clone(), flags))
+        .merge(order_routes(pools.clone(), product_cache))
+        .merge(outbox_routes(pools.write.clone()))
+        .merge(auth_event_routes(pools.write.clone()))
+        .merge(health_routes(pools.write, &config.app_env))
+        .merge(maintenance.routes())
+        .merge(read_only.routes())
+        .merge(config_routes(config))
+        .fallback(route_not_found)
+        .method_not_allowed_fallback(method_not_allowed)
+        .layer(body_limit(flags.max_body_bytes))
+        .layer(middleware::from_fn(payload_too_large_middleware))
         .layer(middleware::from_fn(auth_middleware))
-        .layer(TraceLayer::new_for_http())
-        .layer(cors)
+        .layer(middleware::from_fn_with_state(
+            read_only,
+            write_switch_middleware,
+        ))
+        .layer(middleware::from_fn_with_state(
+            maintenance,
+            write_switch_middleware,
+        ))
+        .layer(middleware::from_fn_with_state(
+            flags.trust_proxy,
+            client_ip_middleware,
+        ))
+        .layer(Extension(flags.clone()))
+        .layer(middleware::from_fn(log_requests))
+        .layer(middleware::from_fn(request_id_middleware));
+
+    with_root_span(
+        with_compression(router, flags.compression)
+            .layer(TraceLayer::new_for_http())
+            .layer(cors),
+        Span::current(),
+    )
 }
 
-/// Create CORS layer with configured restrictions
-fn create_cors_layer() -> CorsLayer {
-    let allowed_origins_str = env::var("ALLOWED_ORIGINS").unwrap_or_else(|_| "".to_string());
+/// Run every request inside `span`, so request logs inherit its fields
+///
+/// Connections are served on their own tasks, which do not inherit the span
+/// that was current at startup (the `app` span carrying `APP_ENV`).
+fn with_root_span(router: Router, span: Span) -> Router {
+    router.layer(middleware::from_fn(move |req: Request, next: Next| {
+        next.run(req).instrument(span.clone())
+    }))
+}
 
-    let origins: Vec<&str> = allowed_origins_str
-        .split(',')
-        .map(|s| s.trim())
-        .filter(|s| !s.is_empty())
-        .collect();
+/// Compress responses (gzip/br) based on the client's `Accept-Encoding`
+///
+/// The default predicate skips tiny bodies, images and `text/event-stream`,
+/// so the product SSE stream is never buffered by the compressor.
+fn with_compression(router: Router, enabled: bool) -> Router {
+    if enabled {
+        tracing::info!("Response compression enabled");
+        router.layer(CompressionLayer::new())
+    } else {
+        router
+    }
+}
+
+/// JSON 404 for paths that match no route, echoing the requested path
+async fn route_not_found(uri: Uri) -> Response {
+    route_error(StatusCode::NOT_FOUND, "Route not found".to_string(), &uri)
+}
+
+/// JSON 405 for known paths called with an unsupported method
+async fn method_not_allowed(method: Method, uri: Uri) -> Response {
+    route_error(
+        StatusCode::METHOD_NOT_ALLOWED,
+        format!("Method {} not allowed", method),
+        &uri,
+    )
+}
+
+/// Same `{"error": ...}` shape as `ApiError`, plus the path that was requested
+fn route_error(status: StatusCode, message: String, uri: &Uri) -> Response {
+    let mut body = json!({
+        "error": message,
+        "path": uri.path(),
+    });
+    if let Some(request_id) = current_request_id() {
+        body["request_id"] = json!(request_id);
+    }
+
+    (status, Json(body)).into_response()
+}
+
+/// Log one summary line per request with method, URI, status and latency
+///
+/// Headers (including `Authorization`) and bodies are never logged.
+async fn log_requests(req: Request, next: Next) -> Response {
+    let start = Instant::now();
+    let method = req.method().clone();
+    let uri = req.uri().clone();
+
+    let response = next.run(req).await;
 
+    tracing::info!(
+        method = %method,
+        uri = %uri,
+        status = response.status().as_u16(),
+        latency_ms = start.elapsed().as_millis() as u64,
+        "request completed"
+    );
+
+    response
+}
+
+/// Build the CORS layer from the allowed origins and preflight max-age
+fn build_cors_layer(origins: &[String], max_age: Duration) -> CorsLayer {
     let allowed_origins: Vec<HeaderValue> = origins
         .iter()
         .filter_map(|origin| match origin.parse::<HeaderValue>() {
@@ -74,7 +273,277 @@ fn create_cors_layer() -> CorsLayer {
 
     CorsLayer::new()
         .allow_origin(allowed_origins)
-        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
+        .allow_methods([
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::PATCH,
+            Method::DELETE,
+            Method::OPTIONS,
+        ])
         .allow_headers(allowed_headers)
+        .expose_headers([HeaderName::from_static(REQUEST_ID_HEADER)])
         .allow_credentials(true)
+        .max_age(max_age)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::StatusCode, routing::get};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_log_requests_passes_response_through() {
+        let app = Router::new()
+            .route("/ping", get(|| async { (StatusCode::CREATED, "pong") }))
+            .layer(middleware::from_fn(log_requests));
+
+        let request = Request::builder()
+            .uri("/ping")
+            .header("authorization", "Bearer secret-token")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"pong");
+    }
+
+    #[tokio::test]
+    async fn test_cors_preflight_uses_configured_max_age() {
+        let app = Router::new()
+            .route("/api/products", get(|| async { "ok" }))
+            .layer(build_cors_layer(
+                &["http://localhost:5173".to_string()],
+                Duration::from_secs(600),
+            ));
+
+        let request = Request::builder()
+            .method(Method::OPTIONS)
+            .uri("/api/products")
+            .header("origin", "http://localhost:5173")
+            .header("access-control-request-method", "GET")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.headers()["access-control-max-age"], "600");
+        assert_eq!(
+            response.headers()["access-control-allow-origin"],
+            "http://localhost:5173"
+        );
+    }
+
+    fn app() -> Router {
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .connect_lazy("postgres://postgres@127.0.0.1:1/unreachable")
+            .unwrap();
+        let config = Config::from_lookup(|name| {
+            (name == "DATABASE_URL")
+                .then(|| "postgres://postgres@127.0.0.1:1/unreachable".to_string())
+        })
+        .unwrap();
+        create_router(&config, DbPools::single(pool))
+    }
+
+    async fn json_body(response: Response) -> serde_json::Value {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_unknown_route_returns_json_404() {
+        let request = Request::builder()
+            .uri("/api/does-not-exist")
+            .header(REQUEST_ID_HEADER, "req-404")
+            .body(Body::empty())
+            .unwrap();
+        let response = app().oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            json_body(response).await,
+            json!({
+                "error": "Route not found",
+                "path": "/api/does-not-exist",
+                "request_id": "req-404",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wrong_method_returns_json_405() {
+        let request = Request::builder()
+            .method(Method::DELETE)
+            .uri("/health")
+            .header(REQUEST_ID_HEADER, "req-405")
+            .body(Body::empty())
+            .unwrap();
+        let response = app().oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(
+            json_body(response).await,
+            json!({
+                "error": "Method DELETE not allowed",
+                "path": "/health",
+                "request_id": "req-405",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_user_export_requires_login_and_ownership() {
+        use crate::core::authentication::{CurrentUser, Role};
+
+        let uri = format!("/api/users/{}/export", uuid::Uuid::new_v4());
+
+        let request = Request::builder().uri(&uri).body(Body::empty()).unwrap();
+        let response = app().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let mut request = Request::builder().uri(&uri).body(Body::empty()).unwrap();
+        request.extensions_mut().insert(CurrentUser {
+            id: uuid::Uuid::new_v4(),
+            role: Role::User,
+        });
+        let response = app().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_favorite_endpoints_require_authentication() {
+        let favorite = format!("/api/products/{}/favorite", uuid::Uuid::new_v4());
+        let requests = [
+            ("POST", favorite.clone()),
+            ("DELETE", favorite),
+            ("GET", "/api/me/favorites".to_string()),
+        ];
+
+        for (method, uri) in requests {
+            let request = Request::builder()
+                .method(method)
+                .uri(&uri)
+                .body(Body::empty())
+                .unwrap();
+            let response = app().oneshot(request).await.unwrap();
+            assert_eq!(
+                response.status(),
+                StatusCode::UNAUTHORIZED,
+                "{} {}",
+                method,
+                uri
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_inactive_user_endpoints_require_admin() {
+        use crate::core::authentication::{CurrentUser, Role};
+
+        let reactivate = format!("/api/users/{}/reactivate", uuid::Uuid::new_v4());
+        let requests = [
+            ("GET", "/api/admin/users/inactive".to_string()),
+            ("POST", reactivate),
+        ];
+
+        for (method, uri) in requests {
+            let request = Request::builder()
+                .method(method)
+                .uri(&uri)
+                .body(Body::empty())
+                .unwrap();
+            let response = app().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{}", uri);
+
+            let mut request = Request::builder()
+                .method(method)
+                .uri(&uri)
+                .body(Body::empty())
+                .unwrap();
+            request.extensions_mut().insert(CurrentUser {
+                id: uuid::Uuid::new_v4(),
+                role: Role::User,
+            });
+            let response = app().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN, "{}", uri);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_order_requires_login() {
+        let request = Request::builder()
+            .method("POST")
+            .uri("/api/orders")
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"items":[]}"#))
+            .unwrap();
+        let response = app().oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    fn large_list_router(enabled: bool) -> Router {
+        let items: Vec<String> = (0..500).map(|i| format!("Product {}", i)).collect();
+        let router = Router::new().route(
+            "/products",
+            get(move || async move { axum::Json(items.clone()) }),
+        );
+        with_compression(router, enabled)
+    }
+
+    fn gzip_request() -> Request {
+        Request::builder()
+            .uri("/products")
+            .header("accept-encoding", "gzip")
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_large_list_is_gzip_compressed() {
+        let response = large_list_router(true)
+            .oneshot(gzip_request())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-encoding"], "gzip");
+    }
+
+    #[tokio::test]
+    async fn test_compression_can_be_disabled() {
+        let response = large_list_router(false)
+            .oneshot(gzip_request())
+            .await
+            .unwrap();
+
+        assert!(response.headers().get("content-encoding").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_event_stream_is_not_compressed() {
+        let router = Router::new().route(
+            "/products",
+            get(|| async {
+                (
+                    [("content-type", "text/event-stream")],
+                    "data: hello\n\n".repeat(100),
+                )
+            }),
+        );
+
+        let response = with_compression(router, true)
+            .oneshot(gzip_request())
+            .await
+            .unwrap();
+
+        assert!(response.headers().get("content-encoding").is_none());
+    }
 }
@@ -0,0 +1,48 @@
+use crate::core::{
+    config::{Config, StartupError, redact_url_password},
+    db::DbPools,
+};
+use crate::utils::init_jwt;
+
+/// Validate the configuration and connect to the database before serving
+///
+/// Every check runs even when an earlier one fails, so a misconfigured
+/// deployment reports all of its problems in one go instead of one per restart.
+pub async fn self_check(config: &Config) -> Result<DbPools, StartupError> {
+    let mut problems = config
+        .validate()
+        .err()
+        .map_or_else(Vec::new, |error| error.0);
+
+    // Also catches bad RS256 keys and JWT settings the config check doesn't cover
+    if let Err(error) = init_jwt(config) {
+        let problem = error.to_string();
+        if !problems.contains(&problem) {
+            problems.push(problem);
+        }
+    }
+
+    tracing::info!("Connecting to database...");
+    let pools = match DbPools::connect(
+        &config.database_url,
+        config.database_read_url.as_deref(),
+        config.database_pool,
+    )
+    .await
+    {
+        Ok(pools) => Some(pools),
+        Err(error) => {
+            problems.push(format!(
+                "Database at {} is unreachable: {}",
+                redact_url_password(&config.database_url),
+                error
+            ));
+            None
+        }
+    };
+
+    match pools {
+        Some(pools) if problems.is_empty() => Ok(pools),
+        _ => Err(StartupError(problems)),
+    }
+}
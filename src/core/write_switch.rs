@@ -0,0 +1,116 @@
+use axum::{
+    Json, Router,
+    extract::{Request, State},
+    http::Method,
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::post,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+use crate::core::{authentication::require_admin, error::ApiError};
+
+/// Whether a request method changes data
+pub(crate) fn is_write(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+    )
+}
+
+/// What sets one write switch apart from another
+#[derive(Debug)]
+pub struct WriteSwitchSpec {
+    pub name: &'static str,        // Used in logs, e.g. "Maintenance mode"
+    pub toggle_path: &'static str, // Admin route that flips the switch
+    pub state_key: &'static str,   // Key of the new state in the toggle response
+    pub message: &'static str,     // 503 error message while the switch is on
+    pub exempt_paths: &'static [&'static str], // Paths that stay writable while it is on
+}
+
+/// Runtime switch that rejects writes with 503 while it is on
+///
+/// Shared between the middleware and the admin toggle route. Reads (GET/HEAD/OPTIONS)
+/// always pass through, so the health checks and token verification keep working.
+#[derive(Clone)]
+pub struct WriteSwitch {
+    enabled: Arc<AtomicBool>,
+    spec: &'static WriteSwitchSpec,
+}
+
+impl WriteSwitch {
+    /// Switch starting in the given state
+    pub fn new(spec: &'static WriteSwitchSpec, enabled: bool) -> Self {
+        Self {
+            enabled: Arc::new(AtomicBool::new(enabled)),
+            spec,
+        }
+    }
+
+    /// Whether the switch is currently on
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Turn the switch on or off
+    pub fn set(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Whether this request is rejected while the switch is on
+    fn blocks(&self, method: &Method, path: &str) -> bool {
+        is_write(method) && !self.spec.exempt_paths.contains(&path)
+    }
+
+    /// Create the admin-only toggle route
+    pub fn routes(&self) -> Router {
+        Router::new()
+            .route(
+                self.spec.toggle_path,
+                post(toggle_switch).route_layer(middleware::from_fn(require_admin)),
+            )
+            .with_state(self.clone())
+    }
+}
+
+/// Request body for toggling a write switch
+#[derive(Debug, Deserialize)]
+pub struct ToggleSwitch {
+    pub enabled: bool, // New state
+}
+
+async fn toggle_switch(
+    State(switch): State<WriteSwitch>,
+    Json(request): Json<ToggleSwitch>,
+) -> impl IntoResponse {
+    switch.set(request.enabled);
+    tracing::warn!(
+        "{} {}",
+        switch.spec.name,
+        if request.enabled {
+            "enabled"
+        } else {
+            "disabled"
+        }
+    );
+
+    Json(json!({ switch.spec.state_key: request.enabled }))
+}
+
+/// Reject mutating requests with 503 while the switch is on
+pub async fn write_switch_middleware(
+    State(switch): State<WriteSwitch>,
+    req: Request,
+    next: Next,
+) -> Response {
+    if switch.is_enabled() && switch.blocks(req.method(), req.uri().path()) {
+        return ApiError::ServiceUnavailable(switch.spec.message.to_string()).into_response();
+    }
+
+    next.run(req).await
+}
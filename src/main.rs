@@ -1,15 +1,17 @@
-mod core;
-mod modules;
-mod seeder;
-mod utils;
-
-use crate::core::config::Config;
-use crate::core::db::init_db;
-use crate::core::server::run_server;
+use learning_rust::core::config::Config;
+use learning_rust::core::db::run_migrations;
+use learning_rust::core::health::mark_started;
+use learning_rust::core::server::run_server;
+use learning_rust::core::startup::self_check;
+use learning_rust::seeder;
+use tracing::Instrument;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    // Record start time for uptime reporting
+    mark_started();
+
     // Load environment variables
     dotenv::dotenv().ok();
 
@@ -21,19 +23,56 @@ async fn main() -> anyhow::Result<()> {
         .with(tracing_subscriber::fmt::layer())
         .init();
 
-    // Load config
-    let config = Config::from_env();
+    // Load config, exiting with a clear message when it is invalid
+    let config = match Config::from_env() {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("Configuration error: {}", error);
+            std::process::exit(1);
+        }
+    };
+
+    // Every log line carries the environment name through the root span
+    let root_span = tracing::info_span!("app", env = %config.app_env);
+    start(config).instrument(root_span).await
+}
+
+/// Self-check, migrate, seed and serve
+async fn start(config: Config) -> anyhow::Result<()> {
+    tracing::info!("Starting in {} environment", config.app_env);
+
+    // Verify secrets, seed files and the database before doing anything else
+    let db_pools = match self_check(&config).await {
+        Ok(db_pools) => db_pools,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
+    if config.database_read_url.is_some() {
+        tracing::info!("Database connections established (read replica enabled)");
+    } else {
+        tracing::info!("Database connection established");
+    }
 
-    // Initialize database
-    tracing::info!("Connecting to database...");
-    let db_pool = init_db(&config.database_url).await?;
-    tracing::info!("Database connection established");
+    if config.run_migrations {
+        tracing::info!("Running database migrations...");
+        run_migrations(&db_pools.write).await?;
+        tracing::info!("Database migrations completed");
+    } else {
+        tracing::info!("Skipping database migrations (RUN_MIGRATIONS=false)");
+    }
 
-    tracing::info!("Running seeders in development mode");
-    seeder::run_all_seeders(&db_pool).await?;
+    // Never seed sample data into production
+    if config.seeds_on_start() {
+        tracing::info!("Running seeders in development mode");
+        seeder::run_all_seeders(&db_pools.write, &config.features).await?;
+    } else {
+        tracing::info!("Skipping seeders (APP_ENV=production)");
+    }
 
     // Run server
-    run_server(config, db_pool).await?;
+    run_server(config, db_pools).await?;
 
     Ok(())
 }
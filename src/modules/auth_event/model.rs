@@ -0,0 +1,26 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::utils::timestamp;
+
+/// Event type recorded for login attempts
+pub const EVENT_LOGIN: &str = "login";
+
+/// Authentication audit log entry
+#[derive(Debug, Serialize, Clone)]
+pub struct AuthEvent {
+    pub id: i64,               // Monotonic event ID
+    pub user_id: Option<Uuid>, // User involved (None when the account is unknown)
+    pub event_type: String,    // Event name, e.g. "login"
+    pub ip: Option<String>,    // Client IP address
+    pub success: bool,         // Whether the attempt succeeded
+    #[serde(with = "timestamp")]
+    pub created_at: DateTime<Utc>, // When the event was recorded
+}
+
+/// Query parameters for listing authentication events
+#[derive(Debug, Deserialize)]
+pub struct AuthEventQuery {
+    pub user_id: Option<Uuid>, // Only return events for this user
+}
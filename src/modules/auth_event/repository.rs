@@ -0,0 +1,96 @@
+use crate::{
+    core::{db::DbPool, error::ApiError},
+    modules::auth_event::model::AuthEvent,
+    utils::offset_to_chrono,
+};
+use sqlx::{FromRow, QueryBuilder, Row, postgres::PgRow, query};
+use std::net::IpAddr;
+use uuid::Uuid;
+
+const SELECT_AUTH_EVENT_BASE: &str =
+    "SELECT id, user_id, event_type, ip, success, created_at FROM auth_events";
+const QUERY_INSERT: &str =
+    "INSERT INTO auth_events (user_id, event_type, ip, success) VALUES ($1, $2, $3, $4)";
+
+/// Repository untuk operasi database audit log autentikasi
+pub struct AuthEventRepository {
+    pool: DbPool,
+}
+
+// Implementasi FromRow untuk AuthEvent untuk menangani konversi tipe khusus
+impl<'r> FromRow<'r, PgRow> for AuthEvent {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        Ok(AuthEvent {
+            id: row.try_get("id")?,
+            user_id: row.try_get("user_id")?,
+            event_type: row.try_get("event_type")?,
+            ip: row.try_get("ip")?,
+            success: row.try_get("success")?,
+            created_at: offset_to_chrono(row.try_get("created_at")?),
+        })
+    }
+}
+
+/// Mencatat event autentikasi ke audit log
+pub async fn record_auth_event(
+    pool: &DbPool,
+    user_id: Option<Uuid>,
+    event_type: &str,
+    ip: Option<IpAddr>,
+    success: bool,
+) -> Result<(), ApiError> {
+    query(QUERY_INSERT)
+        .bind(user_id)
+        .bind(event_type)
+        .bind(ip.map(|ip| ip.to_string()))
+        .bind(success)
+        .execute(pool)
+        .await
+        .map_err(ApiError::Database)?;
+
+    Ok(())
+}
+
+impl AuthEventRepository {
+    /// Membuat repository audit log baru
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Semua event milik satu pengguna, dari yang paling lama
+    pub async fn list_for_user(&self, user_id: Uuid) -> Result<Vec<AuthEvent>, ApiError> {
+        let query_str = format!("{SELECT_AUTH_EVENT_BASE} WHERE user_id = $1 ORDER BY id ASC");
+
+        sqlx::query_as::<_, AuthEvent>(&query_str)
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(ApiError::Database)
+    }
+
+    /// Daftar event terbaru, opsional difilter per pengguna
+    pub async fn list(
+        &self,
+        user_id: Option<Uuid>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<AuthEvent>, ApiError> {
+        let mut query_builder = QueryBuilder::new(SELECT_AUTH_EVENT_BASE);
+
+        if let Some(user_id) = user_id {
+            query_builder.push(" WHERE user_id = ");
+            query_builder.push_bind(user_id);
+        }
+
+        query_builder.push(" ORDER BY id DESC LIMIT ");
+        query_builder.push_bind(limit);
+        query_builder.push(" OFFSET ");
+        query_builder.push_bind(offset);
+
+        query_builder
+            .build_query_as::<AuthEvent>()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(ApiError::Database)
+    }
+}
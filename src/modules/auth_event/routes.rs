@@ -0,0 +1,38 @@
+use crate::core::{
+    authentication::require_admin, db::DbPool, pagination::PageParams, query::ApiQuery,
+};
+use crate::modules::auth_event::model::AuthEventQuery;
+use crate::modules::auth_event::repository::AuthEventRepository;
+use crate::modules::auth_event::service::AuthEventService;
+use axum::{Router, extract::State, middleware, response::IntoResponse, routing::get};
+use std::sync::Arc;
+
+/// Type alias for the shared state
+type SharedHandler = Arc<AuthEventService>;
+
+/// Create authentication audit log routes (admin only)
+pub fn auth_event_routes(pool: DbPool) -> Router {
+    // Create repository and handler
+    let repository = AuthEventRepository::new(pool);
+    let handler = Arc::new(AuthEventService::new(repository));
+
+    // Define routes with shared state
+    Router::new()
+        .route(
+            "/api/admin/auth-events",
+            get(list_events).route_layer(middleware::from_fn(require_admin)),
+        )
+        .with_state(handler)
+}
+
+/// Handler functions that take the shared state
+async fn list_events(
+    State(handler): State<SharedHandler>,
+    ApiQuery(query): ApiQuery<AuthEventQuery>,
+    page: PageParams,
+) -> impl IntoResponse {
+    handler.list_events(query, page).await.map_or_else(
+        |err| err.into_response(),
+        |response| response.into_response(),
+    )
+}
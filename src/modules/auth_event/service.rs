@@ -0,0 +1,32 @@
+use crate::core::{
+    error::ApiError,
+    pagination::{Page, PageParams},
+};
+use crate::modules::auth_event::model::AuthEventQuery;
+use crate::modules::auth_event::repository::AuthEventRepository;
+use axum::{Json, response::IntoResponse};
+
+/// Authentication audit log HTTP request handlers
+pub struct AuthEventService {
+    repository: AuthEventRepository, // Audit log repository for database operations
+}
+
+impl AuthEventService {
+    /// Create a new audit log handler
+    pub fn new(repository: AuthEventRepository) -> Self {
+        Self { repository }
+    }
+
+    /// Handler to list authentication events, newest first
+    pub async fn list_events(
+        &self,
+        query: AuthEventQuery,
+        page: PageParams,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        let events = self
+            .repository
+            .list(query.user_id, page.limit, page.offset)
+            .await?;
+        Ok(Json(Page::new(events, page)))
+    }
+}
@@ -1,2 +1,5 @@
+pub mod auth_event;
+pub mod order;
+pub mod outbox;
 pub mod product; // Export product module
 pub mod user;
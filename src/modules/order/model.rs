@@ -0,0 +1,37 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::utils::timestamp;
+
+/// Order model representing a purchase in the database
+#[derive(Debug, Clone, Serialize)]
+pub struct Order {
+    pub id: Uuid,              // Unique identifier
+    pub user_id: Uuid,         // User who placed the order
+    pub items: Vec<OrderItem>, // Purchased products
+    pub total: f64,            // Sum of quantity * unit price over all items
+    #[serde(with = "timestamp")]
+    pub created_at: DateTime<Utc>, // Creation timestamp
+}
+
+/// One purchased product within an order
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OrderItem {
+    pub product_id: Uuid, // Purchased product
+    pub quantity: i32,    // Number of units bought
+    pub unit_price: f64,  // Product price at the time of purchase
+}
+
+/// DTO for placing a new order
+#[derive(Debug, Deserialize)]
+pub struct CreateOrder {
+    pub items: Vec<OrderItemRequest>, // Products to buy
+}
+
+/// Requested product and quantity within a new order
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct OrderItemRequest {
+    pub product_id: Uuid, // Product to buy
+    pub quantity: i32,    // Number of units (must be positive)
+}
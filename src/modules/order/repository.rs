@@ -0,0 +1,134 @@
+use crate::{
+    core::{
+        db::{DbPool, DbPools},
+        error::ApiError,
+    },
+    modules::{
+        order::model::{Order, OrderItem, OrderItemRequest},
+        product::{model::Product, repository::ProductRepository},
+    },
+    utils::offset_to_chrono,
+};
+use sqlx::{Row, query};
+use tracing::info;
+use uuid::Uuid;
+
+const QUERY_INSERT_ORDER: &str =
+    "INSERT INTO orders (user_id, total) VALUES ($1, $2) RETURNING id, created_at";
+const QUERY_INSERT_ITEM: &str =
+    "INSERT INTO order_items (order_id, product_id, quantity, unit_price) VALUES ($1, $2, $3, $4)";
+
+/// Repository untuk operasi database order
+pub struct OrderRepository {
+    pool: DbPool,
+}
+
+impl OrderRepository {
+    /// Membuat instance repository baru
+    pub fn new(pools: DbPools) -> Self {
+        Self { pool: pools.write }
+    }
+
+    /// Membuat order dan mengurangi stok setiap produk dalam satu transaksi
+    ///
+    /// `items` harus sudah digabung per produk dan diurutkan berdasarkan ID
+    /// supaya baris produk selalu dikunci dengan urutan yang sama. Kalau salah
+    /// satu produk tidak ada, tidak aktif atau stoknya kurang, seluruh order
+    /// di-rollback dan tidak ada stok yang berubah.
+    pub async fn create(
+        &self,
+        user_id: Uuid,
+        items: &[OrderItemRequest],
+    ) -> Result<Order, ApiError> {
+        let mut tx = self.pool.begin().await.map_err(ApiError::Database)?;
+
+        // Transaksi di-rollback otomatis saat `tx` di-drop tanpa commit
+        let mut order_items = Vec::with_capacity(items.len());
+        for item in items {
+            let product =
+                ProductRepository::adjust_stock(&mut tx, item.product_id, -item.quantity).await?;
+            ensure_orderable(&product)?;
+
+            order_items.push(OrderItem {
+                product_id: product.id,
+                quantity: item.quantity,
+                unit_price: product.price,
+            });
+        }
+
+        let total = order_total(&order_items);
+        let row = query(QUERY_INSERT_ORDER)
+            .bind(user_id)
+            .bind(total)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(ApiError::Database)?;
+        let id: Uuid = row.try_get("id").map_err(ApiError::Database)?;
+        let created_at = offset_to_chrono(row.try_get("created_at").map_err(ApiError::Database)?);
+
+        for item in &order_items {
+            query(QUERY_INSERT_ITEM)
+                .bind(id)
+                .bind(item.product_id)
+                .bind(item.quantity)
+                .bind(item.unit_price)
+                .execute(&mut *tx)
+                .await
+                .map_err(ApiError::Database)?;
+        }
+
+        tx.commit().await.map_err(ApiError::Database)?;
+        info!(
+            "Order {} dibuat oleh pengguna {} (total {})",
+            id, user_id, total
+        );
+
+        Ok(Order {
+            id,
+            user_id,
+            items: order_items,
+            total,
+            created_at,
+        })
+    }
+}
+
+/// Produk non-aktif tidak bisa dibeli
+fn ensure_orderable(product: &Product) -> Result<(), ApiError> {
+    if !product.is_active {
+        return Err(ApiError::BadRequest(format!(
+            "Produk dengan ID {} tidak aktif",
+            product.id
+        )));
+    }
+    Ok(())
+}
+
+/// Menjumlahkan harga order dalam satuan sen supaya tidak ada selisih pembulatan float
+fn order_total(items: &[OrderItem]) -> f64 {
+    let cents: i64 = items
+        .iter()
+        .map(|item| (item.unit_price * 100.0).round() as i64 * i64::from(item.quantity))
+        .sum();
+    cents as f64 / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(unit_price: f64, quantity: i32) -> OrderItem {
+        OrderItem {
+            product_id: Uuid::new_v4(),
+            quantity,
+            unit_price,
+        }
+    }
+
+    #[test]
+    fn test_order_total_sums_line_items() {
+        assert_eq!(order_total(&[]), 0.0);
+        assert_eq!(order_total(&[item(0.1, 3), item(0.2, 1)]), 0.5);
+        assert_eq!(order_total(&[item(19.99, 2), item(5000.0, 1)]), 5039.98);
+    }
+}
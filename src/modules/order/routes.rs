@@ -0,0 +1,41 @@
+use crate::core::{
+    authentication::{AuthUser, require_user},
+    db::DbPools,
+    json::ApiJson,
+};
+use crate::modules::order::model::CreateOrder;
+use crate::modules::order::repository::OrderRepository;
+use crate::modules::order::service::OrderService;
+use crate::modules::product::cache::ProductCache;
+use axum::{Router, extract::State, middleware, response::IntoResponse, routing::post};
+use std::sync::Arc;
+
+/// Type alias for the shared state
+type SharedHandler = Arc<OrderService>;
+
+/// Create order routes (authenticated users only)
+pub fn order_routes(pools: DbPools, product_cache: ProductCache) -> Router {
+    // Create repository and handler
+    let repository = OrderRepository::new(pools);
+    let handler = Arc::new(OrderService::new(repository, product_cache));
+
+    // Define routes with shared state
+    Router::new()
+        .route(
+            "/api/orders",
+            post(create_order).route_layer(middleware::from_fn(require_user)),
+        )
+        .with_state(handler)
+}
+
+/// Handler functions that take the shared state
+async fn create_order(
+    State(handler): State<SharedHandler>,
+    AuthUser(user): AuthUser,
+    ApiJson(request): ApiJson<CreateOrder>,
+) -> impl IntoResponse {
+    handler.create_order(request, &user).await.map_or_else(
+        |err| err.into_response(),
+        |response| response.into_response(),
+    )
+}
@@ -0,0 +1,131 @@
+use crate::core::{authentication::CurrentUser, error::ApiError};
+use crate::modules::order::model::{CreateOrder, OrderItemRequest};
+use crate::modules::order::repository::OrderRepository;
+use crate::modules::product::cache::ProductCache;
+use axum::{Json, http::StatusCode, response::IntoResponse};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+/// Most distinct products a single order may contain
+const MAX_ORDER_ITEMS: usize = 100;
+
+/// Service handling order requests
+pub struct OrderService {
+    repository: OrderRepository,
+    product_cache: ProductCache, // Cached product reads to invalidate after stock changes
+}
+
+impl OrderService {
+    /// Create a new handler instance
+    pub fn new(repository: OrderRepository, product_cache: ProductCache) -> Self {
+        Self {
+            repository,
+            product_cache,
+        }
+    }
+
+    /// Handler to place an order for the authenticated user
+    ///
+    /// Stock is decremented for every item in one transaction; if any product
+    /// is missing, inactive or out of stock nothing is changed.
+    pub async fn create_order(
+        &self,
+        request: CreateOrder,
+        user: &CurrentUser,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        let items = normalize_order_items(request.items)?;
+        let order = self.repository.create(user.id, &items).await?;
+        for item in &order.items {
+            self.product_cache.invalidate(item.product_id);
+        }
+
+        Ok((StatusCode::CREATED, Json(order)))
+    }
+}
+
+/// Validate order items, merging repeated products and sorting them by ID
+///
+/// The ID order makes concurrent orders lock product rows in the same sequence.
+fn normalize_order_items(items: Vec<OrderItemRequest>) -> Result<Vec<OrderItemRequest>, ApiError> {
+    if items.is_empty() {
+        return Err(ApiError::BadRequest(
+            "Order must contain at least one item".to_string(),
+        ));
+    }
+
+    let mut quantities: BTreeMap<Uuid, i32> = BTreeMap::new();
+    for item in items {
+        if item.quantity <= 0 {
+            return Err(ApiError::BadRequest(format!(
+                "Quantity for product {} must be greater than zero",
+                item.product_id
+            )));
+        }
+
+        let quantity = quantities.entry(item.product_id).or_insert(0);
+        *quantity = quantity.checked_add(item.quantity).ok_or_else(|| {
+            ApiError::BadRequest(format!(
+                "Quantity for product {} is too large",
+                item.product_id
+            ))
+        })?;
+    }
+
+    if quantities.len() > MAX_ORDER_ITEMS {
+        return Err(ApiError::BadRequest(format!(
+            "An order may contain at most {} different products",
+            MAX_ORDER_ITEMS
+        )));
+    }
+
+    Ok(quantities
+        .into_iter()
+        .map(|(product_id, quantity)| OrderItemRequest {
+            product_id,
+            quantity,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(product_id: Uuid, quantity: i32) -> OrderItemRequest {
+        OrderItemRequest {
+            product_id,
+            quantity,
+        }
+    }
+
+    #[test]
+    fn test_normalize_order_items_merges_and_sorts_by_product() {
+        let (a, b) = {
+            let (x, y) = (Uuid::new_v4(), Uuid::new_v4());
+            if x < y { (x, y) } else { (y, x) }
+        };
+
+        let items = normalize_order_items(vec![item(b, 1), item(a, 2), item(b, 3)]).unwrap();
+        assert_eq!(items, vec![item(a, 2), item(b, 4)]);
+    }
+
+    #[test]
+    fn test_normalize_order_items_rejects_invalid_orders() {
+        assert!(matches!(
+            normalize_order_items(Vec::new()),
+            Err(ApiError::BadRequest(_))
+        ));
+        assert!(matches!(
+            normalize_order_items(vec![item(Uuid::new_v4(), 0)]),
+            Err(ApiError::BadRequest(_))
+        ));
+
+        let id = Uuid::new_v4();
+        assert!(normalize_order_items(vec![item(id, i32::MAX), item(id, 1)]).is_err());
+
+        let too_many = (0..=MAX_ORDER_ITEMS)
+            .map(|_| item(Uuid::new_v4(), 1))
+            .collect();
+        assert!(normalize_order_items(too_many).is_err());
+    }
+}
@@ -0,0 +1,32 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::utils::timestamp;
+
+/// Outbox event recorded in the same transaction as a mutation
+#[derive(Debug, Serialize, Clone)]
+pub struct OutboxEvent {
+    pub id: i64,                    // Monotonic event ID used as a cursor
+    pub aggregate_id: Uuid,         // ID of the mutated entity
+    pub event_type: String,         // Event name, e.g. "product.created"
+    pub payload: serde_json::Value, // Event payload
+    #[serde(with = "timestamp")]
+    pub created_at: DateTime<Utc>, // When the event was recorded
+    #[serde(with = "timestamp::option")]
+    pub processed_at: Option<DateTime<Utc>>, // When a consumer marked it processed
+}
+
+/// Query parameters for pulling outbox events
+#[derive(Debug, Deserialize)]
+pub struct OutboxQuery {
+    pub after: Option<i64>, // Only return events with an ID greater than this (may skip late commits)
+    pub limit: Option<i64>, // Maximum number of events to return
+    pub include_processed: Option<bool>, // Also return already processed events
+}
+
+/// DTO for marking events as processed
+#[derive(Debug, Deserialize)]
+pub struct MarkProcessed {
+    pub ids: Vec<i64>, // Event IDs to mark as processed
+}
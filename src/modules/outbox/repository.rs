@@ -0,0 +1,136 @@
+use crate::{
+    core::{db::DbPool, error::ApiError},
+    modules::outbox::model::OutboxEvent,
+    utils::offset_to_chrono,
+};
+use sqlx::{FromRow, Postgres, QueryBuilder, Row, Transaction, postgres::PgRow, query};
+use uuid::Uuid;
+
+const SELECT_OUTBOX_BASE: &str =
+    "SELECT id, aggregate_id, event_type, payload, created_at, processed_at FROM outbox";
+const QUERY_INSERT: &str =
+    "INSERT INTO outbox (aggregate_id, event_type, payload) VALUES ($1, $2, $3)";
+const QUERY_MARK_PROCESSED: &str =
+    "UPDATE outbox SET processed_at = NOW() WHERE id = ANY($1) AND processed_at IS NULL";
+
+/// Repository untuk operasi database outbox
+pub struct OutboxRepository {
+    pool: DbPool,
+}
+
+// Implementasi FromRow untuk OutboxEvent untuk menangani konversi tipe khusus
+impl<'r> FromRow<'r, PgRow> for OutboxEvent {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        Ok(OutboxEvent {
+            id: row.try_get("id")?,
+            aggregate_id: row.try_get("aggregate_id")?,
+            event_type: row.try_get("event_type")?,
+            payload: row.try_get("payload")?,
+            created_at: offset_to_chrono(row.try_get("created_at")?),
+            processed_at: row
+                .try_get::<Option<_>, _>("processed_at")?
+                .map(offset_to_chrono),
+        })
+    }
+}
+
+/// Mencatat event ke outbox di dalam transaksi yang sedang berjalan
+pub async fn record_event(
+    tx: &mut Transaction<'_, Postgres>,
+    aggregate_id: Uuid,
+    event_type: &str,
+    payload: serde_json::Value,
+) -> Result<(), ApiError> {
+    query(QUERY_INSERT)
+        .bind(aggregate_id)
+        .bind(event_type)
+        .bind(payload)
+        .execute(&mut **tx)
+        .await
+        .map_err(ApiError::Database)?;
+
+    Ok(())
+}
+
+impl OutboxRepository {
+    /// Membuat repository outbox baru
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Daftar event setelah ID tertentu, diurutkan berdasarkan ID
+    ///
+    /// ID dialokasikan saat insert, bukan saat commit, jadi transaksi yang commit
+    /// belakangan bisa memunculkan ID yang lebih kecil dari cursor `after`. Event
+    /// seperti itu terlewat oleh cursor; konsumen yang butuh at-least-once harus
+    /// memanggil tanpa `after` (hanya event yang belum diproses) lalu menandainya.
+    pub async fn list_after(
+        &self,
+        after: Option<i64>,
+        limit: i64,
+        include_processed: bool,
+    ) -> Result<Vec<OutboxEvent>, ApiError> {
+        Self::list_query(after, limit, include_processed)
+            .build_query_as::<OutboxEvent>()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(ApiError::Database)
+    }
+
+    /// Query daftar event; tanpa `after` hanya status processed yang membatasi
+    fn list_query(
+        after: Option<i64>,
+        limit: i64,
+        include_processed: bool,
+    ) -> QueryBuilder<'static, Postgres> {
+        let mut query_builder = QueryBuilder::new(SELECT_OUTBOX_BASE);
+        query_builder.push(" WHERE 1=1");
+
+        if let Some(after) = after {
+            query_builder.push(" AND id > ");
+            query_builder.push_bind(after);
+        }
+
+        if !include_processed {
+            query_builder.push(" AND processed_at IS NULL");
+        }
+
+        query_builder.push(" ORDER BY id ASC LIMIT ");
+        query_builder.push_bind(limit);
+        query_builder
+    }
+
+    /// Menandai event sebagai sudah diproses, mengembalikan jumlah yang ditandai
+    pub async fn mark_processed(&self, ids: &[i64]) -> Result<u64, ApiError> {
+        let result = query(QUERY_MARK_PROCESSED)
+            .bind(ids)
+            .execute(&self.pool)
+            .await
+            .map_err(ApiError::Database)?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unprocessed_pull_does_not_skip_late_commits() {
+        // Tanpa cursor, event dengan ID kecil yang commit belakangan tetap terambil
+        let sql = OutboxRepository::list_query(None, 100, false).into_sql();
+
+        assert!(!sql.contains("id >"));
+        assert!(sql.ends_with("WHERE 1=1 AND processed_at IS NULL ORDER BY id ASC LIMIT $1"));
+    }
+
+    #[test]
+    fn test_cursor_pull_filters_by_id() {
+        let sql = OutboxRepository::list_query(Some(42), 100, true).into_sql();
+
+        assert!(sql.contains("AND id > $1"));
+        assert!(!sql.contains("processed_at IS NULL"));
+        assert!(sql.ends_with("ORDER BY id ASC LIMIT $2"));
+    }
+}
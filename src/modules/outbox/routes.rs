@@ -0,0 +1,48 @@
+use crate::core::{db::DbPool, query::ApiQuery};
+use crate::modules::outbox::model::{MarkProcessed, OutboxQuery};
+use crate::modules::outbox::repository::OutboxRepository;
+use crate::modules::outbox::service::OutboxService;
+use axum::{
+    Json, Router,
+    extract::State,
+    response::IntoResponse,
+    routing::{get, post},
+};
+use std::sync::Arc;
+
+/// Type alias for the shared state
+type SharedHandler = Arc<OutboxService>;
+
+/// Create outbox routes
+pub fn outbox_routes(pool: DbPool) -> Router {
+    // Create repository and handler
+    let repository = OutboxRepository::new(pool);
+    let handler = Arc::new(OutboxService::new(repository));
+
+    // Define routes with shared state
+    Router::new()
+        .route("/api/outbox", get(list_events))
+        .route("/api/outbox/processed", post(mark_processed))
+        .with_state(handler)
+}
+
+/// Handler functions that take the shared state
+async fn list_events(
+    State(handler): State<SharedHandler>,
+    ApiQuery(query): ApiQuery<OutboxQuery>,
+) -> impl IntoResponse {
+    handler.list_events(query).await.map_or_else(
+        |err| err.into_response(),
+        |response| response.into_response(),
+    )
+}
+
+async fn mark_processed(
+    State(handler): State<SharedHandler>,
+    Json(request): Json<MarkProcessed>,
+) -> impl IntoResponse {
+    handler.mark_processed(request).await.map_or_else(
+        |err| err.into_response(),
+        |response| response.into_response(),
+    )
+}
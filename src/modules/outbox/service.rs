@@ -0,0 +1,55 @@
+use crate::core::error::ApiError;
+use crate::modules::outbox::model::{MarkProcessed, OutboxQuery};
+use crate::modules::outbox::repository::OutboxRepository;
+use axum::{Json, response::IntoResponse};
+use serde_json::json;
+
+const DEFAULT_OUTBOX_LIMIT: i64 = 100;
+const MAX_OUTBOX_LIMIT: i64 = 1000;
+
+/// Outbox HTTP request handlers
+pub struct OutboxService {
+    repository: OutboxRepository, // Outbox repository for database operations
+}
+
+impl OutboxService {
+    /// Create a new outbox handler
+    pub fn new(repository: OutboxRepository) -> Self {
+        Self { repository }
+    }
+
+    /// Handler to pull events, optionally after a cursor
+    ///
+    /// The `after` cursor can skip events whose transaction committed after a
+    /// later ID was already returned; without it the unprocessed events are
+    /// listed, which is the at-least-once way to consume the outbox.
+    pub async fn list_events(
+        &self,
+        query: OutboxQuery,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        let limit = query
+            .limit
+            .unwrap_or(DEFAULT_OUTBOX_LIMIT)
+            .clamp(1, MAX_OUTBOX_LIMIT);
+
+        let events = self
+            .repository
+            .list_after(query.after, limit, query.include_processed.unwrap_or(false))
+            .await?;
+
+        Ok(Json(events))
+    }
+
+    /// Handler to mark events as processed
+    pub async fn mark_processed(
+        &self,
+        request: MarkProcessed,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        if request.ids.is_empty() {
+            return Err(ApiError::BadRequest("No event IDs provided".to_string()));
+        }
+
+        let processed = self.repository.mark_processed(&request.ids).await?;
+        Ok(Json(json!({ "processed": processed })))
+    }
+}
@@ -0,0 +1,200 @@
+use crate::core::{config::FeatureFlags, error::ApiError};
+use crate::modules::product::model::Product;
+use moka::sync::Cache;
+use std::{future::Future, time::Duration};
+use uuid::Uuid;
+
+/// Most products kept in the cache at once
+const MAX_CACHED_PRODUCTS: u64 = 10_000;
+
+/// In-memory TTL cache of products by ID, used by single-product reads
+///
+/// Clones share the same entries, so every component that changes products
+/// (the product service, orders) can invalidate what the reads see. With a
+/// TTL of zero the cache is disabled and every read goes to the database.
+#[derive(Clone, Default)]
+pub struct ProductCache {
+    entries: Option<Cache<Uuid, Product>>,
+}
+
+impl ProductCache {
+    /// Cache entries for `ttl`; a zero TTL disables caching
+    pub fn new(ttl: Duration) -> Self {
+        let entries = (!ttl.is_zero()).then(|| {
+            Cache::builder()
+                .max_capacity(MAX_CACHED_PRODUCTS)
+                .time_to_live(ttl)
+                .build()
+        });
+        Self { entries }
+    }
+
+    /// Build the cache from `PRODUCT_CACHE_TTL_SECS` (unset, invalid or 0 disables it)
+    pub fn from_flags(flags: &FeatureFlags) -> Self {
+        let ttl_secs = flags.product_cache_ttl_secs;
+        if ttl_secs > 0 {
+            tracing::info!("Product cache enabled (TTL {}s)", ttl_secs);
+        }
+        Self::new(Duration::from_secs(ttl_secs))
+    }
+
+    /// Return the cached product, or run `load` and cache its result
+    ///
+    /// Errors (such as not found) are never cached.
+    pub async fn get_or_load<F>(&self, id: Uuid, load: F) -> Result<Product, ApiError>
+    where
+        F: Future<Output = Result<Product, ApiError>>,
+    {
+        let Some(entries) = &self.entries else {
+            return load.await;
+        };
+
+        if let Some(product) = entries.get(&id) {
+            return Ok(product);
+        }
+
+        let product = load.await?;
+        entries.insert(id, product.clone());
+        Ok(product)
+    }
+
+    /// Drop the cached copy of a product after it changed or was deleted
+    pub fn invalidate(&self, id: Uuid) {
+        if let Some(entries) = &self.entries {
+            entries.invalidate(&id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn product(id: Uuid, stock: i32) -> Product {
+        let now = Utc::now();
+        Product {
+            id,
+            sku: None,
+            name: "Laptop".to_string(),
+            description: None,
+            price: 15000000.0,
+            formatted_price: None,
+            stock,
+            category: None,
+            is_active: true,
+            is_featured: false,
+            version: 1,
+            owner_id: None,
+            tags: Vec::new(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    // Stand-in for the repository that counts how often the database is hit
+    struct SpyRepository {
+        calls: AtomicUsize,
+        stock: AtomicUsize,
+    }
+
+    impl SpyRepository {
+        fn new() -> Self {
+            Self {
+                calls: AtomicUsize::new(0),
+                stock: AtomicUsize::new(10),
+            }
+        }
+
+        async fn find_by_id(&self, id: Uuid) -> Result<Product, ApiError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(product(id, self.stock.load(Ordering::SeqCst) as i32))
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_second_read_hits_the_cache() {
+        let cache = ProductCache::new(Duration::from_secs(60));
+        let repository = SpyRepository::new();
+        let id = Uuid::new_v4();
+
+        cache
+            .get_or_load(id, repository.find_by_id(id))
+            .await
+            .unwrap();
+        let product = cache
+            .get_or_load(id, repository.find_by_id(id))
+            .await
+            .unwrap();
+
+        assert_eq!(product.id, id);
+        assert_eq!(repository.calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_busts_the_cached_product() {
+        let cache = ProductCache::new(Duration::from_secs(60));
+        let repository = SpyRepository::new();
+        let id = Uuid::new_v4();
+        cache
+            .get_or_load(id, repository.find_by_id(id))
+            .await
+            .unwrap();
+
+        // An update changes the row and invalidates the entry
+        repository.stock.store(4, Ordering::SeqCst);
+        cache.invalidate(id);
+
+        let product = cache
+            .get_or_load(id, repository.find_by_id(id))
+            .await
+            .unwrap();
+        assert_eq!(product.stock, 4);
+        assert_eq!(repository.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_zero_ttl_disables_caching() {
+        let cache = ProductCache::new(Duration::ZERO);
+        let repository = SpyRepository::new();
+        let id = Uuid::new_v4();
+
+        cache
+            .get_or_load(id, repository.find_by_id(id))
+            .await
+            .unwrap();
+        cache
+            .get_or_load(id, repository.find_by_id(id))
+            .await
+            .unwrap();
+
+        assert_eq!(repository.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_not_cached() {
+        let cache = ProductCache::new(Duration::from_secs(60));
+        let id = Uuid::new_v4();
+
+        let missing = cache
+            .get_or_load(id, async {
+                Err(ApiError::NotFound("Product not found".to_string()))
+            })
+            .await;
+        assert!(missing.is_err());
+
+        let repository = SpyRepository::new();
+        assert!(
+            cache
+                .get_or_load(id, repository.find_by_id(id))
+                .await
+                .is_ok()
+        );
+        assert_eq!(repository.calls(), 1);
+    }
+}
@@ -1,40 +1,369 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use uuid::Uuid;
 
+use crate::utils::{deserialize_nullable, number::deserialize_decimal, timestamp};
+
+/// Field names that can be requested via the `fields` query parameter
+pub const PRODUCT_FIELDS: [&str; 15] = [
+    "id",
+    "sku",
+    "name",
+    "description",
+    "price",
+    "formatted_price",
+    "stock",
+    "category",
+    "is_active",
+    "is_featured",
+    "version",
+    "owner_id",
+    "tags",
+    "created_at",
+    "updated_at",
+];
+
+/// Fields that a JSON patch may add, replace or remove
+pub const PATCHABLE_FIELDS: [&str; 8] = [
+    "sku",
+    "name",
+    "description",
+    "price",
+    "stock",
+    "category",
+    "is_active",
+    "is_featured",
+];
+
 /// Product model representing a product in the database
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Product {
     pub id: Uuid,                    // Unique identifier
+    pub sku: Option<String>,         // Optional stock keeping unit
     pub name: String,                // Product name
     pub description: Option<String>, // Optional product description
     pub price: f64,                  // Product price
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub formatted_price: Option<String>, // Price formatted in DEFAULT_CURRENCY, when configured
     pub stock: i32,                  // Available inventory
     pub category: Option<String>,    // Optional product category
     pub is_active: bool,             // Whether product is active
-    pub created_at: DateTime<Utc>,   // Creation timestamp
-    pub updated_at: DateTime<Utc>,   // Last update timestamp
+    #[serde(default)]
+    pub is_featured: bool, // Whether storefronts highlight the product
+    pub version: i32,                // Optimistic concurrency version
+    pub owner_id: Option<Uuid>,      // User who created the product (None for legacy rows)
+    #[serde(default)]
+    pub tags: Vec<String>, // Tag names, sorted alphabetically
+    #[serde(with = "timestamp")]
+    pub created_at: DateTime<Utc>, // Creation timestamp
+    #[serde(with = "timestamp")]
+    pub updated_at: DateTime<Utc>, // Last update timestamp
+}
+
+impl Product {
+    /// Editable fields that differ from `before`, with old and new values as text
+    pub fn changes_from(&self, before: &Product) -> Vec<FieldChange> {
+        let values = [
+            ("sku", before.sku.clone(), self.sku.clone()),
+            ("name", Some(before.name.clone()), Some(self.name.clone())),
+            (
+                "description",
+                before.description.clone(),
+                self.description.clone(),
+            ),
+            (
+                "price",
+                Some(before.price.to_string()),
+                Some(self.price.to_string()),
+            ),
+            (
+                "stock",
+                Some(before.stock.to_string()),
+                Some(self.stock.to_string()),
+            ),
+            ("category", before.category.clone(), self.category.clone()),
+            (
+                "is_active",
+                Some(before.is_active.to_string()),
+                Some(self.is_active.to_string()),
+            ),
+            (
+                "is_featured",
+                Some(before.is_featured.to_string()),
+                Some(self.is_featured.to_string()),
+            ),
+        ];
+
+        values
+            .into_iter()
+            .filter(|(_, old_value, new_value)| old_value != new_value)
+            .map(|(field, old_value, new_value)| FieldChange {
+                field,
+                old_value,
+                new_value,
+            })
+            .collect()
+    }
+}
+
+/// One editable product field whose value changed
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    pub field: &'static str, // Field name as used in the JSON representation
+    pub old_value: Option<String>, // Value before the change (None for null)
+    pub new_value: Option<String>, // Value after the change (None for null)
+}
+
+/// Recorded change of one product field
+#[derive(Debug, Clone, Serialize)]
+pub struct ProductAuditEntry {
+    pub id: i64,                   // Sequential audit ID
+    pub product_id: Uuid,          // Product that was updated
+    pub field: String,             // Field that changed
+    pub old_value: Option<String>, // Value before the update
+    pub new_value: Option<String>, // Value after the update
+    pub changed_by: Option<Uuid>,  // User who made the change (None for seeds or deleted users)
+    #[serde(with = "timestamp")]
+    pub changed_at: DateTime<Utc>, // When the change was made
 }
 
 /// DTO for creating a new product
 #[derive(Debug, Deserialize)]
 pub struct CreateProduct {
+    pub sku: Option<String>,         // Optional stock keeping unit
     pub name: String,                // Required product name
     pub description: Option<String>, // Optional product description
     pub price: f64,                  // Required product price
     pub stock: Option<i32>,          // Optional stock amount (defaults to 0)
     pub category: Option<String>,    // Optional product category
+    pub is_featured: Option<bool>,   // Optional featured flag (defaults to false)
+}
+
+impl CreateProduct {
+    /// Use `default` as the stock when the payload left it out
+    pub fn apply_default_stock(&mut self, default: i32) {
+        self.stock.get_or_insert(default);
+    }
 }
 
 /// DTO for updating an existing product
+///
+/// Nullable columns use `Option<Option<_>>`: an absent key leaves the value
+/// unchanged while an explicit `null` clears it.
 #[derive(Debug, Deserialize)]
 pub struct UpdateProduct {
-    pub name: Option<String>,        // Optional product name update
-    pub description: Option<String>, // Optional product description update
-    pub price: Option<f64>,          // Optional product price update
-    pub stock: Option<i32>,          // Optional stock amount update
-    pub category: Option<String>,    // Optional product category update
-    pub is_active: Option<bool>,     // Optional active status update
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub sku: Option<Option<String>>, // Optional SKU update (null clears)
+    pub name: Option<String>, // Optional product name update
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub description: Option<Option<String>>, // Optional description update (null clears)
+    pub price: Option<f64>,   // Optional product price update
+    pub stock: Option<i32>,   // Optional stock amount update
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub category: Option<Option<String>>, // Optional category update (null clears)
+    pub is_active: Option<bool>, // Optional active status update
+    pub is_featured: Option<bool>, // Optional featured flag update
+    pub version: i32,         // Version the client last read
+}
+
+/// Query parameters for updating a product
+#[derive(Debug, Deserialize)]
+pub struct UpdateProductQuery {
+    pub dry_run: Option<bool>, // Preview the result without saving it
+}
+
+/// DTO for deleting several products at once
+#[derive(Debug, Deserialize)]
+pub struct BulkDeleteProducts {
+    pub ids: Vec<Uuid>, // IDs of the products to delete
+}
+
+/// DTO for restoring a product deleted within the undo window
+#[derive(Debug, Deserialize)]
+pub struct UndoDeleteProduct {
+    pub undo_token: String, // Token returned by DELETE /api/products/{id}
+}
+
+/// DTO for creating several products in one request
+#[derive(Debug, Deserialize)]
+pub struct BulkCreateProducts {
+    pub products: Vec<CreateProduct>, // Products to create, in order
+}
+
+/// How `POST /api/products/bulk` treats products that fail
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BulkCreateMode {
+    #[default]
+    AllOrNothing, // One failure rolls back the whole batch
+    BestEffort, // Create what can be created and report the rest
+}
+
+/// Query parameters for bulk create
+#[derive(Debug, Default, Deserialize)]
+pub struct BulkCreateQuery {
+    #[serde(default)]
+    pub mode: BulkCreateMode,
+}
+
+/// A product from a bulk create that was not created
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BulkCreateFailure {
+    pub index: usize,   // Position of the product in the request
+    pub reason: String, // Why it was rejected
+}
+
+/// Result of validating a create payload without saving it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProductValidation {
+    pub valid: bool, // Whether the payload would pass create validation
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub problems: Vec<ValidationProblem>, // Every failed check, in field order
+}
+
+/// One failed check of a product payload
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValidationProblem {
+    pub field: &'static str, // Payload field the problem is about
+    pub message: String,     // Same message create would return
+}
+
+/// DTO for fetching several products by ID
+#[derive(Debug, Deserialize)]
+pub struct BatchGetProducts {
+    pub ids: Vec<Uuid>, // IDs of the products to fetch
+}
+
+/// DTO for comparing products side by side
+#[derive(Debug, Deserialize)]
+pub struct CompareProducts {
+    pub ids: Vec<Uuid>, // Products to compare, in display order
+}
+
+/// Products in the requested order with a summary of how they compare
+#[derive(Debug, Clone, Serialize)]
+pub struct ProductComparison {
+    pub products: Vec<Product>,
+    pub summary: ComparisonSummary,
+}
+
+/// Which compared product wins on price and stock
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ComparisonSummary {
+    pub cheapest: Uuid,    // Lowest price (first in request order on ties)
+    pub most_stock: Uuid,  // Highest stock (first in request order on ties)
+    pub price_spread: f64, // Highest price minus lowest price
+}
+
+impl ComparisonSummary {
+    /// Summarize the products, or `None` when there are fewer than two to compare
+    pub fn from_products(products: &[Product]) -> Option<Self> {
+        if products.len() < 2 {
+            return None;
+        }
+
+        let cheapest = products.iter().reduce(|best, product| {
+            if product.price < best.price {
+                product
+            } else {
+                best
+            }
+        })?;
+        let priciest = products.iter().reduce(|best, product| {
+            if product.price > best.price {
+                product
+            } else {
+                best
+            }
+        })?;
+        let most_stock = products.iter().reduce(|best, product| {
+            if product.stock > best.stock {
+                product
+            } else {
+                best
+            }
+        })?;
+
+        Some(Self {
+            cheapest: cheapest.id,
+            most_stock: most_stock.id,
+            price_spread: priciest.price - cheapest.price,
+        })
+    }
+}
+
+/// DTO for attaching tags to a product
+#[derive(Debug, Deserialize)]
+pub struct AddProductTags {
+    pub tags: Vec<String>, // Tag names; unknown tags are created on demand
+}
+
+/// DTO for moving every product from one category to another
+#[derive(Debug, Deserialize)]
+pub struct RecategorizeProducts {
+    pub from: String, // Current category name
+    pub to: String,   // New category name
+}
+
+/// DTO for changing the price of every product in a category by a percentage
+#[derive(Debug, Deserialize)]
+pub struct AdjustPrices {
+    pub category: String, // Category whose products are repriced
+    pub percent: f64,     // Percentage change, e.g. -10 for a 10% discount
+}
+
+/// DTO for moving stock from one product to another
+#[derive(Debug, Deserialize)]
+pub struct TransferStock {
+    pub from: Uuid,    // Product the stock is taken from
+    pub to: Uuid,      // Product the stock is added to
+    pub quantity: i32, // Units to move (must be positive)
+}
+
+/// How product categories are validated, chosen via `CATEGORY_MODE`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CategoryMode {
+    /// Any non-empty category is accepted as free text
+    Open,
+    /// Only the configured categories are accepted
+    Closed(Vec<String>),
+}
+
+impl CategoryMode {
+    /// Build the mode from `CATEGORY_MODE` and a comma-separated category list
+    ///
+    /// Closed mode without any categories would reject everything, so it falls back to open.
+    pub fn parse(mode: &str, categories: &str) -> Self {
+        if !mode.trim().eq_ignore_ascii_case("closed") {
+            return Self::Open;
+        }
+
+        let mut allowed: Vec<String> = Vec::new();
+        for category in categories.split(',').map(str::trim) {
+            if !category.is_empty() && !allowed.iter().any(|c| c.eq_ignore_ascii_case(category)) {
+                allowed.push(category.to_string());
+            }
+        }
+
+        if allowed.is_empty() {
+            tracing::warn!("CATEGORY_MODE=closed without CATEGORIES, accepting any category");
+            return Self::Open;
+        }
+
+        Self::Closed(allowed)
+    }
+
+    /// Map a category to its configured spelling, or `None` if closed mode does not know it
+    pub fn resolve(&self, category: &str) -> Option<String> {
+        match self {
+            Self::Open => Some(category.to_string()),
+            Self::Closed(allowed) => allowed
+                .iter()
+                .find(|known| known.eq_ignore_ascii_case(category))
+                .cloned(),
+        }
+    }
 }
 
 /// Filter criteria for querying products
@@ -42,9 +371,283 @@ pub struct UpdateProduct {
 pub struct ProductFilter {
     pub name: Option<String>,     // Filter by name (partial match)
     pub category: Option<String>, // Filter by category (exact match)
-    pub min_price: Option<f64>,   // Filter by minimum price
-    pub max_price: Option<f64>,   // Filter by maximum price
+    pub tag: Option<String>,      // Filter by tag name
+    #[serde(default, deserialize_with = "deserialize_decimal")]
+    pub min_price: Option<f64>, // Filter by minimum price (`19.99` or `19,99`)
+    #[serde(default, deserialize_with = "deserialize_decimal")]
+    pub max_price: Option<f64>, // Filter by maximum price (`19.99` or `19,99`)
     pub is_active: Option<bool>,  // Filter by active status
+    pub featured: Option<bool>,   // Filter by featured flag
     pub limit: Option<i64>,       // Maximum number of results
     pub offset: Option<i64>,      // Number of results to skip
+    pub fields: Option<String>,   // Comma-separated list of fields to return
+    pub mine: Option<bool>,       // Only return products owned by the caller
+    pub cursor: Option<String>,   // Opaque keyset cursor (empty starts from the first page)
+    #[serde(skip)]
+    pub owner_id: Option<Uuid>, // Owner to filter by (resolved from `mine`)
+    #[serde(skip)]
+    pub after: Option<(String, Uuid)>, // Last seen (name, id) decoded from `cursor`
+}
+
+/// Query parameters for endpoints that only accept a result limit
+#[derive(Debug, Deserialize)]
+pub struct LimitQuery {
+    pub limit: Option<i64>, // Maximum number of results (endpoint-specific default)
+}
+
+impl LimitQuery {
+    /// The requested limit, falling back to `default` and clamped to `1..=max`
+    pub fn resolve(&self, default: i64, max: i64) -> i64 {
+        self.limit.unwrap_or(default).clamp(1, max)
+    }
+}
+
+/// Query parameters for full-text product search
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    pub q: Option<String>,  // Search term
+    pub limit: Option<i64>, // Maximum number of results
+}
+
+/// Query parameters for the price histogram
+#[derive(Debug, Deserialize)]
+pub struct PriceBucketQuery {
+    pub buckets: Option<i32>, // Number of equal-width buckets (default 5, max 20)
+}
+
+/// One bucket of the active-product price histogram
+#[derive(Debug, Serialize, PartialEq)]
+pub struct PriceBucket {
+    pub min: f64,   // Lower price bound (inclusive)
+    pub max: f64,   // Upper price bound (inclusive for the last bucket)
+    pub count: i64, // Number of active products in the bucket
+}
+
+/// Aggregate product statistics
+#[derive(Debug, Serialize)]
+pub struct ProductStats {
+    pub total: i64,                         // Total number of products
+    pub active: i64,                        // Number of active products
+    pub inactive: i64,                      // Number of inactive products
+    pub total_stock: i64,                   // Sum of stock across all products
+    pub avg_price: f64,                     // Average price computed in the database
+    pub by_category: BTreeMap<String, i64>, // Product count per category
+}
+
+/// Dashboard summary of the products owned by one user
+#[derive(Debug, Serialize)]
+pub struct OwnerSummary {
+    pub product_count: i64,     // Number of products owned
+    pub active_count: i64,      // Number of those that are active
+    pub total_stock_value: f64, // Sum of price * stock across owned products
+}
+
+/// Change notification published whenever a product is mutated
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProductEvent {
+    Created { product: Product }, // A new product was created
+    Updated { product: Product }, // An existing product was updated
+    Deleted { id: Uuid },         // A product was deleted
+}
+
+impl ProductEvent {
+    /// Event for an upsert: `Created` when the row was inserted, `Updated` otherwise
+    pub fn upserted(product: Product, created: bool) -> Self {
+        if created {
+            Self::Created { product }
+        } else {
+            Self::Updated { product }
+        }
+    }
+
+    /// Event name used for the SSE `event:` field
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Created { .. } => "created",
+            Self::Updated { .. } => "updated",
+            Self::Deleted { .. } => "deleted",
+        }
+    }
+
+    /// ID of the product the event refers to
+    pub fn aggregate_id(&self) -> Uuid {
+        match self {
+            Self::Created { product } | Self::Updated { product } => product.id,
+            Self::Deleted { id } => *id,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_product_stats_response_shape() {
+        let stats = ProductStats {
+            total: 3,
+            active: 2,
+            inactive: 1,
+            total_stock: 15,
+            avg_price: 250.5,
+            by_category: BTreeMap::from([("Kamera".to_string(), 1), ("Audio".to_string(), 2)]),
+        };
+
+        assert_eq!(
+            serde_json::to_string(&stats).unwrap(),
+            r#"{"total":3,"active":2,"inactive":1,"total_stock":15,"avg_price":250.5,"by_category":{"Audio":2,"Kamera":1}}"#
+        );
+    }
+
+    #[test]
+    fn test_category_mode_open_passes_categories_through() {
+        let mode = CategoryMode::parse("open", "Audio, Elektronik");
+        assert_eq!(mode, CategoryMode::Open);
+        assert_eq!(mode.resolve("Anything"), Some("Anything".to_string()));
+    }
+
+    #[test]
+    fn test_category_mode_closed_rejects_unknown_categories() {
+        let mode = CategoryMode::parse("closed", "Audio, Elektronik,,audio");
+        assert_eq!(
+            mode,
+            CategoryMode::Closed(vec!["Audio".to_string(), "Elektronik".to_string()])
+        );
+        assert_eq!(mode.resolve("elektronik"), Some("Elektronik".to_string()));
+        assert_eq!(mode.resolve("Mainan"), None);
+    }
+
+    #[test]
+    fn test_category_mode_closed_without_categories_falls_back_to_open() {
+        assert_eq!(CategoryMode::parse("CLOSED", " , "), CategoryMode::Open);
+    }
+
+    fn product() -> Product {
+        let now = Utc::now();
+        Product {
+            id: Uuid::new_v4(),
+            sku: None,
+            name: "Laptop".to_string(),
+            description: None,
+            price: 15000000.0,
+            formatted_price: None,
+            stock: 10,
+            category: Some("Electronics".to_string()),
+            is_active: true,
+            is_featured: false,
+            version: 1,
+            owner_id: None,
+            tags: Vec::new(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn test_changes_from_records_name_and_stock_changes() {
+        let before = product();
+        let after = Product {
+            name: "Gaming Laptop".to_string(),
+            stock: 8,
+            version: 2,
+            ..before.clone()
+        };
+
+        assert_eq!(
+            after.changes_from(&before),
+            vec![
+                FieldChange {
+                    field: "name",
+                    old_value: Some("Laptop".to_string()),
+                    new_value: Some("Gaming Laptop".to_string()),
+                },
+                FieldChange {
+                    field: "stock",
+                    old_value: Some("10".to_string()),
+                    new_value: Some("8".to_string()),
+                },
+            ]
+        );
+        assert!(before.changes_from(&before).is_empty());
+    }
+
+    #[test]
+    fn test_changes_from_records_featured_flag() {
+        let before = product();
+        let after = Product {
+            is_featured: true,
+            ..before.clone()
+        };
+
+        assert_eq!(
+            after.changes_from(&before),
+            vec![FieldChange {
+                field: "is_featured",
+                old_value: Some("false".to_string()),
+                new_value: Some("true".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_changes_from_renders_cleared_values_as_none() {
+        let before = product();
+        let after = Product {
+            category: None,
+            ..before.clone()
+        };
+
+        assert_eq!(
+            after.changes_from(&before),
+            vec![FieldChange {
+                field: "category",
+                old_value: Some("Electronics".to_string()),
+                new_value: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_update_product_omitted_fields_are_unchanged() {
+        let update: UpdateProduct = serde_json::from_str(r#"{"version": 1}"#).unwrap();
+        assert_eq!(update.description, None);
+        assert_eq!(update.category, None);
+    }
+
+    #[test]
+    fn test_update_product_null_fields_are_cleared() {
+        let update: UpdateProduct =
+            serde_json::from_str(r#"{"description": null, "category": null, "version": 1}"#)
+                .unwrap();
+        assert_eq!(update.description, Some(None));
+        assert_eq!(update.category, Some(None));
+    }
+
+    #[test]
+    fn test_update_product_values_are_set() {
+        let update: UpdateProduct =
+            serde_json::from_str(r#"{"description": "New", "category": "Audio", "version": 1}"#)
+                .unwrap();
+        assert_eq!(update.description, Some(Some("New".to_string())));
+        assert_eq!(update.category, Some(Some("Audio".to_string())));
+    }
+
+    #[test]
+    fn test_product_timestamps_serialize_with_second_precision() {
+        let created_at = Utc.with_ymd_and_hms(2025, 4, 1, 9, 30, 15).unwrap();
+        let product = Product {
+            created_at: created_at + chrono::Duration::nanoseconds(123_456_789),
+            updated_at: created_at,
+            ..product()
+        };
+
+        let json = serde_json::to_value(&product).unwrap();
+        assert_eq!(json["created_at"], "2025-04-01T09:30:15Z");
+        assert_eq!(json["updated_at"], "2025-04-01T09:30:15Z");
+
+        let parsed: Product = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.created_at, created_at);
+    }
 }
@@ -1,22 +1,102 @@
 use crate::{
-    core::{db::DbPool, error::ApiError},
-    modules::product::model::{CreateProduct, Product, ProductFilter, UpdateProduct},
-    utils::offset_to_chrono,
+    core::{
+        config::FeatureFlags,
+        db::{DbPool, DbPools, timed, with_transaction},
+        error::ApiError,
+        pagination::SortOrder,
+    },
+    modules::{
+        outbox::repository::record_event,
+        product::model::{
+            BulkCreateMode, CreateProduct, OwnerSummary, PriceBucket, Product, ProductAuditEntry,
+            ProductEvent, ProductFilter, ProductStats, UpdateProduct,
+        },
+    },
+    utils::{default_currency, format_currency, offset_to_chrono},
 };
 use bigdecimal::BigDecimal;
-use sqlx::{FromRow, QueryBuilder, Row, postgres::PgRow, query, query_as};
+use chrono::{DateTime, Duration, SubsecRound, Utc};
+use sqlx::{Acquire, FromRow, QueryBuilder, Row, postgres::PgRow, query, query_as, query_scalar};
 use std::str::FromStr;
 use tracing::{info, warn};
 use uuid::Uuid;
 
 // Konstanta SQL untuk menghindari duplikasi
-const SELECT_PRODUCT_FIELDS: &str =
-    "id, name, description, price, stock, category, is_active, created_at, updated_at";
-const SELECT_PRODUCT_BASE: &str = "SELECT id, name, description, price, stock, category, is_active, created_at, updated_at FROM products";
+const QUERY_INSERT_AUDIT: &str = "INSERT INTO product_audit (product_id, field, old_value, new_value, changed_by) VALUES ($1, $2, $3, $4, $5)";
+const QUERY_LIST_AUDIT: &str = "SELECT id, product_id, field, old_value, new_value, changed_by, changed_at FROM product_audit WHERE product_id = $1 ORDER BY id DESC LIMIT $2 OFFSET $3";
+const QUERY_ADD_FAVORITE: &str =
+    "INSERT INTO favorites (user_id, product_id) VALUES ($1, $2) ON CONFLICT DO NOTHING";
+const QUERY_REMOVE_FAVORITE: &str = "DELETE FROM favorites WHERE user_id = $1 AND product_id = $2";
+// Tag produk diambil lewat subquery supaya setiap query produk tetap satu baris per produk
+const SELECT_PRODUCT_FIELDS: &str = "id, sku, name, description, price, stock, category, is_active, is_featured, version, owner_id, created_at, updated_at, ARRAY(SELECT t.name::TEXT FROM product_tags pt JOIN tags t ON t.id = pt.tag_id WHERE pt.product_id = products.id ORDER BY t.name) AS tags";
+// Produk unggulan harus aktif juga; is_featured sendiri tidak mengubah status aktif
+const FEATURED_PRODUCTS_CLAUSE: &str =
+    "AND is_featured = true AND is_active = true ORDER BY updated_at DESC, id ASC LIMIT $1";
+// Setiap urutan diakhiri id supaya hasil dengan nilai sama tetap stabil
+const RECENT_PRODUCTS_CLAUSE: &str = "ORDER BY updated_at DESC, id ASC LIMIT $1";
+const SEARCH_RANKED_CLAUSE: &str = "AND search_vector @@ websearch_to_tsquery('simple', $1)
+    ORDER BY ts_rank(search_vector, websearch_to_tsquery('simple', $1)) DESC, name ASC, id ASC
+    LIMIT $2";
+const RELATED_PRODUCTS_CLAUSE: &str = "AND category_slug = $1 AND id <> $2 AND is_active = true
+    ORDER BY ABS(price - $3::DECIMAL), name ASC, id ASC
+    LIMIT $4";
+const SELECT_PRODUCT_BASE: &str = "SELECT id, sku, name, description, price, stock, category, is_active, is_featured, version, owner_id, created_at, updated_at, ARRAY(SELECT t.name::TEXT FROM product_tags pt JOIN tags t ON t.id = pt.tag_id WHERE pt.product_id = products.id ORDER BY t.name) AS tags FROM products WHERE deleted_at IS NULL";
+
+// Perubahan tag ikut menaikkan version supaya ETag berubah walaupun dalam detik yang sama
+const QUERY_BUMP_AFTER_TAG_CHANGE: &str =
+    "UPDATE products SET version = version + 1, updated_at = NOW() WHERE id = $1";
+
+// Salinan tanpa SKU dan non-aktif; 248 + " (copy)" pas dengan name VARCHAR(255)
+const QUERY_DUPLICATE_PRODUCT: &str = "INSERT INTO products (name, description, price, stock, category, category_slug, is_active, owner_id)
+    SELECT LEFT(name, 248) || ' (copy)', description, price, stock, category, category_slug, false, $2
+    FROM products WHERE id = $1 AND deleted_at IS NULL";
+
+// Soft delete: baris tetap ada supaya audit, favorit dan pesanan tidak ikut terhapus
+const QUERY_SOFT_DELETE: &str = "UPDATE products
+    SET deleted_at = NOW(), delete_id = gen_random_uuid(), version = version + 1, updated_at = NOW()
+    WHERE id = $1 AND deleted_at IS NULL
+    RETURNING delete_id";
+// Selama produk terhapus, SKU-nya boleh dipakai produk lain
+const QUERY_SKU_TAKEN_BY_OTHER: &str = "SELECT EXISTS (
+    SELECT 1 FROM products p JOIN products other ON other.sku = p.sku
+    WHERE p.id = $1 AND other.id <> p.id AND other.deleted_at IS NULL)";
+
+// Statistik dihitung di database; COALESCE supaya katalog kosong tetap bernilai 0
+const QUERY_STATS_TOTALS: &str = "SELECT
+    COUNT(*) AS total,
+    COUNT(*) FILTER (WHERE is_active) AS active,
+    COUNT(*) FILTER (WHERE NOT is_active) AS inactive,
+    COALESCE(SUM(stock), 0)::BIGINT AS total_stock,
+    COALESCE(AVG(price), 0)::FLOAT8 AS avg_price
+    FROM products
+    WHERE deleted_at IS NULL";
+const QUERY_STATS_BY_CATEGORY: &str = "SELECT category, COUNT(*) AS count FROM products
+    WHERE deleted_at IS NULL AND category IS NOT NULL GROUP BY category";
+
+// Kolom yang boleh dipakai untuk PRODUCTS_DEFAULT_SORT
+pub(crate) const PRODUCT_SORT_COLUMNS: [&str; 5] =
+    ["name", "price", "stock", "created_at", "updated_at"];
 
 /// Repository untuk operasi database produk
 pub struct ProductRepository {
-    pool: DbPool,
+    pool: DbPool,            // Pool utama untuk operasi tulis
+    read_pool: DbPool,       // Pool untuk query baca (replica jika dikonfigurasi)
+    default_sort: SortOrder, // Urutan daftar produk (PRODUCTS_DEFAULT_SORT)
+}
+
+// Implementasi FromRow untuk ProductAuditEntry untuk konversi timestamp
+impl<'r> FromRow<'r, PgRow> for ProductAuditEntry {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        Ok(ProductAuditEntry {
+            id: row.try_get("id")?,
+            product_id: row.try_get("product_id")?,
+            field: row.try_get("field")?,
+            old_value: row.try_get("old_value")?,
+            new_value: row.try_get("new_value")?,
+            changed_by: row.try_get("changed_by")?,
+            changed_at: offset_to_chrono(row.try_get("changed_at")?),
+        })
+    }
 }
 
 // Implementasi FromRow untuk Product untuk menangani konversi tipe khusus
@@ -41,12 +121,18 @@ impl<'r> FromRow<'r, PgRow> for Product {
         // Mendapatkan field lainnya
         Ok(Product {
             id,
+            sku: row.try_get("sku")?,
             name,
             description,
             price,
+            formatted_price: default_currency().map(|currency| format_currency(price, currency)),
             stock: row.try_get("stock")?,
             category: row.try_get("category")?,
             is_active: row.try_get("is_active")?,
+            is_featured: row.try_get("is_featured")?,
+            version: row.try_get("version")?,
+            owner_id: row.try_get("owner_id")?,
+            tags: row.try_get("tags")?,
             created_at: offset_to_chrono(row.try_get("created_at")?),
             updated_at: offset_to_chrono(row.try_get("updated_at")?),
         })
@@ -55,15 +141,91 @@ impl<'r> FromRow<'r, PgRow> for Product {
 
 impl ProductRepository {
     /// Membuat repository produk baru
-    pub fn new(pool: DbPool) -> Self {
-        Self { pool }
+    pub fn new(pools: DbPools, flags: &FeatureFlags) -> Self {
+        Self {
+            pool: pools.write,
+            read_pool: pools.read,
+            default_sort: flags.products_default_sort,
+        }
     }
 
     /// Membuat produk baru di database
-    pub async fn create(&self, product: &CreateProduct) -> Result<Product, ApiError> {
+    pub async fn create(
+        &self,
+        product: &CreateProduct,
+        owner_id: Option<Uuid>,
+    ) -> Result<Product, ApiError> {
+        let mut tx = self.pool.begin().await.map_err(ApiError::Database)?;
+        let result = Self::insert_product(&mut tx, product, owner_id).await?;
+
+        tx.commit().await.map_err(ApiError::Database)?;
+        info!("Produk baru dibuat dengan ID: {}", result.id);
+        Ok(result)
+    }
+
+    /// Membuat banyak produk sekaligus dalam satu transaksi
+    ///
+    /// Mode all-or-nothing berhenti di produk pertama yang gagal dan membatalkan
+    /// semuanya; hasilnya berakhir dengan error produk itu dan tidak ada yang
+    /// tersimpan. Mode best-effort memasukkan setiap produk di savepoint sendiri,
+    /// jadi produk yang gagal hanya membatalkan dirinya dan sisanya tetap di-commit.
+    /// Hasil berurutan sesuai `products`.
+    pub async fn create_many(
+        &self,
+        products: &[CreateProduct],
+        owner_id: Uuid,
+        mode: BulkCreateMode,
+    ) -> Result<Vec<Result<Product, ApiError>>, ApiError> {
+        if products.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut tx = self.pool.begin().await.map_err(ApiError::Database)?;
+        let mut results = Vec::with_capacity(products.len());
+
+        for product in products {
+            let result = match mode {
+                BulkCreateMode::AllOrNothing => {
+                    Self::insert_product(&mut tx, product, Some(owner_id)).await
+                }
+                BulkCreateMode::BestEffort => {
+                    let mut savepoint = (&mut tx).begin().await.map_err(ApiError::Database)?;
+                    match Self::insert_product(&mut savepoint, product, Some(owner_id)).await {
+                        Ok(created) => savepoint
+                            .commit()
+                            .await
+                            .map(|_| created)
+                            .map_err(ApiError::Database),
+                        // Savepoint di-rollback saat di-drop
+                        Err(error) => Err(error),
+                    }
+                }
+            };
+
+            let failed = result.is_err();
+            results.push(result);
+            if failed && mode == BulkCreateMode::AllOrNothing {
+                return Ok(results);
+            }
+        }
+
+        tx.commit().await.map_err(ApiError::Database)?;
+        info!(
+            "{} produk dibuat secara bulk",
+            results.iter().filter(|result| result.is_ok()).count()
+        );
+        Ok(results)
+    }
+
+    /// Insert satu produk dan catat event outbox-nya di transaksi yang diberikan
+    async fn insert_product(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        product: &CreateProduct,
+        owner_id: Option<Uuid>,
+    ) -> Result<Product, ApiError> {
         let query_str = format!(
-            "INSERT INTO products (name, description, price, stock, category) 
-            VALUES ($1, $2, $3, $4, $5) 
+            "INSERT INTO products (name, description, price, stock, category, sku, owner_id, category_slug, is_featured) 
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) 
             RETURNING {SELECT_PRODUCT_FIELDS}"
         );
 
@@ -71,101 +233,986 @@ impl ProductRepository {
             .bind(&product.name)
             .bind(product.description.as_deref())
             .bind(product.price)
-            .bind(product.stock.unwrap_or(0))
+            .bind(resolved_stock(product)?)
             .bind(product.category.as_deref())
-            .fetch_one(&self.pool)
+            .bind(product.sku.as_deref())
+            .bind(owner_id)
+            .bind(product.category.as_deref().map(category_slug))
+            .bind(product.is_featured.unwrap_or(false))
+            .fetch_one(&mut **tx)
             .await
             .map_err(ApiError::Database)?;
 
-        info!("Produk baru dibuat dengan ID: {}", result.id);
+        Self::record_outbox(
+            tx,
+            &ProductEvent::Created {
+                product: result.clone(),
+            },
+        )
+        .await?;
+
         Ok(result)
     }
 
-    /// Mendapatkan produk berdasarkan ID
-    pub async fn find_by_id(&self, id: Uuid) -> Result<Product, ApiError> {
-        let query_str = format!("{SELECT_PRODUCT_BASE} WHERE id = $1");
+    /// Menduplikasi produk sebagai produk baru yang non-aktif
+    ///
+    /// Nama diberi akhiran " (copy)" dan SKU dikosongkan supaya tidak konflik.
+    pub async fn duplicate(&self, id: Uuid, owner_id: Uuid) -> Result<Product, ApiError> {
+        let query_str = format!("{QUERY_DUPLICATE_PRODUCT} RETURNING {SELECT_PRODUCT_FIELDS}");
 
-        query_as::<_, Product>(&query_str)
+        let mut tx = self.pool.begin().await.map_err(ApiError::Database)?;
+
+        let result = query_as::<_, Product>(&query_str)
             .bind(id)
-            .fetch_optional(&self.pool)
+            .bind(owner_id)
+            .fetch_optional(&mut *tx)
             .await
             .map_err(ApiError::Database)?
-            .ok_or_else(|| ApiError::NotFound(format!("Produk dengan ID {} tidak ditemukan", id)))
+            .ok_or_else(|| {
+                ApiError::NotFound(format!("Produk dengan ID {} tidak ditemukan", id))
+            })?;
+
+        Self::record_outbox(
+            &mut tx,
+            &ProductEvent::Created {
+                product: result.clone(),
+            },
+        )
+        .await?;
+
+        tx.commit().await.map_err(ApiError::Database)?;
+        info!("Produk {} diduplikasi menjadi {}", id, result.id);
+        Ok(result)
+    }
+
+    /// Membuat atau memperbarui produk berdasarkan SKU secara atomik
+    ///
+    /// Mengembalikan produk hasil dan `true` jika produk baru dibuat. Produk
+    /// yang sudah ada hanya diperbarui kalau dimiliki `owner_id` atau oleh admin.
+    pub async fn upsert_by_sku(
+        &self,
+        sku: &str,
+        product: &CreateProduct,
+        owner_id: Uuid,
+        is_admin: bool,
+    ) -> Result<(Product, bool), ApiError> {
+        // xmax = 0 hanya berlaku untuk baris yang baru di-insert
+        let query_str = format!(
+            "INSERT INTO products (name, description, price, stock, category, sku, owner_id, category_slug, is_featured)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $9, COALESCE($10, false))
+            ON CONFLICT (sku) WHERE deleted_at IS NULL DO UPDATE SET
+                name = EXCLUDED.name,
+                description = EXCLUDED.description,
+                price = EXCLUDED.price,
+                stock = EXCLUDED.stock,
+                category = EXCLUDED.category,
+                category_slug = EXCLUDED.category_slug,
+                is_featured = COALESCE($10, products.is_featured),
+                version = products.version + 1,
+                updated_at = NOW()
+            WHERE products.owner_id = $7 OR $8
+            RETURNING {SELECT_PRODUCT_FIELDS}, (xmax = 0) AS inserted"
+        );
+
+        let mut tx = self.pool.begin().await.map_err(ApiError::Database)?;
+
+        let row = query(&query_str)
+            .bind(&product.name)
+            .bind(product.description.as_deref())
+            .bind(product.price)
+            .bind(resolved_stock(product)?)
+            .bind(product.category.as_deref())
+            .bind(sku)
+            .bind(owner_id)
+            .bind(is_admin)
+            .bind(product.category.as_deref().map(category_slug))
+            .bind(product.is_featured)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(ApiError::Database)?
+            // Tidak ada baris berarti konflik dengan produk milik pengguna lain
+            .ok_or_else(|| {
+                ApiError::Forbidden("You do not have permission to modify this product".to_string())
+            })?;
+
+        let result = Product::from_row(&row).map_err(ApiError::Database)?;
+        let created: bool = row.try_get("inserted").map_err(ApiError::Database)?;
+
+        Self::record_outbox(&mut tx, &ProductEvent::upserted(result.clone(), created)).await?;
+
+        tx.commit().await.map_err(ApiError::Database)?;
+
+        info!(
+            "Produk dengan SKU {} berhasil {}",
+            sku,
+            if created { "dibuat" } else { "diperbarui" }
+        );
+        Ok((result, created))
+    }
+
+    /// Mendapatkan produk berdasarkan ID
+    pub async fn find_by_id(&self, id: Uuid) -> Result<Product, ApiError> {
+        let query_str = format!("{SELECT_PRODUCT_BASE} AND id = $1");
+
+        timed(
+            "products.find_by_id",
+            query_as::<_, Product>(&query_str)
+                .bind(id)
+                .fetch_optional(&self.read_pool),
+        )
+        .await
+        .map_err(ApiError::Database)?
+        .ok_or_else(|| ApiError::NotFound(format!("Produk dengan ID {} tidak ditemukan", id)))
+    }
+
+    /// Mendapatkan beberapa produk sekaligus; ID yang tidak ada diabaikan dan urutan tidak dijamin
+    pub async fn find_by_ids(&self, ids: &[Uuid]) -> Result<Vec<Product>, ApiError> {
+        let query_str = format!("{SELECT_PRODUCT_BASE} AND id = ANY($1)");
+
+        timed(
+            "products.find_by_ids",
+            query_as::<_, Product>(&query_str)
+                .bind(ids)
+                .fetch_all(&self.read_pool),
+        )
+        .await
+        .map_err(ApiError::Database)
+    }
+
+    /// Mencari produk berdasarkan SKU
+    pub async fn find_by_sku(&self, sku: &str) -> Result<Option<Product>, ApiError> {
+        let query_str = format!("{SELECT_PRODUCT_BASE} AND sku = $1");
+
+        timed(
+            "products.find_by_sku",
+            query_as::<_, Product>(&query_str)
+                .bind(sku)
+                .fetch_optional(&self.read_pool),
+        )
+        .await
+        .map_err(ApiError::Database)
+    }
+
+    /// Mencari produk berdasarkan nama persis; kalau ada beberapa, yang paling lama dibuat
+    pub async fn find_by_name(&self, name: &str) -> Result<Option<Product>, ApiError> {
+        let query_str =
+            format!("{SELECT_PRODUCT_BASE} AND name = $1 ORDER BY created_at, id LIMIT 1");
+
+        timed(
+            "products.find_by_name",
+            query_as::<_, Product>(&query_str)
+                .bind(name)
+                .fetch_optional(&self.read_pool),
+        )
+        .await
+        .map_err(ApiError::Database)
     }
 
     /// Daftar produk dengan filter opsional
     pub async fn list(&self, filter: &ProductFilter) -> Result<Vec<Product>, ApiError> {
         // Mulai membangun query SQL dinamis
         let mut query_builder = QueryBuilder::new(SELECT_PRODUCT_BASE);
-        query_builder.push(" WHERE 1=1");
 
         // Terapkan filter
         Self::apply_filters(&mut query_builder, filter);
 
-        // Terapkan pengurutan, limit dan offset
-        query_builder.push(" ORDER BY name ASC");
+        // Keyset pagination: lanjutkan setelah (name, id) terakhir yang dilihat
+        if let Some((name, id)) = &filter.after {
+            query_builder.push(" AND (name, id) > (");
+            query_builder.push_bind(name);
+            query_builder.push(", ");
+            query_builder.push_bind(id);
+            query_builder.push(")");
+        }
+
+        // Terapkan pengurutan, limit dan offset; cursor selalu berbasis (name, id)
+        let sort = if filter.cursor.is_some() {
+            SortOrder::asc("name")
+        } else {
+            self.default_sort
+        };
+        query_builder.push(" ORDER BY ");
+        query_builder.push(sort.to_sql());
 
         if let Some(limit) = filter.limit {
             query_builder.push(" LIMIT ");
             query_builder.push_bind(limit);
         }
 
-        if let Some(offset) = filter.offset {
+        // Offset hanya dipakai kalo tidak pakai cursor
+        if let (Some(offset), None) = (filter.offset, &filter.cursor) {
             query_builder.push(" OFFSET ");
             query_builder.push_bind(offset);
         }
 
         // Eksekusi query dan konversi hasil langsung ke struct Product
-        query_builder
-            .build_query_as::<Product>()
-            .fetch_all(&self.pool)
+        timed(
+            "products.list",
+            query_builder
+                .build_query_as::<Product>()
+                .fetch_all(&self.read_pool),
+        )
+        .await
+        .map_err(ApiError::Database)
+    }
+
+    /// Menghitung produk yang cocok dengan filter daftar tanpa mengambil barisnya
+    ///
+    /// Limit, offset, cursor dan pengurutan diabaikan.
+    pub async fn count(&self, filter: &ProductFilter) -> Result<i64, ApiError> {
+        let mut query_builder = Self::count_query(filter);
+
+        timed(
+            "products.count",
+            query_builder
+                .build_query_scalar::<i64>()
+                .fetch_one(&self.read_pool),
+        )
+        .await
+        .map_err(ApiError::Database)
+    }
+
+    /// Riwayat perubahan field sebuah produk, yang terbaru lebih dulu
+    pub async fn list_audit(
+        &self,
+        product_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ProductAuditEntry>, ApiError> {
+        timed(
+            "products.list_audit",
+            query_as::<_, ProductAuditEntry>(QUERY_LIST_AUDIT)
+                .bind(product_id)
+                .bind(limit)
+                .bind(offset)
+                .fetch_all(&self.read_pool),
+        )
+        .await
+        .map_err(ApiError::Database)
+    }
+
+    /// Menandai produk sebagai favorit pengguna
+    ///
+    /// Idempoten: mengembalikan `false` kalau produk sudah jadi favorit sebelumnya.
+    pub async fn add_favorite(&self, user_id: Uuid, product_id: Uuid) -> Result<bool, ApiError> {
+        let result = query(QUERY_ADD_FAVORITE)
+            .bind(user_id)
+            .bind(product_id)
+            .execute(&self.pool)
             .await
-            .map_err(ApiError::Database)
+            .map_err(|err| match &err {
+                // Produk dihapus di antara pengecekan dan insert
+                sqlx::Error::Database(db_err) if db_err.is_foreign_key_violation() => {
+                    ApiError::NotFound(format!("Produk dengan ID {} tidak ditemukan", product_id))
+                }
+                _ => ApiError::Database(err),
+            })?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Menghapus produk dari favorit pengguna, mengembalikan `false` kalau memang bukan favorit
+    pub async fn remove_favorite(&self, user_id: Uuid, product_id: Uuid) -> Result<bool, ApiError> {
+        let result = query(QUERY_REMOVE_FAVORITE)
+            .bind(user_id)
+            .bind(product_id)
+            .execute(&self.pool)
+            .await
+            .map_err(ApiError::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Daftar produk favorit pengguna, yang terakhir ditandai lebih dulu
+    pub async fn list_favorites(
+        &self,
+        user_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Product>, ApiError> {
+        // Favorit diambil lewat subquery supaya kolom created_at tidak ambigu
+        let query_str = format!(
+            "SELECT {SELECT_PRODUCT_FIELDS} FROM products
+            JOIN (SELECT product_id, created_at AS favorited_at FROM favorites WHERE user_id = $1) f
+                ON f.product_id = products.id
+            WHERE products.deleted_at IS NULL
+            ORDER BY f.favorited_at DESC, products.id
+            LIMIT $2 OFFSET $3"
+        );
+
+        timed(
+            "products.list_favorites",
+            query_as::<_, Product>(&query_str)
+                .bind(user_id)
+                .bind(limit)
+                .bind(offset)
+                .fetch_all(&self.read_pool),
+        )
+        .await
+        .map_err(ApiError::Database)
+    }
+
+    /// Daftar produk unggulan yang aktif, yang terakhir diperbarui lebih dulu
+    pub async fn list_featured(&self, limit: i64) -> Result<Vec<Product>, ApiError> {
+        let query_str = format!("{SELECT_PRODUCT_BASE} {FEATURED_PRODUCTS_CLAUSE}");
+
+        timed(
+            "products.list_featured",
+            query_as::<_, Product>(&query_str)
+                .bind(limit)
+                .fetch_all(&self.read_pool),
+        )
+        .await
+        .map_err(ApiError::Database)
+    }
+
+    /// Daftar produk yang terakhir diperbarui
+    pub async fn list_recent(&self, limit: i64) -> Result<Vec<Product>, ApiError> {
+        let query_str = format!("{SELECT_PRODUCT_BASE} {RECENT_PRODUCTS_CLAUSE}");
+
+        timed(
+            "products.list_recent",
+            query_as::<_, Product>(&query_str)
+                .bind(limit)
+                .fetch_all(&self.read_pool),
+        )
+        .await
+        .map_err(ApiError::Database)
+    }
+
+    /// Mencari produk dengan full-text search, diurutkan berdasarkan relevansi
+    pub async fn search_ranked(&self, term: &str, limit: i64) -> Result<Vec<Product>, ApiError> {
+        let query_str = format!("{SELECT_PRODUCT_BASE} {SEARCH_RANKED_CLAUSE}");
+
+        timed(
+            "products.search_ranked",
+            query_as::<_, Product>(&query_str)
+                .bind(term)
+                .bind(limit)
+                .fetch_all(&self.read_pool),
+        )
+        .await
+        .map_err(ApiError::Database)
+    }
+
+    /// Mendapatkan produk aktif lain dalam kategori yang sama, diurutkan berdasarkan kedekatan harga
+    pub async fn find_related(&self, id: Uuid, limit: i64) -> Result<Vec<Product>, ApiError> {
+        let source = self.find_by_id(id).await?;
+
+        // Produk tanpa kategori tidak memiliki produk terkait
+        let Some(category) = source.category.as_deref().map(category_slug) else {
+            return Ok(Vec::new());
+        };
+
+        let query_str = format!("{SELECT_PRODUCT_BASE} {RELATED_PRODUCTS_CLAUSE}");
+
+        timed(
+            "products.find_related",
+            query_as::<_, Product>(&query_str)
+                .bind(category)
+                .bind(id)
+                .bind(source.price)
+                .bind(limit)
+                .fetch_all(&self.read_pool),
+        )
+        .await
+        .map_err(ApiError::Database)
+    }
+
+    /// Menghitung statistik agregat produk langsung di database
+    pub async fn stats(&self) -> Result<ProductStats, ApiError> {
+        let totals = timed(
+            "products.stats.totals",
+            query(QUERY_STATS_TOTALS).fetch_one(&self.read_pool),
+        )
+        .await
+        .map_err(ApiError::Database)?;
+
+        let by_category = timed(
+            "products.stats.by_category",
+            query(QUERY_STATS_BY_CATEGORY).fetch_all(&self.read_pool),
+        )
+        .await
+        .map_err(ApiError::Database)?
+        .iter()
+        .map(|row| Ok((row.try_get("category")?, row.try_get("count")?)))
+        .collect::<Result<_, sqlx::Error>>()
+        .map_err(ApiError::Database)?;
+
+        Ok(ProductStats {
+            total: totals.try_get("total").map_err(ApiError::Database)?,
+            active: totals.try_get("active").map_err(ApiError::Database)?,
+            inactive: totals.try_get("inactive").map_err(ApiError::Database)?,
+            total_stock: totals.try_get("total_stock").map_err(ApiError::Database)?,
+            avg_price: totals.try_get("avg_price").map_err(ApiError::Database)?,
+            by_category,
+        })
+    }
+
+    /// Menghitung histogram harga produk aktif dengan `width_bucket`
+    ///
+    /// Batas harga dan jumlah per bucket dihitung dalam satu query supaya konsisten.
+    /// Katalog kosong menghasilkan daftar kosong.
+    pub async fn price_buckets(&self, buckets: i32) -> Result<Vec<PriceBucket>, ApiError> {
+        // Harga maksimum masuk bucket n+1 di width_bucket, jadi dibatasi ke bucket terakhir
+        let rows = timed(
+            "products.price_buckets",
+            query(
+                "WITH bounds AS (
+                    SELECT MIN(price)::FLOAT8 AS min_price, MAX(price)::FLOAT8 AS max_price
+                    FROM products
+                    WHERE is_active = true AND deleted_at IS NULL
+                )
+                SELECT
+                    b.min_price,
+                    b.max_price,
+                    CASE
+                        WHEN b.min_price = b.max_price THEN 1
+                        ELSE LEAST(width_bucket(p.price::FLOAT8, b.min_price, b.max_price, $1), $1)
+                    END AS bucket,
+                    COUNT(*) AS count
+                FROM products p
+                CROSS JOIN bounds b
+                WHERE p.is_active = true AND p.deleted_at IS NULL
+                GROUP BY b.min_price, b.max_price, bucket",
+            )
+            .bind(buckets)
+            .fetch_all(&self.read_pool),
+        )
+        .await
+        .map_err(ApiError::Database)?;
+
+        let Some(first) = rows.first() else {
+            return Ok(Vec::new());
+        };
+        let min_price: f64 = first.try_get("min_price").map_err(ApiError::Database)?;
+        let max_price: f64 = first.try_get("max_price").map_err(ApiError::Database)?;
+        let counts = rows
+            .iter()
+            .map(|row| Ok((row.try_get("bucket")?, row.try_get("count")?)))
+            .collect::<Result<Vec<(i32, i64)>, sqlx::Error>>()
+            .map_err(ApiError::Database)?;
+
+        Ok(build_price_buckets(min_price, max_price, buckets, &counts))
+    }
+
+    /// Semua produk milik satu pengguna, termasuk yang non-aktif
+    pub async fn list_by_owner(&self, owner_id: Uuid) -> Result<Vec<Product>, ApiError> {
+        let query_str = format!("{SELECT_PRODUCT_BASE} AND owner_id = $1 ORDER BY created_at, id");
+
+        timed(
+            "products.list_by_owner",
+            query_as::<_, Product>(&query_str)
+                .bind(owner_id)
+                .fetch_all(&self.read_pool),
+        )
+        .await
+        .map_err(ApiError::Database)
+    }
+
+    /// Menghitung produk aktif milik satu pengguna dalam kategori tertentu
+    pub async fn count_active_in_category(
+        &self,
+        category: &str,
+        owner_id: Uuid,
+    ) -> Result<i64, ApiError> {
+        query_scalar(
+            "SELECT COUNT(*) FROM products
+            WHERE category_slug = $1 AND owner_id = $2 AND is_active = true AND deleted_at IS NULL",
+        )
+        .bind(category_slug(category))
+        .bind(owner_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(ApiError::Database)
+    }
+
+    /// Menghitung ringkasan produk milik satu pengguna
+    pub async fn owner_summary(&self, owner_id: Uuid) -> Result<OwnerSummary, ApiError> {
+        let row = timed(
+            "products.owner_summary",
+            query(
+                "SELECT
+                    COUNT(*) AS product_count,
+                    COUNT(*) FILTER (WHERE is_active) AS active_count,
+                    COALESCE(SUM(price * stock), 0)::FLOAT8 AS total_stock_value
+                FROM products
+                WHERE owner_id = $1 AND deleted_at IS NULL",
+            )
+            .bind(owner_id)
+            .fetch_one(&self.read_pool),
+        )
+        .await
+        .map_err(ApiError::Database)?;
+
+        Ok(OwnerSummary {
+            product_count: row.try_get("product_count").map_err(ApiError::Database)?,
+            active_count: row.try_get("active_count").map_err(ApiError::Database)?,
+            total_stock_value: row
+                .try_get("total_stock_value")
+                .map_err(ApiError::Database)?,
+        })
     }
 
     /// Memperbarui produk yang ada
-    pub async fn update(&self, id: Uuid, update: &UpdateProduct) -> Result<Product, ApiError> {
+    pub async fn update(
+        &self,
+        id: Uuid,
+        update: &UpdateProduct,
+        changed_by: Option<Uuid>,
+    ) -> Result<Product, ApiError> {
+        let updated = with_transaction(&self.pool, |tx, _| {
+            Box::pin(async move {
+                // Periksa apakah produk ada dan dapatkan nilai saat ini
+                let current = Self::get_current_product(tx, id).await?;
+
+                // Siapkan nilai update menggunakan pendekatan yang lebih ringkas
+                let updated = Self::apply_updates(tx, id, &current, update).await?;
+                Self::record_audit(tx, &current, &updated, changed_by).await?;
+
+                Self::record_outbox(
+                    tx,
+                    &ProductEvent::Updated {
+                        product: updated.clone(),
+                    },
+                )
+                .await?;
+
+                Ok(updated)
+            })
+        })
+        .await?;
+
+        info!("Produk dengan ID: {} berhasil diperbarui", id);
+
+        Ok(updated)
+    }
+
+    /// Menghitung hasil update tanpa menyimpannya
+    ///
+    /// Update dijalankan di dalam transaksi yang selalu di-rollback, sehingga
+    /// tidak ada perubahan produk maupun event outbox yang tersimpan.
+    /// Mengembalikan produk sebelum dan sesudah update.
+    pub async fn preview_update(
+        &self,
+        id: Uuid,
+        update: &UpdateProduct,
+    ) -> Result<(Product, Product), ApiError> {
         let mut tx = self.pool.begin().await.map_err(ApiError::Database)?;
 
-        // Periksa apakah produk ada dan dapatkan nilai saat ini
         let current = Self::get_current_product(&mut tx, id).await?;
-
-        // Siapkan nilai update menggunakan pendekatan yang lebih ringkas
         let updated = Self::apply_updates(&mut tx, id, &current, update).await?;
 
+        tx.rollback().await.map_err(ApiError::Database)?;
+        info!("Dry-run update untuk produk dengan ID: {}", id);
+
+        Ok((current, updated))
+    }
+
+    /// Memindahkan semua produk dari satu kategori ke kategori lain
+    ///
+    /// Mengembalikan produk yang berubah; event outbox dicatat untuk masing-masing.
+    pub async fn rename_category(&self, from: &str, to: &str) -> Result<Vec<Product>, ApiError> {
+        let query_str = format!(
+            "UPDATE products
+            SET category = $2, category_slug = $3, version = version + 1, updated_at = NOW()
+            WHERE category_slug = $1 AND deleted_at IS NULL
+            RETURNING {SELECT_PRODUCT_FIELDS}"
+        );
+
+        let mut tx = self.pool.begin().await.map_err(ApiError::Database)?;
+
+        let updated = query_as::<_, Product>(&query_str)
+            .bind(category_slug(from))
+            .bind(to)
+            .bind(category_slug(to))
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(ApiError::Database)?;
+
+        for product in &updated {
+            Self::record_outbox(
+                &mut tx,
+                &ProductEvent::Updated {
+                    product: product.clone(),
+                },
+            )
+            .await?;
+        }
+
         tx.commit().await.map_err(ApiError::Database)?;
-        info!("Produk dengan ID: {} berhasil diperbarui", id);
+        info!(
+            "{} produk dipindahkan dari kategori '{}' ke '{}'",
+            updated.len(),
+            from,
+            to
+        );
 
         Ok(updated)
     }
 
-    /// Menghapus produk berdasarkan ID
-    pub async fn delete(&self, id: Uuid) -> Result<(), ApiError> {
-        let result = query("DELETE FROM products WHERE id = $1")
-            .bind(id)
-            .execute(&self.pool)
+    /// Mengubah harga semua produk dalam satu kategori sebesar persentase tertentu
+    ///
+    /// Dijalankan sebagai satu UPDATE dalam transaksi; bila ada harga yang
+    /// menjadi negatif, seluruh perubahan dibatalkan.
+    pub async fn adjust_prices(
+        &self,
+        category: &str,
+        percent: f64,
+    ) -> Result<Vec<Product>, ApiError> {
+        let query_str = format!(
+            "UPDATE products
+            SET price = ROUND(price * (1 + $2::NUMERIC / 100), 2),
+                version = version + 1,
+                updated_at = NOW()
+            WHERE category_slug = $1 AND deleted_at IS NULL
+            RETURNING {SELECT_PRODUCT_FIELDS}"
+        );
+
+        let mut tx = self.pool.begin().await.map_err(ApiError::Database)?;
+
+        let updated = query_as::<_, Product>(&query_str)
+            .bind(category_slug(category))
+            .bind(percent)
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(ApiError::Database)?;
+
+        // Transaksi di-rollback otomatis saat `tx` di-drop tanpa commit
+        if updated.iter().any(|product| product.price < 0.0) {
+            return Err(ApiError::BadRequest(
+                "Price adjustment would result in negative prices".to_string(),
+            ));
+        }
+
+        for product in &updated {
+            Self::record_outbox(
+                &mut tx,
+                &ProductEvent::Updated {
+                    product: product.clone(),
+                },
+            )
+            .await?;
+        }
+
+        tx.commit().await.map_err(ApiError::Database)?;
+        info!(
+            "Harga {} produk di kategori '{}' diubah sebesar {}%",
+            updated.len(),
+            category,
+            percent
+        );
+
+        Ok(updated)
+    }
+
+    /// Menambahkan tag ke produk, membuat tag baru bila belum ada
+    ///
+    /// Tag yang sudah terpasang diabaikan. Mengembalikan produk dengan tag terbaru.
+    pub async fn add_tags(&self, id: Uuid, tags: &[String]) -> Result<Product, ApiError> {
+        let mut tx = self.pool.begin().await.map_err(ApiError::Database)?;
+
+        // Kunci produk dulu, sekalian memastikan produknya ada
+        Self::get_current_product(&mut tx, id).await?;
+
+        query("INSERT INTO tags (name) SELECT UNNEST($1::TEXT[]) ON CONFLICT (name) DO NOTHING")
+            .bind(tags)
+            .execute(&mut *tx)
             .await
             .map_err(ApiError::Database)?;
 
+        query(
+            "INSERT INTO product_tags (product_id, tag_id)
+            SELECT $1, id FROM tags WHERE name = ANY($2)
+            ON CONFLICT DO NOTHING",
+        )
+        .bind(id)
+        .bind(tags)
+        .execute(&mut *tx)
+        .await
+        .map_err(ApiError::Database)?;
+
+        let product = Self::bump_updated_at(&mut tx, id).await?;
+
+        tx.commit().await.map_err(ApiError::Database)?;
+        info!("Tag {:?} ditambahkan ke produk {}", tags, id);
+        Ok(product)
+    }
+
+    /// Melepas satu tag dari produk
+    pub async fn remove_tag(&self, id: Uuid, tag: &str) -> Result<Product, ApiError> {
+        let mut tx = self.pool.begin().await.map_err(ApiError::Database)?;
+
+        Self::get_current_product(&mut tx, id).await?;
+
+        let result = query(
+            "DELETE FROM product_tags pt USING tags t
+            WHERE pt.tag_id = t.id AND pt.product_id = $1 AND t.name = $2",
+        )
+        .bind(id)
+        .bind(tag)
+        .execute(&mut *tx)
+        .await
+        .map_err(ApiError::Database)?;
+
         if result.rows_affected() == 0 {
             return Err(ApiError::NotFound(format!(
-                "Produk dengan ID {} tidak ditemukan",
-                id
+                "Tag '{}' tidak ditemukan pada produk {}",
+                tag, id
             )));
         }
 
+        let product = Self::bump_updated_at(&mut tx, id).await?;
+
+        tx.commit().await.map_err(ApiError::Database)?;
+        info!("Tag '{}' dilepas dari produk {}", tag, id);
+        Ok(product)
+    }
+
+    /// Mengaktifkan kembali produk yang non-aktif atau sudah dihapus
+    pub async fn restore(&self, id: Uuid) -> Result<Product, ApiError> {
+        let mut tx = self.pool.begin().await.map_err(ApiError::Database)?;
+
+        let (current, deleted) = Self::get_product_including_deleted(&mut tx, id).await?;
+        ensure_restorable(&current, deleted)?;
+
+        let restored = Self::revive(&mut tx, id, true).await?;
+
+        Self::record_outbox(
+            &mut tx,
+            &ProductEvent::Updated {
+                product: restored.clone(),
+            },
+        )
+        .await?;
+
+        tx.commit().await.map_err(ApiError::Database)?;
+        info!("Produk dengan ID: {} berhasil dipulihkan", id);
+        Ok(restored)
+    }
+
+    /// Memajukan `updated_at` dan `version` produk tanpa mengubah field lain
+    pub async fn touch(&self, id: Uuid) -> Result<Product, ApiError> {
+        let mut tx = self.pool.begin().await.map_err(ApiError::Database)?;
+
+        let current = Self::get_current_product(&mut tx, id).await?;
+        let updated_at = touch_timestamp(current.updated_at, Utc::now());
+
+        let query_str = format!(
+            "UPDATE products SET updated_at = $2, version = version + 1
+            WHERE id = $1
+            RETURNING {SELECT_PRODUCT_FIELDS}"
+        );
+
+        let touched = query_as::<_, Product>(&query_str)
+            .bind(id)
+            .bind(
+                time::OffsetDateTime::from_unix_timestamp(updated_at.timestamp())
+                    .map_err(|e| ApiError::Internal(format!("Invalid timestamp: {}", e)))?,
+            )
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(ApiError::Database)?;
+
+        Self::record_outbox(
+            &mut tx,
+            &ProductEvent::Updated {
+                product: touched.clone(),
+            },
+        )
+        .await?;
+
+        tx.commit().await.map_err(ApiError::Database)?;
+        info!("updated_at produk dengan ID: {} dimajukan", id);
+        Ok(touched)
+    }
+
+    /// Memindahkan stok dari satu produk ke produk lain dalam satu transaksi
+    ///
+    /// Kedua baris dikunci dengan FOR UPDATE berurutan berdasarkan ID supaya tidak
+    /// deadlock dengan transfer ke arah sebaliknya. Mengembalikan (asal, tujuan).
+    pub async fn transfer_stock(
+        &self,
+        from: Uuid,
+        to: Uuid,
+        quantity: i32,
+    ) -> Result<(Product, Product), ApiError> {
+        let mut tx = self.pool.begin().await.map_err(ApiError::Database)?;
+
+        let (source, target) = if from < to {
+            let source = Self::get_current_product(&mut tx, from).await?;
+            (source, Self::get_current_product(&mut tx, to).await?)
+        } else {
+            let target = Self::get_current_product(&mut tx, to).await?;
+            (Self::get_current_product(&mut tx, from).await?, target)
+        };
+
+        // Transaksi di-rollback otomatis saat `tx` di-drop tanpa commit
+        let (source_stock, target_stock) = plan_stock_transfer(&source, &target, quantity)?;
+
+        let source = Self::set_stock(&mut tx, from, source_stock).await?;
+        let target = Self::set_stock(&mut tx, to, target_stock).await?;
+
+        tx.commit().await.map_err(ApiError::Database)?;
+        info!(
+            "{} stok dipindahkan dari produk {} ke produk {}",
+            quantity, from, to
+        );
+
+        Ok((source, target))
+    }
+
+    /// Menambah (delta positif) atau mengurangi (delta negatif) stok produk
+    ///
+    /// Dipakai modul lain di dalam transaksi mereka sendiri, misalnya saat membuat
+    /// order. Baris produk dikunci dengan FOR UPDATE dan perubahan yang membuat
+    /// stok negatif ditolak; event outbox ikut tercatat di transaksi yang sama.
+    pub(crate) async fn adjust_stock(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        id: Uuid,
+        delta: i32,
+    ) -> Result<Product, ApiError> {
+        let product = Self::get_current_product(tx, id).await?;
+        let stock = plan_stock_adjustment(&product, delta)?;
+        Self::set_stock(tx, id, stock).await
+    }
+
+    /// Menghapus produk berdasarkan ID (soft delete)
+    ///
+    /// Produk hanya ditandai `deleted_at`, jadi audit, favorit dan item pesanan
+    /// tetap utuh. Mengembalikan `delete_id` yang dipakai token undo.
+    pub async fn delete(&self, id: Uuid) -> Result<Uuid, ApiError> {
+        let mut tx = self.pool.begin().await.map_err(ApiError::Database)?;
+
+        // Kunci produk dulu, sekalian memastikan produknya ada
+        Self::get_current_product(&mut tx, id).await?;
+
+        let delete_id: Uuid = query_scalar(QUERY_SOFT_DELETE)
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(ApiError::Database)?;
+
+        Self::record_outbox(&mut tx, &ProductEvent::Deleted { id }).await?;
+
+        tx.commit().await.map_err(ApiError::Database)?;
         info!("Produk dengan ID: {} berhasil dihapus", id);
-        Ok(())
+        Ok(delete_id)
+    }
+
+    /// Membatalkan penghapusan produk
+    ///
+    /// Hanya berlaku selama produk masih membawa `delete_id` yang sama; `delete_id`
+    /// dikosongkan saat produk kembali, jadi satu token undo hanya bisa dipakai sekali.
+    pub async fn undo_delete(
+        &self,
+        delete_id: Uuid,
+        product_id: Uuid,
+    ) -> Result<Product, ApiError> {
+        let mut tx = self.pool.begin().await.map_err(ApiError::Database)?;
+
+        let current: Option<Uuid> =
+            query_scalar("SELECT delete_id FROM products WHERE id = $1 FOR UPDATE")
+                .bind(product_id)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(ApiError::Database)?
+                .ok_or_else(|| {
+                    ApiError::NotFound(format!("Produk dengan ID {} tidak ditemukan", product_id))
+                })?;
+
+        if current != Some(delete_id) {
+            return Err(ApiError::Conflict(
+                "Undo token has already been used".to_string(),
+            ));
+        }
+
+        let restored = Self::revive(&mut tx, product_id, false).await?;
+
+        Self::record_outbox(
+            &mut tx,
+            &ProductEvent::Created {
+                product: restored.clone(),
+            },
+        )
+        .await?;
+
+        tx.commit().await.map_err(ApiError::Database)?;
+        info!("Penghapusan produk {} dibatalkan", product_id);
+        Ok(restored)
+    }
+
+    /// Menghapus banyak produk sekaligus (soft delete), mengembalikan ID yang benar-benar terhapus
+    pub async fn delete_many(&self, ids: &[Uuid]) -> Result<Vec<Uuid>, ApiError> {
+        let mut tx = self.pool.begin().await.map_err(ApiError::Database)?;
+
+        let deleted: Vec<Uuid> = query_scalar(
+            "UPDATE products SET deleted_at = NOW(), version = version + 1, updated_at = NOW()
+            WHERE id = ANY($1) AND deleted_at IS NULL
+            RETURNING id",
+        )
+        .bind(ids)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(ApiError::Database)?;
+
+        for id in &deleted {
+            Self::record_outbox(&mut tx, &ProductEvent::Deleted { id: *id }).await?;
+        }
+
+        tx.commit().await.map_err(ApiError::Database)?;
+        info!("{} produk berhasil dihapus secara massal", deleted.len());
+
+        Ok(deleted)
     }
 
     // HELPER METHODS
 
+    /// Mencatat event produk ke outbox dalam transaksi yang sama dengan mutasinya
+    async fn record_outbox(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        event: &ProductEvent,
+    ) -> Result<(), ApiError> {
+        let payload = serde_json::to_value(event)
+            .map_err(|e| ApiError::Internal(format!("Error serializing event: {}", e)))?;
+
+        record_event(
+            tx,
+            event.aggregate_id(),
+            &format!("product.{}", event.name()),
+            payload,
+        )
+        .await
+    }
+
+    /// Mencatat setiap field yang benar-benar berubah ke audit produk
+    async fn record_audit(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        before: &Product,
+        after: &Product,
+        changed_by: Option<Uuid>,
+    ) -> Result<(), ApiError> {
+        for change in after.changes_from(before) {
+            query(QUERY_INSERT_AUDIT)
+                .bind(after.id)
+                .bind(change.field)
+                .bind(change.old_value)
+                .bind(change.new_value)
+                .bind(changed_by)
+                .execute(&mut **tx)
+                .await
+                .map_err(ApiError::Database)?;
+        }
+
+        Ok(())
+    }
+
     /// Mendapatkan produk saat ini dari database dengan kunci FOR UPDATE
     async fn get_current_product(
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         id: Uuid,
     ) -> Result<Product, ApiError> {
-        let query_str = format!("{SELECT_PRODUCT_BASE} WHERE id = $1 FOR UPDATE");
+        let query_str = format!("{SELECT_PRODUCT_BASE} AND id = $1 FOR UPDATE");
 
         query_as::<_, Product>(&query_str)
             .bind(id)
@@ -175,6 +1222,121 @@ impl ProductRepository {
             .ok_or_else(|| ApiError::NotFound(format!("Produk dengan ID {} tidak ditemukan", id)))
     }
 
+    /// Seperti `get_current_product`, tapi ikut membaca produk yang sudah dihapus
+    ///
+    /// Mengembalikan produk beserta penanda apakah produk itu sedang terhapus.
+    async fn get_product_including_deleted(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        id: Uuid,
+    ) -> Result<(Product, bool), ApiError> {
+        let query_str = format!(
+            "SELECT {SELECT_PRODUCT_FIELDS}, deleted_at IS NOT NULL AS deleted
+            FROM products WHERE id = $1 FOR UPDATE"
+        );
+
+        let row = query(&query_str)
+            .bind(id)
+            .fetch_optional(&mut **tx)
+            .await
+            .map_err(ApiError::Database)?
+            .ok_or_else(|| {
+                ApiError::NotFound(format!("Produk dengan ID {} tidak ditemukan", id))
+            })?;
+
+        let product = Product::from_row(&row).map_err(ApiError::Database)?;
+        let deleted: bool = row.try_get("deleted").map_err(ApiError::Database)?;
+        Ok((product, deleted))
+    }
+
+    /// Mengembalikan produk yang terhapus, dipakai restore dan undo delete
+    ///
+    /// SKU dicek lebih dulu karena selama produk terhapus SKU-nya boleh dipakai
+    /// produk lain. `activate` sekalian mengaktifkan produk yang non-aktif.
+    async fn revive(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        id: Uuid,
+        activate: bool,
+    ) -> Result<Product, ApiError> {
+        let sku_taken: bool = query_scalar(QUERY_SKU_TAKEN_BY_OTHER)
+            .bind(id)
+            .fetch_one(&mut **tx)
+            .await
+            .map_err(ApiError::Database)?;
+
+        if sku_taken {
+            return Err(ApiError::Conflict("SKU already in use".to_string()));
+        }
+
+        let query_str = format!(
+            "UPDATE products
+            SET deleted_at = NULL, delete_id = NULL, is_active = is_active OR $2,
+                version = version + 1, updated_at = NOW()
+            WHERE id = $1
+            RETURNING {SELECT_PRODUCT_FIELDS}"
+        );
+
+        query_as::<_, Product>(&query_str)
+            .bind(id)
+            .bind(activate)
+            .fetch_one(&mut **tx)
+            .await
+            .map_err(ApiError::Database)
+    }
+
+    /// Mengubah stok produk dan mencatat event outbox
+    async fn set_stock(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        id: Uuid,
+        stock: i32,
+    ) -> Result<Product, ApiError> {
+        let query_str = format!(
+            "UPDATE products SET stock = $2, version = version + 1, updated_at = NOW()
+            WHERE id = $1
+            RETURNING {SELECT_PRODUCT_FIELDS}"
+        );
+
+        let product = query_as::<_, Product>(&query_str)
+            .bind(id)
+            .bind(stock)
+            .fetch_one(&mut **tx)
+            .await
+            .map_err(ApiError::Database)?;
+
+        Self::record_outbox(
+            tx,
+            &ProductEvent::Updated {
+                product: product.clone(),
+            },
+        )
+        .await?;
+
+        Ok(product)
+    }
+
+    /// Menaikkan version dan `updated_at` setelah perubahan tag dan mencatat event outbox
+    async fn bump_updated_at(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        id: Uuid,
+    ) -> Result<Product, ApiError> {
+        let query_str = format!("{QUERY_BUMP_AFTER_TAG_CHANGE} RETURNING {SELECT_PRODUCT_FIELDS}");
+
+        let product = query_as::<_, Product>(&query_str)
+            .bind(id)
+            .fetch_one(&mut **tx)
+            .await
+            .map_err(ApiError::Database)?;
+
+        Self::record_outbox(
+            tx,
+            &ProductEvent::Updated {
+                product: product.clone(),
+            },
+        )
+        .await?;
+
+        Ok(product)
+    }
+
     /// Menerapkan pembaruan ke produk
     async fn apply_updates(
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
@@ -183,21 +1345,30 @@ impl ProductRepository {
         update: &UpdateProduct,
     ) -> Result<Product, ApiError> {
         // Siapkan nilai update
+        let sku_str = match &update.sku {
+            Some(sku) => sku.as_deref(),
+            None => current.sku.as_deref(),
+        };
         let name = update.name.as_ref().unwrap_or(&current.name);
-        let description_str = update
-            .description
-            .as_deref()
-            .or(current.description.as_deref());
+        let description_str = match &update.description {
+            Some(description) => description.as_deref(),
+            None => current.description.as_deref(),
+        };
         let price = update.price.unwrap_or(current.price);
         let stock = update.stock.unwrap_or(current.stock);
-        let category_str = update.category.as_deref().or(current.category.as_deref());
+        let category_str = match &update.category {
+            Some(category) => category.as_deref(),
+            None => current.category.as_deref(),
+        };
         let is_active = update.is_active.unwrap_or(current.is_active);
+        let is_featured = update.is_featured.unwrap_or(current.is_featured);
 
-        // Eksekusi update
+        // Eksekusi update, hanya berhasil jika versi masih sama dengan yang dibaca klien
         let query_str = format!(
             "UPDATE products
-            SET name = $1, description = $2, price = $3, stock = $4, category = $5, is_active = $6, updated_at = NOW()
-            WHERE id = $7
+            SET name = $1, description = $2, price = $3, stock = $4, category = $5, is_active = $6,
+                sku = $9, category_slug = $10, is_featured = $11, version = version + 1, updated_at = NOW()
+            WHERE id = $7 AND version = $8
             RETURNING {SELECT_PRODUCT_FIELDS}"
         );
 
@@ -209,9 +1380,22 @@ impl ProductRepository {
             .bind(category_str)
             .bind(is_active)
             .bind(id)
-            .fetch_one(&mut **tx)
+            .bind(update.version)
+            .bind(sku_str)
+            .bind(category_str.map(category_slug))
+            .bind(is_featured)
+            .fetch_optional(&mut **tx)
             .await
             .map_err(ApiError::Database)
+            .and_then(require_version_match)
+    }
+
+    /// Query COUNT(*) dengan filter yang sama seperti daftar produk
+    fn count_query(filter: &ProductFilter) -> QueryBuilder<'_, sqlx::Postgres> {
+        let mut query_builder =
+            QueryBuilder::new("SELECT COUNT(*) FROM products WHERE deleted_at IS NULL");
+        Self::apply_filters(&mut query_builder, filter);
+        query_builder
     }
 
     /// Menerapkan filter ke query builder
@@ -224,9 +1408,19 @@ impl ProductRepository {
             query_builder.push_bind(format!("%{}%", name));
         }
 
+        // Bandingkan slug supaya "electronics" cocok dengan "Electronics"
         if let Some(category) = &filter.category {
-            query_builder.push(" AND category = ");
-            query_builder.push_bind(category);
+            query_builder.push(" AND category_slug = ");
+            query_builder.push_bind(category_slug(category));
+        }
+
+        if let Some(tag) = &filter.tag {
+            query_builder.push(
+                " AND EXISTS (SELECT 1 FROM product_tags pt JOIN tags t ON t.id = pt.tag_id
+                WHERE pt.product_id = products.id AND t.name = ",
+            );
+            query_builder.push_bind(tag);
+            query_builder.push(")");
         }
 
         if let Some(min_price) = filter.min_price {
@@ -243,5 +1437,525 @@ impl ProductRepository {
             query_builder.push(" AND is_active = ");
             query_builder.push_bind(is_active);
         }
+
+        if let Some(featured) = filter.featured {
+            query_builder.push(" AND is_featured = ");
+            query_builder.push_bind(featured);
+        }
+
+        if let Some(owner_id) = filter.owner_id {
+            query_builder.push(" AND owner_id = ");
+            query_builder.push_bind(owner_id);
+        }
+    }
+}
+
+/// Menyusun bucket harga dengan lebar sama dari jumlah per nomor bucket (mulai dari 1)
+///
+/// Bucket tanpa produk tetap muncul dengan `count` 0. Bila semua harga sama,
+/// hanya ada satu bucket.
+fn build_price_buckets(
+    min_price: f64,
+    max_price: f64,
+    buckets: i32,
+    counts: &[(i32, i64)],
+) -> Vec<PriceBucket> {
+    let buckets = if min_price == max_price {
+        1
+    } else {
+        buckets.max(1)
+    };
+    let width = (max_price - min_price) / buckets as f64;
+
+    (1..=buckets)
+        .map(|bucket| PriceBucket {
+            min: min_price + width * (bucket - 1) as f64,
+            max: if bucket == buckets {
+                max_price
+            } else {
+                min_price + width * bucket as f64
+            },
+            count: counts
+                .iter()
+                .filter(|(number, _)| *number == bucket)
+                .map(|(_, count)| count)
+                .sum(),
+        })
+        .collect()
+}
+
+/// Membuat slug kategori: huruf kecil dan spasi diganti tanda hubung
+///
+/// Harus sama dengan backfill di migrasi `add_products_category_slug`.
+fn category_slug(category: &str) -> String {
+    category
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase()
+}
+
+/// Menghitung stok baru (asal, tujuan) untuk transfer stok
+///
+/// Gagal kalau stok asal tidak cukup atau stok tujuan melebihi batas.
+fn plan_stock_transfer(
+    source: &Product,
+    target: &Product,
+    quantity: i32,
+) -> Result<(i32, i32), ApiError> {
+    if source.stock < quantity {
+        return Err(ApiError::BadRequest(format!(
+            "Stok produk {} tidak cukup (tersedia {}, diminta {})",
+            source.id, source.stock, quantity
+        )));
+    }
+
+    let target_stock = target
+        .stock
+        .checked_add(quantity)
+        .ok_or_else(|| ApiError::BadRequest(format!("Stok produk {} melebihi batas", target.id)))?;
+
+    Ok((source.stock - quantity, target_stock))
+}
+
+/// Menghitung stok baru setelah ditambah `delta`
+///
+/// Gagal kalau stok tidak cukup untuk pengurangan atau melebihi batas.
+fn plan_stock_adjustment(product: &Product, delta: i32) -> Result<i32, ApiError> {
+    match product.stock.checked_add(delta) {
+        Some(stock) if stock >= 0 => Ok(stock),
+        Some(_) => Err(ApiError::BadRequest(format!(
+            "Stok produk {} tidak cukup (tersedia {}, diminta {})",
+            product.id, product.stock, -delta
+        ))),
+        None => Err(ApiError::BadRequest(format!(
+            "Stok produk {} melebihi batas",
+            product.id
+        ))),
+    }
+}
+
+/// Waktu `updated_at` baru untuk touch
+///
+/// Timestamp di API hanya sampai detik, jadi hasilnya minimal satu detik setelah
+/// nilai sebelumnya supaya perubahan selalu terlihat walaupun di-touch dua kali
+/// dalam detik yang sama.
+fn touch_timestamp(previous: DateTime<Utc>, now: DateTime<Utc>) -> DateTime<Utc> {
+    now.trunc_subsecs(0)
+        .max(previous.trunc_subsecs(0) + Duration::seconds(1))
+}
+
+/// Stok awal produk baru; default-nya sudah harus diisi service
+fn resolved_stock(product: &CreateProduct) -> Result<i32, ApiError> {
+    product.stock.ok_or_else(|| {
+        ApiError::Internal("Initial stock must be resolved before insert".to_string())
+    })
+}
+
+/// Update berversi yang tidak mengenai baris berarti versinya sudah berubah
+fn require_version_match(updated: Option<Product>) -> Result<Product, ApiError> {
+    updated.ok_or_else(|| ApiError::Conflict("Product was modified by someone else".to_string()))
+}
+
+/// Hanya produk non-aktif atau yang sudah dihapus yang bisa dipulihkan
+fn ensure_restorable(product: &Product, deleted: bool) -> Result<(), ApiError> {
+    if product.is_active && !deleted {
+        return Err(ApiError::BadRequest(format!(
+            "Produk dengan ID {} sudah aktif",
+            product.id
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use sqlx::postgres::PgPoolOptions;
+
+    fn lazy_pool(database: &str) -> DbPool {
+        PgPoolOptions::new()
+            .connect_lazy(&format!("postgres://postgres@127.0.0.1:1/{database}"))
+            .unwrap()
+    }
+
+    fn product_with_status(is_active: bool) -> Product {
+        let now = Utc::now();
+        Product {
+            id: Uuid::new_v4(),
+            sku: None,
+            name: "Keyboard".to_string(),
+            description: None,
+            price: 500000.0,
+            formatted_price: None,
+            stock: 3,
+            category: None,
+            is_active,
+            is_featured: false,
+            version: 1,
+            owner_id: None,
+            tags: Vec::new(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn test_build_price_buckets_for_known_distribution() {
+        // Harga 0..=100: bucket 1 berisi 3 produk, bucket 2 kosong, dst.
+        let buckets = build_price_buckets(0.0, 100.0, 4, &[(1, 3), (3, 1), (4, 2)]);
+
+        assert_eq!(
+            buckets,
+            vec![
+                PriceBucket {
+                    min: 0.0,
+                    max: 25.0,
+                    count: 3
+                },
+                PriceBucket {
+                    min: 25.0,
+                    max: 50.0,
+                    count: 0
+                },
+                PriceBucket {
+                    min: 50.0,
+                    max: 75.0,
+                    count: 1
+                },
+                PriceBucket {
+                    min: 75.0,
+                    max: 100.0,
+                    count: 2
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_price_buckets_with_single_price() {
+        assert_eq!(
+            build_price_buckets(5000.0, 5000.0, 5, &[(1, 4)]),
+            vec![PriceBucket {
+                min: 5000.0,
+                max: 5000.0,
+                count: 4
+            }]
+        );
+    }
+
+    #[test]
+    fn test_plan_stock_transfer_moves_quantity() {
+        let mut source = product_with_status(true);
+        source.stock = 10;
+        let mut target = product_with_status(true);
+        target.stock = 2;
+
+        assert_eq!(plan_stock_transfer(&source, &target, 4).unwrap(), (6, 6));
+        assert_eq!(plan_stock_transfer(&source, &target, 10).unwrap(), (0, 12));
+    }
+
+    #[test]
+    fn test_plan_stock_transfer_rejects_insufficient_stock() {
+        let mut source = product_with_status(true);
+        source.stock = 3;
+        let mut target = product_with_status(true);
+        target.stock = 5;
+
+        assert!(matches!(
+            plan_stock_transfer(&source, &target, 4),
+            Err(ApiError::BadRequest(message)) if message.contains("tidak cukup")
+        ));
+        // Gagal sebelum UPDATE dijalankan, jadi kedua stok tetap sama
+        assert_eq!((source.stock, target.stock), (3, 5));
+
+        target.stock = i32::MAX;
+        assert!(plan_stock_transfer(&source, &target, 1).is_err());
+    }
+
+    #[test]
+    fn test_plan_stock_adjustment_applies_delta() {
+        let mut product = product_with_status(true);
+        product.stock = 5;
+
+        assert_eq!(plan_stock_adjustment(&product, -5).unwrap(), 0);
+        assert_eq!(plan_stock_adjustment(&product, 3).unwrap(), 8);
+    }
+
+    #[test]
+    fn test_plan_stock_adjustment_rejects_negative_stock_and_overflow() {
+        let mut product = product_with_status(true);
+        product.stock = 2;
+
+        assert!(matches!(
+            plan_stock_adjustment(&product, -3),
+            Err(ApiError::BadRequest(message)) if message.contains("tidak cukup")
+        ));
+
+        product.stock = i32::MAX;
+        assert!(plan_stock_adjustment(&product, 1).is_err());
+    }
+
+    #[test]
+    fn test_category_slug_ignores_case_and_spacing() {
+        assert_eq!(category_slug("Electronics"), "electronics");
+        assert_eq!(category_slug("electronics"), category_slug("Electronics"));
+        assert_eq!(category_slug("  Home   Office "), "home-office");
+    }
+
+    #[test]
+    fn test_category_filter_matches_on_slug() {
+        let filter: ProductFilter =
+            serde_json::from_value(serde_json::json!({ "category": "Electronics" })).unwrap();
+        let mut query_builder = QueryBuilder::new("SELECT id FROM products WHERE 1=1");
+        ProductRepository::apply_filters(&mut query_builder, &filter);
+
+        assert!(query_builder.sql().contains("AND category_slug = $1"));
+    }
+
+    #[test]
+    fn test_count_query_without_filters_counts_every_product() {
+        let filter: ProductFilter = serde_json::from_value(serde_json::json!({})).unwrap();
+        let query_builder = ProductRepository::count_query(&filter);
+
+        assert_eq!(
+            query_builder.sql(),
+            "SELECT COUNT(*) FROM products WHERE deleted_at IS NULL"
+        );
+    }
+
+    #[test]
+    fn test_count_query_applies_list_filters_but_not_paging() {
+        let filter: ProductFilter = serde_json::from_value(serde_json::json!({
+            "category": "Electronics",
+            "min_price": 10.0,
+            "is_active": true,
+            "limit": 5,
+            "offset": 10
+        }))
+        .unwrap();
+        let sql = ProductRepository::count_query(&filter).into_sql();
+
+        assert!(sql.starts_with("SELECT COUNT(*) FROM products WHERE deleted_at IS NULL"));
+        assert!(sql.contains("AND category_slug = $1"));
+        assert!(sql.contains("AND price >= $2"));
+        assert!(sql.contains("AND is_active = $3"));
+        assert!(!sql.contains("LIMIT"));
+        assert!(!sql.contains("OFFSET"));
+        assert!(!sql.contains("ORDER BY"));
+    }
+
+    #[test]
+    fn test_featured_filter_is_independent_of_active_status() {
+        let filter: ProductFilter =
+            serde_json::from_value(serde_json::json!({ "featured": true })).unwrap();
+        let sql = ProductRepository::count_query(&filter).into_sql();
+
+        assert!(sql.contains("AND is_featured = $1"));
+        assert!(!sql.contains("is_active"));
+    }
+
+    #[test]
+    fn test_tag_changes_bump_version() {
+        assert!(QUERY_BUMP_AFTER_TAG_CHANGE.contains("version = version + 1"));
+        assert!(QUERY_BUMP_AFTER_TAG_CHANGE.contains("updated_at = NOW()"));
+    }
+
+    #[test]
+    fn test_featured_list_only_returns_active_featured_products() {
+        assert!(
+            FEATURED_PRODUCTS_CLAUSE.starts_with("AND is_featured = true AND is_active = true")
+        );
+        assert!(FEATURED_PRODUCTS_CLAUSE.contains("ORDER BY updated_at DESC"));
+    }
+
+    #[test]
+    fn test_list_orderings_end_with_id_tiebreaker() {
+        let order_by = |clause: &str| {
+            let (_, order_by) = clause.split_once("ORDER BY ").unwrap();
+            order_by.split(" LIMIT").next().unwrap().trim().to_string()
+        };
+
+        assert!(order_by(RECENT_PRODUCTS_CLAUSE).ends_with(", id ASC"));
+        assert!(order_by(SEARCH_RANKED_CLAUSE).ends_with(", id ASC"));
+        assert!(order_by(RELATED_PRODUCTS_CLAUSE).ends_with(", id ASC"));
+        assert!(order_by(FEATURED_PRODUCTS_CLAUSE).ends_with(", id ASC"));
+    }
+
+    #[test]
+    fn test_duplicate_copies_as_inactive_without_sku() {
+        let (columns, select) = QUERY_DUPLICATE_PRODUCT.split_once("SELECT").unwrap();
+
+        // Nama dipotong supaya akhiran tetap muat di kolom name
+        assert!(select.contains("LEFT(name, 248) || ' (copy)'"));
+        assert_eq!(248 + " (copy)".len(), 255);
+
+        // SKU tidak disalin (jadi NULL), status selalu non-aktif, pemilik dari $2
+        assert!(!columns.contains("sku"));
+        assert!(!columns.contains("is_featured"));
+        assert!(columns.contains("is_active, owner_id)"));
+        assert!(select.contains("category_slug, false, $2"));
+        assert!(
+            select
+                .trim_end()
+                .ends_with("FROM products WHERE id = $1 AND deleted_at IS NULL")
+        );
+    }
+
+    #[test]
+    fn test_search_ranks_websearch_matches() {
+        let tsquery = "websearch_to_tsquery('simple', $1)";
+
+        // Filter dan ranking memakai tsquery yang sama supaya konsisten
+        assert!(SEARCH_RANKED_CLAUSE.starts_with(&format!("AND search_vector @@ {tsquery}")));
+        assert!(SEARCH_RANKED_CLAUSE.contains(&format!(
+            "ORDER BY ts_rank(search_vector, {tsquery}) DESC, name ASC"
+        )));
+        assert!(SEARCH_RANKED_CLAUSE.trim_end().ends_with("LIMIT $2"));
+        assert!(!SEARCH_RANKED_CLAUSE.contains("ILIKE"));
+    }
+
+    #[test]
+    fn test_related_products_exclude_source_and_inactive() {
+        assert!(RELATED_PRODUCTS_CLAUSE.contains("category_slug = $1"));
+        assert!(RELATED_PRODUCTS_CLAUSE.contains("id <> $2"));
+        assert!(RELATED_PRODUCTS_CLAUSE.contains("is_active = true"));
+        assert!(RELATED_PRODUCTS_CLAUSE.contains("ORDER BY ABS(price - $3::DECIMAL)"));
+        assert!(RELATED_PRODUCTS_CLAUSE.trim_end().ends_with("LIMIT $4"));
+    }
+
+    #[test]
+    fn test_stats_queries_default_empty_catalog_to_zero() {
+        assert!(QUERY_STATS_TOTALS.contains("COUNT(*) FILTER (WHERE is_active) AS active"));
+        assert!(QUERY_STATS_TOTALS.contains("COUNT(*) FILTER (WHERE NOT is_active) AS inactive"));
+        assert!(QUERY_STATS_TOTALS.contains("COALESCE(SUM(stock), 0)::BIGINT AS total_stock"));
+        assert!(QUERY_STATS_TOTALS.contains("COALESCE(AVG(price), 0)::FLOAT8 AS avg_price"));
+
+        assert!(
+            QUERY_STATS_BY_CATEGORY.contains("WHERE deleted_at IS NULL AND category IS NOT NULL")
+        );
+        assert!(QUERY_STATS_BY_CATEGORY.ends_with("GROUP BY category"));
+    }
+
+    #[test]
+    fn test_deleted_products_are_hidden_from_reads() {
+        assert!(SELECT_PRODUCT_BASE.ends_with("FROM products WHERE deleted_at IS NULL"));
+        assert!(
+            QUERY_STATS_TOTALS
+                .trim_end()
+                .ends_with("WHERE deleted_at IS NULL")
+        );
+    }
+
+    #[test]
+    fn test_delete_keeps_the_row_for_undo() {
+        assert!(QUERY_SOFT_DELETE.starts_with("UPDATE products"));
+        assert!(QUERY_SOFT_DELETE.contains("deleted_at = NOW(), delete_id = gen_random_uuid()"));
+        assert!(QUERY_SOFT_DELETE.contains("version = version + 1"));
+        assert!(QUERY_SOFT_DELETE.contains("AND deleted_at IS NULL"));
+        assert!(
+            QUERY_SOFT_DELETE
+                .trim_end()
+                .ends_with("RETURNING delete_id")
+        );
+    }
+
+    #[test]
+    fn test_revive_checks_sku_against_live_products_only() {
+        assert!(QUERY_SKU_TAKEN_BY_OTHER.contains("other.sku = p.sku"));
+        assert!(QUERY_SKU_TAKEN_BY_OTHER.contains("other.id <> p.id"));
+        assert!(QUERY_SKU_TAKEN_BY_OTHER.contains("other.deleted_at IS NULL"));
+    }
+
+    #[test]
+    fn test_ensure_restorable_allows_deactivated_products() {
+        assert!(ensure_restorable(&product_with_status(false), false).is_ok());
+    }
+
+    #[test]
+    fn test_ensure_restorable_allows_deleted_products() {
+        assert!(ensure_restorable(&product_with_status(true), true).is_ok());
+    }
+
+    #[test]
+    fn test_ensure_restorable_rejects_live_active_products() {
+        assert!(matches!(
+            ensure_restorable(&product_with_status(true), false),
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_versioned_update_without_rows_is_conflict() {
+        assert!(matches!(
+            require_version_match(None),
+            Err(ApiError::Conflict(_))
+        ));
+
+        let product = product_with_status(true);
+        let updated = require_version_match(Some(product.clone())).unwrap();
+        assert_eq!(updated.id, product.id);
+    }
+
+    #[tokio::test]
+    async fn test_reads_and_writes_use_separate_pools() {
+        let repository = ProductRepository::new(
+            DbPools {
+                write: lazy_pool("primary"),
+                read: lazy_pool("replica"),
+            },
+            &FeatureFlags::default(),
+        );
+
+        assert_eq!(
+            repository.pool.connect_options().get_database(),
+            Some("primary")
+        );
+        assert_eq!(
+            repository.read_pool.connect_options().get_database(),
+            Some("replica")
+        );
+    }
+
+    #[test]
+    fn test_touch_timestamp_uses_now_when_later() {
+        let previous = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        let now = Utc.timestamp_opt(1_700_000_060, 500_000_000).unwrap();
+
+        assert_eq!(
+            touch_timestamp(previous, now),
+            Utc.timestamp_opt(1_700_000_060, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_touch_timestamp_advances_within_same_second() {
+        // Touch kedua di detik yang sama tetap harus memajukan updated_at
+        let previous = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        let now = Utc.timestamp_opt(1_700_000_000, 900_000_000).unwrap();
+
+        let touched = touch_timestamp(previous, now);
+        assert!(touched > previous);
+        assert_eq!(touched, Utc.timestamp_opt(1_700_000_001, 0).unwrap());
+    }
+
+    #[test]
+    fn test_touch_leaves_other_fields_unchanged() {
+        let before = product_with_status(true);
+        let mut after = before.clone();
+        after.updated_at = touch_timestamp(before.updated_at, Utc::now());
+        after.version += 1;
+
+        assert!(after.updated_at > before.updated_at);
+
+        let without_touched = |product: &Product| {
+            let mut value = serde_json::to_value(product).unwrap();
+            let object = value.as_object_mut().unwrap();
+            object.remove("updated_at");
+            object.remove("version");
+            value
+        };
+        assert_eq!(without_touched(&after), without_touched(&before));
     }
 }
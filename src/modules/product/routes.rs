@@ -1,61 +1,398 @@
-use crate::core::db::DbPool;
-use crate::modules::product::model::{CreateProduct, ProductFilter, UpdateProduct};
+use crate::core::{
+    authentication::{AuthUser, CurrentUser},
+    body_limit::body_limit,
+    config::FeatureFlags,
+    db::DbPools,
+    error::ApiError,
+    json::{ApiJson, ApiJsonOrForm},
+    pagination::PageParams,
+    query::ApiQuery,
+};
+use crate::modules::product::cache::ProductCache;
+use crate::modules::product::model::{
+    AddProductTags, AdjustPrices, BatchGetProducts, BulkCreateProducts, BulkCreateQuery,
+    BulkDeleteProducts, CompareProducts, CreateProduct, LimitQuery, PriceBucketQuery, ProductEvent,
+    ProductFilter, RecategorizeProducts, SearchQuery, TransferStock, UndoDeleteProduct,
+    UpdateProduct, UpdateProductQuery,
+};
 use crate::modules::product::repository::ProductRepository;
-use crate::modules::product::service::ProductService;
+use crate::modules::product::service::{ProductService, prefers_csv};
 use axum::{
-    Json, Router,
-    extract::{Path, Query, State},
-    response::IntoResponse,
-    routing::get,
+    Router,
+    body::Bytes,
+    extract::{Extension, Path, State},
+    http::{HeaderMap, header},
+    response::{
+        IntoResponse,
+        sse::{Event, KeepAlive, Sse},
+    },
+    routing::{delete, get, post, put},
 };
-use std::sync::Arc;
+use futures::stream::{self, Stream};
+use json_patch::Patch;
+use std::{convert::Infallible, sync::Arc, time::Duration};
+use tokio::sync::broadcast::{Receiver, error::RecvError};
 use uuid::Uuid;
 
+/// Media type for RFC 6902 JSON patch bodies
+const JSON_PATCH_CONTENT_TYPE: &str = "application/json-patch+json";
+
+/// Delay clients should wait before reconnecting after falling behind
+const STREAM_RECONNECT_DELAY: Duration = Duration::from_secs(3);
+
 /// Type alias for the shared state
 type SharedHandler = Arc<ProductService>;
 
 /// Create product routes
-pub fn product_routes(pool: DbPool) -> Router {
+pub fn product_routes(pools: DbPools, cache: ProductCache, flags: &FeatureFlags) -> Router {
     // Create repository and handler
-    let repository = ProductRepository::new(pool);
-    let handler = Arc::new(ProductService::new(repository));
+    let repository = ProductRepository::new(pools, flags);
+    let handler = Arc::new(ProductService::new(repository, cache, flags));
 
     // Define routes with shared state
     Router::new()
         .route("/api/products", get(_products).post(create_product))
+        .route("/api/products/count", get(count_products))
+        .route("/api/products/recent", get(recent_products))
+        .route("/api/products/featured", get(featured_products))
+        .route("/api/products/search", get(search_products))
+        .route("/api/products/stats", get(product_stats))
+        .route("/api/products/price-buckets", get(price_buckets))
+        .route("/api/products/stream", get(product_stream))
+        .route(
+            "/api/products/bulk-delete",
+            post(bulk_delete_products).layer(body_limit(flags.max_bulk_body_bytes)),
+        )
+        .route(
+            "/api/products/bulk",
+            post(bulk_create_products).layer(body_limit(flags.max_bulk_body_bytes)),
+        )
+        .route("/api/products/validate", post(validate_product))
+        .route("/api/products/batch-get", post(batch_get_products))
+        .route("/api/products/compare", post(compare_products))
+        .route("/api/products/undo", post(undo_delete_product))
+        .route("/api/products/recategorize", post(recategorize_products))
+        .route("/api/products/price-adjust", post(adjust_prices))
+        .route("/api/products/transfer-stock", post(transfer_stock))
         .route(
             "/api/products/{id}",
-            get(get_product).put(update_product).delete(delete_product),
+            get(get_product)
+                .put(update_product)
+                .patch(patch_product)
+                .delete(delete_product),
         )
+        .route("/api/products/{id}/related", get(related_products))
+        .route("/api/products/{id}/duplicate", post(duplicate_product))
+        .route("/api/products/{id}/restore", post(restore_product))
+        .route("/api/products/{id}/touch", post(touch_product))
+        .route("/api/products/{id}/audit", get(product_audit))
+        .route(
+            "/api/products/{id}/favorite",
+            post(favorite_product).delete(unfavorite_product),
+        )
+        .route("/api/products/{id}/tags", post(add_product_tags))
+        .route("/api/products/{id}/tags/{tag}", delete(remove_product_tag))
+        .route("/api/products/by-sku/{sku}", put(upsert_product_by_sku))
+        .route("/api/me/summary", get(my_summary))
+        .route("/api/me/favorites", get(list_favorites))
         .with_state(handler)
 }
 
 /// Handler functions that take the shared state
 async fn _products(
     State(handler): State<SharedHandler>,
-    Query(filter): Query<ProductFilter>,
+    user: Option<AuthUser>,
+    ApiQuery(filter): ApiQuery<ProductFilter>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let accept = headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok());
+
+    handler
+        .list_products(
+            filter,
+            user.as_ref().map(|AuthUser(user)| user),
+            prefers_csv(accept),
+        )
+        .await
+        .map_or_else(
+            |err| err.into_response(),
+            |response| response.into_response(),
+        )
+}
+
+async fn count_products(
+    State(handler): State<SharedHandler>,
+    user: Option<AuthUser>,
+    ApiQuery(filter): ApiQuery<ProductFilter>,
+) -> impl IntoResponse {
+    handler
+        .count_products(filter, user.as_ref().map(|AuthUser(user)| user))
+        .await
+        .map_or_else(
+            |err| err.into_response(),
+            |response| response.into_response(),
+        )
+}
+
+async fn featured_products(
+    State(handler): State<SharedHandler>,
+    ApiQuery(query): ApiQuery<LimitQuery>,
+) -> impl IntoResponse {
+    handler.featured_products(query).await.map_or_else(
+        |err| err.into_response(),
+        |response| response.into_response(),
+    )
+}
+
+async fn recent_products(
+    State(handler): State<SharedHandler>,
+    ApiQuery(query): ApiQuery<LimitQuery>,
+) -> impl IntoResponse {
+    handler.recent_products(query).await.map_or_else(
+        |err| err.into_response(),
+        |response| response.into_response(),
+    )
+}
+
+async fn search_products(
+    State(handler): State<SharedHandler>,
+    ApiQuery(query): ApiQuery<SearchQuery>,
+) -> impl IntoResponse {
+    handler.search_products(query).await.map_or_else(
+        |err| err.into_response(),
+        |response| response.into_response(),
+    )
+}
+
+async fn product_stats(State(handler): State<SharedHandler>) -> impl IntoResponse {
+    handler.product_stats().await.map_or_else(
+        |err| err.into_response(),
+        |response| response.into_response(),
+    )
+}
+
+async fn price_buckets(
+    State(handler): State<SharedHandler>,
+    ApiQuery(query): ApiQuery<PriceBucketQuery>,
 ) -> impl IntoResponse {
-    handler.list_products(filter).await.map_or_else(
+    handler.price_buckets(query).await.map_or_else(
         |err| err.into_response(),
         |response| response.into_response(),
     )
 }
 
+async fn my_summary(
+    State(handler): State<SharedHandler>,
+    AuthUser(user): AuthUser,
+) -> impl IntoResponse {
+    handler.my_summary(&user).await.map_or_else(
+        |err| err.into_response(),
+        |response| response.into_response(),
+    )
+}
+
+async fn product_stream(
+    State(handler): State<SharedHandler>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    Sse::new(event_stream(handler.subscribe())).keep_alive(KeepAlive::default())
+}
+
+/// Turn a broadcast subscription into an SSE stream
+///
+/// A subscriber that falls behind the channel capacity receives a `lagged`
+/// event with a reconnect hint and the stream ends, so it can resync.
+fn event_stream(receiver: Receiver<ProductEvent>) -> impl Stream<Item = Result<Event, Infallible>> {
+    stream::unfold(Some(receiver), |receiver| async move {
+        let mut receiver = receiver?;
+
+        match receiver.recv().await {
+            Ok(event) => {
+                let sse_event = Event::default()
+                    .event(event.name())
+                    .json_data(&event)
+                    .unwrap_or_else(|_| Event::default().event(event.name()));
+                Some((Ok(sse_event), Some(receiver)))
+            }
+            Err(RecvError::Lagged(skipped)) => {
+                tracing::warn!("Product stream subscriber lagged by {} events", skipped);
+                let hint = Event::default()
+                    .event("lagged")
+                    .data("Subscriber fell behind, please reconnect")
+                    .retry(STREAM_RECONNECT_DELAY);
+                Some((Ok(hint), None))
+            }
+            Err(RecvError::Closed) => None,
+        }
+    })
+}
+
 async fn create_product(
     State(handler): State<SharedHandler>,
-    Json(product): Json<CreateProduct>,
+    Extension(user): Extension<CurrentUser>,
+    ApiJsonOrForm(product): ApiJsonOrForm<CreateProduct>,
+) -> impl IntoResponse {
+    handler.create_product(product, &user).await.map_or_else(
+        |err| err.into_response(),
+        |response| response.into_response(),
+    )
+}
+
+async fn duplicate_product(
+    State(handler): State<SharedHandler>,
+    Extension(user): Extension<CurrentUser>,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    handler.duplicate_product(id, &user).await.map_or_else(
+        |err| err.into_response(),
+        |response| response.into_response(),
+    )
+}
+
+async fn restore_product(
+    State(handler): State<SharedHandler>,
+    Extension(user): Extension<CurrentUser>,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    handler.restore_product(id, &user).await.map_or_else(
+        |err| err.into_response(),
+        |response| response.into_response(),
+    )
+}
+
+async fn touch_product(
+    State(handler): State<SharedHandler>,
+    AuthUser(user): AuthUser,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    handler.touch_product(id, &user).await.map_or_else(
+        |err| err.into_response(),
+        |response| response.into_response(),
+    )
+}
+
+async fn product_audit(
+    State(handler): State<SharedHandler>,
+    AuthUser(user): AuthUser,
+    Path(id): Path<Uuid>,
+    page: PageParams,
+) -> impl IntoResponse {
+    handler.product_audit(id, page, &user).await.map_or_else(
+        |err| err.into_response(),
+        |response| response.into_response(),
+    )
+}
+
+async fn favorite_product(
+    State(handler): State<SharedHandler>,
+    AuthUser(user): AuthUser,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    handler.favorite_product(id, &user).await.map_or_else(
+        |err| err.into_response(),
+        |response| response.into_response(),
+    )
+}
+
+async fn unfavorite_product(
+    State(handler): State<SharedHandler>,
+    AuthUser(user): AuthUser,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    handler.unfavorite_product(id, &user).await.map_or_else(
+        |err| err.into_response(),
+        |response| response.into_response(),
+    )
+}
+
+async fn list_favorites(
+    State(handler): State<SharedHandler>,
+    AuthUser(user): AuthUser,
+    page: PageParams,
+) -> impl IntoResponse {
+    handler.list_favorites(page, &user).await.map_or_else(
+        |err| err.into_response(),
+        |response| response.into_response(),
+    )
+}
+
+async fn transfer_stock(
+    State(handler): State<SharedHandler>,
+    Extension(user): Extension<CurrentUser>,
+    ApiJson(request): ApiJson<TransferStock>,
 ) -> impl IntoResponse {
-    handler.create_product(product).await.map_or_else(
+    handler.transfer_stock(request, &user).await.map_or_else(
         |err| err.into_response(),
         |response| response.into_response(),
     )
 }
 
+async fn add_product_tags(
+    State(handler): State<SharedHandler>,
+    Extension(user): Extension<CurrentUser>,
+    Path(id): Path<Uuid>,
+    ApiJson(request): ApiJson<AddProductTags>,
+) -> impl IntoResponse {
+    handler
+        .add_product_tags(id, request, &user)
+        .await
+        .map_or_else(
+            |err| err.into_response(),
+            |response| response.into_response(),
+        )
+}
+
+async fn remove_product_tag(
+    State(handler): State<SharedHandler>,
+    Extension(user): Extension<CurrentUser>,
+    Path((id, tag)): Path<(Uuid, String)>,
+) -> impl IntoResponse {
+    handler
+        .remove_product_tag(id, tag, &user)
+        .await
+        .map_or_else(
+            |err| err.into_response(),
+            |response| response.into_response(),
+        )
+}
+
+async fn upsert_product_by_sku(
+    State(handler): State<SharedHandler>,
+    Extension(user): Extension<CurrentUser>,
+    Path(sku): Path<String>,
+    ApiJson(product): ApiJson<CreateProduct>,
+) -> impl IntoResponse {
+    handler
+        .upsert_product_by_sku(sku, product, &user)
+        .await
+        .map_or_else(
+            |err| err.into_response(),
+            |response| response.into_response(),
+        )
+}
+
 async fn get_product(
     State(handler): State<SharedHandler>,
     Path(id): Path<Uuid>,
+    headers: HeaderMap,
 ) -> impl IntoResponse {
-    handler.get_product(id).await.map_or_else(
+    let if_none_match = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok());
+
+    handler.get_product(id, if_none_match).await.map_or_else(
+        |err| err.into_response(),
+        |response| response.into_response(),
+    )
+}
+
+async fn related_products(
+    State(handler): State<SharedHandler>,
+    Path(id): Path<Uuid>,
+    ApiQuery(query): ApiQuery<LimitQuery>,
+) -> impl IntoResponse {
+    handler.related_products(id, query).await.map_or_else(
         |err| err.into_response(),
         |response| response.into_response(),
     )
@@ -63,21 +400,330 @@ async fn get_product(
 
 async fn update_product(
     State(handler): State<SharedHandler>,
+    Extension(user): Extension<CurrentUser>,
+    Path(id): Path<Uuid>,
+    ApiQuery(query): ApiQuery<UpdateProductQuery>,
+    ApiJson(update): ApiJson<UpdateProduct>,
+) -> impl IntoResponse {
+    handler
+        .update_product(id, update, query, &user)
+        .await
+        .map_or_else(
+            |err| err.into_response(),
+            |response| response.into_response(),
+        )
+}
+
+async fn patch_product(
+    State(handler): State<SharedHandler>,
+    Extension(user): Extension<CurrentUser>,
     Path(id): Path<Uuid>,
-    Json(update): Json<UpdateProduct>,
+    headers: HeaderMap,
+    body: Bytes,
 ) -> impl IntoResponse {
-    handler.update_product(id, update).await.map_or_else(
+    let patch = match parse_json_patch(&headers, &body) {
+        Ok(patch) => patch,
+        Err(err) => return err.into_response(),
+    };
+
+    handler.patch_product(id, patch, &user).await.map_or_else(
         |err| err.into_response(),
         |response| response.into_response(),
     )
 }
 
+/// Parse an `application/json-patch+json` request body
+fn parse_json_patch(headers: &HeaderMap, body: &[u8]) -> Result<Patch, ApiError> {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+
+    if !content_type.starts_with(JSON_PATCH_CONTENT_TYPE) {
+        return Err(ApiError::BadRequest(format!(
+            "Content-Type must be {}",
+            JSON_PATCH_CONTENT_TYPE
+        )));
+    }
+
+    serde_json::from_slice(body)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid JSON patch: {}", e)))
+}
+
 async fn delete_product(
     State(handler): State<SharedHandler>,
+    Extension(user): Extension<CurrentUser>,
     Path(id): Path<Uuid>,
 ) -> impl IntoResponse {
-    handler.delete_product(id).await.map_or_else(
+    handler.delete_product(id, &user).await.map_or_else(
+        |err| err.into_response(),
+        |response| response.into_response(),
+    )
+}
+
+async fn bulk_create_products(
+    State(handler): State<SharedHandler>,
+    AuthUser(user): AuthUser,
+    ApiQuery(query): ApiQuery<BulkCreateQuery>,
+    ApiJson(request): ApiJson<BulkCreateProducts>,
+) -> impl IntoResponse {
+    handler
+        .bulk_create_products(request, query.mode, &user)
+        .await
+        .map_or_else(
+            |err| err.into_response(),
+            |response| response.into_response(),
+        )
+}
+
+async fn validate_product(
+    State(handler): State<SharedHandler>,
+    ApiJson(product): ApiJson<CreateProduct>,
+) -> impl IntoResponse {
+    handler.validate_product(product).await.map_or_else(
+        |err| err.into_response(),
+        |response| response.into_response(),
+    )
+}
+
+async fn batch_get_products(
+    State(handler): State<SharedHandler>,
+    ApiJson(request): ApiJson<BatchGetProducts>,
+) -> impl IntoResponse {
+    handler.batch_get_products(request).await.map_or_else(
+        |err| err.into_response(),
+        |response| response.into_response(),
+    )
+}
+
+async fn compare_products(
+    State(handler): State<SharedHandler>,
+    ApiJson(request): ApiJson<CompareProducts>,
+) -> impl IntoResponse {
+    handler.compare_products(request).await.map_or_else(
+        |err| err.into_response(),
+        |response| response.into_response(),
+    )
+}
+
+async fn undo_delete_product(
+    State(handler): State<SharedHandler>,
+    AuthUser(user): AuthUser,
+    ApiJson(request): ApiJson<UndoDeleteProduct>,
+) -> impl IntoResponse {
+    handler.undo_delete(request, &user).await.map_or_else(
+        |err| err.into_response(),
+        |response| response.into_response(),
+    )
+}
+
+async fn bulk_delete_products(
+    State(handler): State<SharedHandler>,
+    Extension(user): Extension<CurrentUser>,
+    ApiJson(request): ApiJson<BulkDeleteProducts>,
+) -> impl IntoResponse {
+    handler
+        .bulk_delete_products(request, &user)
+        .await
+        .map_or_else(
+            |err| err.into_response(),
+            |response| response.into_response(),
+        )
+}
+
+async fn recategorize_products(
+    State(handler): State<SharedHandler>,
+    Extension(user): Extension<CurrentUser>,
+    ApiJson(request): ApiJson<RecategorizeProducts>,
+) -> impl IntoResponse {
+    handler
+        .recategorize_products(request, &user)
+        .await
+        .map_or_else(
+            |err| err.into_response(),
+            |response| response.into_response(),
+        )
+}
+
+async fn adjust_prices(
+    State(handler): State<SharedHandler>,
+    Extension(user): Extension<CurrentUser>,
+    ApiJson(request): ApiJson<AdjustPrices>,
+) -> impl IntoResponse {
+    handler.adjust_prices(request, &user).await.map_or_else(
         |err| err.into_response(),
         |response| response.into_response(),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+    use futures::StreamExt;
+    use tokio::sync::broadcast;
+
+    #[test]
+    fn test_parse_json_patch_requires_patch_content_type() {
+        let body = br#"[{"op": "replace", "path": "/price", "value": 10}]"#;
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CONTENT_TYPE,
+            JSON_PATCH_CONTENT_TYPE.parse().unwrap(),
+        );
+        assert_eq!(parse_json_patch(&headers, body).unwrap().0.len(), 1);
+
+        headers.insert(header::CONTENT_TYPE, "application/json".parse().unwrap());
+        assert!(matches!(
+            parse_json_patch(&headers, body),
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_event_stream_emits_published_events() {
+        let (sender, receiver) = broadcast::channel(8);
+        let mut events = Box::pin(event_stream(receiver));
+
+        let id = Uuid::new_v4();
+        sender.send(ProductEvent::Deleted { id }).unwrap();
+
+        let event = events.next().await.unwrap().unwrap();
+        let rendered = format!("{:?}", event);
+        assert!(rendered.contains("deleted"));
+        assert!(rendered.contains(&id.to_string()));
+
+        drop(sender);
+        assert!(events.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_event_stream_ends_with_hint_when_lagging() {
+        let (sender, receiver) = broadcast::channel(1);
+        let mut events = Box::pin(event_stream(receiver));
+
+        for _ in 0..3 {
+            sender
+                .send(ProductEvent::Deleted { id: Uuid::new_v4() })
+                .unwrap();
+        }
+
+        let hint = events.next().await.unwrap().unwrap();
+        assert!(format!("{:?}", hint).contains("lagged"));
+        assert!(events.next().await.is_none());
+    }
+
+    /// POST to the product routes without the auth middleware, as a regular user
+    async fn post_as_user(uri: &str, body: serde_json::Value) -> (StatusCode, serde_json::Value) {
+        use crate::core::authentication::Role;
+        use axum::body::Body;
+        use tower::ServiceExt;
+
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .connect_lazy("postgres://postgres@127.0.0.1:1/unreachable")
+            .unwrap();
+        let app = product_routes(
+            DbPools::single(pool),
+            ProductCache::default(),
+            &FeatureFlags::default(),
+        );
+
+        let mut request = axum::http::Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        request.extensions_mut().insert(CurrentUser {
+            id: Uuid::new_v4(),
+            role: Role::User,
+        });
+
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    async fn bulk_create(mode: &str, body: serde_json::Value) -> (StatusCode, serde_json::Value) {
+        post_as_user(&format!("/api/products/bulk?mode={}", mode), body).await
+    }
+
+    #[tokio::test]
+    async fn test_bulk_create_all_or_nothing_rejects_batch_with_invalid_product() {
+        // Rejected during validation, before the (unreachable) database is used
+        let (status, body) = bulk_create(
+            "all-or-nothing",
+            serde_json::json!({ "products": [
+                { "name": "Laptop", "price": 100.0 },
+                { "name": "Mouse", "price": -1.0 },
+            ]}),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "Product #1: Price cannot be negative");
+    }
+
+    #[tokio::test]
+    async fn test_bulk_create_best_effort_reports_invalid_product() {
+        let (status, body) = bulk_create(
+            "best-effort",
+            serde_json::json!({ "products": [{ "name": "  ", "price": 100.0 }] }),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::MULTI_STATUS);
+        assert_eq!(body["created"], serde_json::json!([]));
+        assert_eq!(body["failed"][0]["index"], 0);
+        assert!(body["failed"][0]["reason"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_bulk_create_rejects_unknown_mode() {
+        let (status, _) = bulk_create(
+            "sometimes",
+            serde_json::json!({ "products": [{ "name": "Laptop", "price": 1.0 }] }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_validate_accepts_valid_payload() {
+        // The database is unreachable, so this also shows validation never touches it
+        let (status, body) = post_as_user(
+            "/api/products/validate",
+            serde_json::json!({ "name": " Laptop ", "price": 100.0, "category": "Electronics" }),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, serde_json::json!({ "valid": true }));
+    }
+
+    #[tokio::test]
+    async fn test_validate_lists_every_problem() {
+        let (status, body) = post_as_user(
+            "/api/products/validate",
+            serde_json::json!({ "name": "   ", "price": -1.0, "category": " " }),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "valid": false,
+                "problems": [
+                    { "field": "name", "message": "Name cannot be empty" },
+                    { "field": "category", "message": "Category cannot be empty" },
+                    { "field": "price", "message": "Price cannot be negative" },
+                ]
+            })
+        );
+    }
+}
@@ -1,82 +1,2189 @@
-use crate::core::error::ApiError;
-use crate::modules::product::model::{CreateProduct, ProductFilter, UpdateProduct};
+use crate::core::{
+    authentication::CurrentUser,
+    config::FeatureFlags,
+    error::ApiError,
+    pagination::{Page, PageParams},
+};
+use crate::modules::product::cache::ProductCache;
+use crate::modules::product::model::{
+    AddProductTags, AdjustPrices, BatchGetProducts, BulkCreateFailure, BulkCreateMode,
+    BulkCreateProducts, BulkDeleteProducts, CategoryMode, CompareProducts, ComparisonSummary,
+    CreateProduct, LimitQuery, PATCHABLE_FIELDS, PRODUCT_FIELDS, PriceBucketQuery, Product,
+    ProductComparison, ProductEvent, ProductFilter, ProductValidation, RecategorizeProducts,
+    SearchQuery, TransferStock, UndoDeleteProduct, UpdateProduct, UpdateProductQuery,
+    ValidationProblem,
+};
 use crate::modules::product::repository::ProductRepository;
-use axum::{Json, http::StatusCode, response::IntoResponse};
-use serde_json::json;
+use crate::utils::{
+    UndoDelete, generate_undo_token, normalize_whitespace, require_non_empty, verify_undo_token,
+};
+use axum::{
+    Json,
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use json_patch::{Patch, PatchOperation};
+use serde_json::{Map, Value, json};
+use std::collections::{HashMap, HashSet};
+use tokio::sync::broadcast;
 use uuid::Uuid;
 
+const CSV_CONTENT_TYPE: &str = "text/csv; charset=utf-8";
+const EVENT_CHANNEL_CAPACITY: usize = 100;
+const DEFAULT_RELATED_LIMIT: i64 = 5;
+const MAX_RELATED_LIMIT: i64 = 50;
+const DEFAULT_RECENT_LIMIT: i64 = 20;
+const MAX_RECENT_LIMIT: i64 = 100;
+const DEFAULT_FEATURED_LIMIT: i64 = 20;
+const MAX_FEATURED_LIMIT: i64 = 100;
+const DEFAULT_CURSOR_LIMIT: i64 = 20;
+const MAX_CURSOR_LIMIT: i64 = 100;
+const DEFAULT_SEARCH_LIMIT: i64 = 20;
+const MAX_SEARCH_LIMIT: i64 = 100;
+const DEFAULT_PRICE_BUCKETS: i32 = 5;
+const MAX_PRICE_BUCKETS: i32 = 20;
+const MAX_TAG_LENGTH: usize = 50;
+const MAX_BATCH_GET_IDS: usize = 200;
+const MAX_COMPARE_IDS: usize = 20;
+const TOO_FEW_TO_COMPARE: &str = "At least two different product IDs are required to compare";
+const MAX_BULK_CREATE_PRODUCTS: usize = 500;
+
 /// Product HTTP request handlers
 pub struct ProductService {
     repository: ProductRepository, // Product repository for database operations
+    events: broadcast::Sender<ProductEvent>, // Change notifications for stream subscribers
+    default_initial_stock: i32,    // Stock of new products created without one
+    min_initial_stock: i32,        // Lowest stock a new product may be created with
+    category_mode: CategoryMode,   // Free-text or fixed category list
+    max_products_per_category: Option<i64>, // Cap on active products per owner and category
+    name_max: usize,               // Longest allowed name, in characters
+    description_max: usize,        // Longest allowed description, in characters
+    undo_window: chrono::Duration, // How long a delete can be undone
+    cache: ProductCache,           // TTL cache for single-product reads
 }
 
 impl ProductService {
     /// Create a new product handler
-    pub fn new(repository: ProductRepository) -> Self {
-        Self { repository }
+    pub fn new(repository: ProductRepository, cache: ProductCache, flags: &FeatureFlags) -> Self {
+        let (events, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        Self {
+            repository,
+            events,
+            cache,
+            default_initial_stock: flags.default_initial_stock,
+            min_initial_stock: flags.min_initial_stock,
+            category_mode: CategoryMode::parse(&flags.category_mode, &flags.categories.join(",")),
+            max_products_per_category: flags.max_products_per_category,
+            name_max: flags.product_name_max,
+            description_max: flags.product_desc_max,
+            undo_window: chrono::Duration::seconds(flags.undo_delete_window_secs),
+        }
+    }
+
+    /// Subscribe to product change notifications
+    pub fn subscribe(&self) -> broadcast::Receiver<ProductEvent> {
+        self.events.subscribe()
+    }
+
+    // Helper method to notify subscribers, ignoring the case where nobody is listening
+    //
+    // Every product mutation publishes an event, so this is also where cached reads are invalidated.
+    fn publish(&self, event: ProductEvent) {
+        self.cache.invalidate(event.aggregate_id());
+        let _ = self.events.send(event);
     }
 
     /// Handler to create a new product
     pub async fn create_product(
         &self,
-        product: CreateProduct,
+        mut product: CreateProduct,
+        user: &CurrentUser,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        self.validate_new_product(&mut product)?;
+
+        // Enforce the per-category cap for the caller's own products
+        if let (Some(cap), Some(category)) =
+            (self.max_products_per_category, product.category.as_deref())
+        {
+            let count = self
+                .repository
+                .count_active_in_category(category, user.id)
+                .await?;
+            ensure_below_category_cap(count, cap, category)?;
+        }
+
+        // Create product owned by the caller and return with 201 status
+        let created_product = self.repository.create(&product, Some(user.id)).await?;
+        self.publish(ProductEvent::Created {
+            product: created_product.clone(),
+        });
+
+        Ok((StatusCode::CREATED, Json(created_product)))
+    }
+
+    /// Handler to check a create payload without saving it
+    ///
+    /// Runs the same checks as `create_product` except the per-category cap,
+    /// which needs the database, and reports every problem at once.
+    pub async fn validate_product(
+        &self,
+        mut product: CreateProduct,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        let problems = self.new_product_problems(&mut product);
+        Ok(Json(product_validation(problems)))
+    }
+
+    /// Handler to create several products owned by the caller in one request
+    ///
+    /// In `all-or-nothing` mode (the default) either every product is created or
+    /// none is, and the first failing product is reported as the error. In
+    /// `best-effort` mode each product is created on its own: the response is 201
+    /// when all were created and 207 Multi-Status otherwise, listing the failures.
+    pub async fn bulk_create_products(
+        &self,
+        request: BulkCreateProducts,
+        mode: BulkCreateMode,
+        user: &CurrentUser,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        if request.products.is_empty() {
+            return Err(ApiError::BadRequest("No products provided".to_string()));
+        }
+        if request.products.len() > MAX_BULK_CREATE_PRODUCTS {
+            return Err(ApiError::BadRequest(format!(
+                "At most {} products can be created at once",
+                MAX_BULK_CREATE_PRODUCTS
+            )));
+        }
+
+        // Validate everything before touching the database
+        let mut failed = Vec::new();
+        let mut valid = Vec::with_capacity(request.products.len());
+        let mut category_counts = HashMap::new();
+        for (index, mut product) in request.products.into_iter().enumerate() {
+            match self
+                .check_bulk_product(&mut product, user, &mut category_counts)
+                .await
+            {
+                Ok(()) => valid.push((index, product)),
+                Err(error) if mode == BulkCreateMode::AllOrNothing => {
+                    return Err(bulk_item_error(index, error));
+                }
+                Err(error) => failed.push(bulk_failure(index, &error)),
+            }
+        }
+
+        let (indices, products): (Vec<usize>, Vec<CreateProduct>) = valid.into_iter().unzip();
+        let results = self
+            .repository
+            .create_many(&products, user.id, mode)
+            .await?;
+
+        let mut created = Vec::with_capacity(results.len());
+        for (index, result) in indices.into_iter().zip(results) {
+            match result {
+                Ok(product) => created.push(product),
+                // Nothing was committed, so there is nothing to publish
+                Err(error) if mode == BulkCreateMode::AllOrNothing => {
+                    return Err(bulk_item_error(index, error));
+                }
+                Err(error) => failed.push(bulk_failure(index, &error)),
+            }
+        }
+
+        for product in &created {
+            self.publish(ProductEvent::Created {
+                product: product.clone(),
+            });
+        }
+
+        Ok(bulk_create_response(created, failed))
+    }
+
+    // Helper method to validate one product of a bulk create, counting it against the category cap
+    async fn check_bulk_product(
+        &self,
+        product: &mut CreateProduct,
+        user: &CurrentUser,
+        category_counts: &mut HashMap<String, i64>,
+    ) -> Result<(), ApiError> {
+        self.validate_new_product(product)?;
+
+        if let (Some(cap), Some(category)) =
+            (self.max_products_per_category, product.category.clone())
+        {
+            let count = match category_counts.get(&category) {
+                Some(count) => *count,
+                None => {
+                    self.repository
+                        .count_active_in_category(&category, user.id)
+                        .await?
+                }
+            };
+            ensure_below_category_cap(count, cap, &category)?;
+            category_counts.insert(category, count + 1);
+        }
+
+        Ok(())
+    }
+
+    /// Handler to duplicate a product as a new inactive copy owned by the caller
+    pub async fn duplicate_product(
+        &self,
+        id: Uuid,
+        user: &CurrentUser,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        let copy = self.repository.duplicate(id, user.id).await?;
+        self.publish(ProductEvent::Created {
+            product: copy.clone(),
+        });
+
+        Ok((StatusCode::CREATED, Json(copy)))
+    }
+
+    /// Handler to create or update a product identified by SKU
+    pub async fn upsert_product_by_sku(
+        &self,
+        sku: String,
+        mut product: CreateProduct,
+        user: &CurrentUser,
     ) -> Result<impl IntoResponse + '_, ApiError> {
+        let sku = sku.trim();
+        if sku.is_empty() {
+            return Err(ApiError::BadRequest("SKU cannot be empty".to_string()));
+        }
+
+        normalize_create_product(&mut product)?;
+        product.apply_default_stock(self.default_initial_stock);
+        product.category = self.check_category(product.category)?;
+        self.validate_lengths(Some(&product.name), product.description.as_deref())?;
+
         // Validate price
         self.validate_price(product.price)?;
 
-        // Create product in database and return with 201 status
-        let created_product = self.repository.create(&product).await?;
-        Ok((StatusCode::CREATED, Json(created_product)))
+        let (product, created) = self
+            .repository
+            .upsert_by_sku(sku, &product, user.id, user.is_admin())
+            .await?;
+        self.publish(ProductEvent::upserted(product.clone(), created));
+
+        Ok((upsert_status(created), Json(product)))
     }
 
     /// Handler to get a product by ID
-    pub async fn get_product(&self, id: Uuid) -> Result<impl IntoResponse + '_, ApiError> {
-        let product = self.repository.find_by_id(id).await?;
-        Ok(Json(product))
+    ///
+    /// Returns an `ETag` header and answers `304 Not Modified` when `if_none_match` matches it.
+    pub async fn get_product(
+        &self,
+        id: Uuid,
+        if_none_match: Option<&str>,
+    ) -> Result<Response, ApiError> {
+        let product = self
+            .cache
+            .get_or_load(id, self.repository.find_by_id(id))
+            .await?;
+        let etag = product_etag(&product);
+
+        if if_none_match.is_some_and(|header| etag_matches(header, &etag)) {
+            return Ok((StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response());
+        }
+
+        Ok(([(header::ETAG, etag)], Json(product)).into_response())
+    }
+
+    /// Handler to fetch several products by ID in one request
+    ///
+    /// Products are returned in no particular order; IDs that don't exist are listed in `missing`.
+    pub async fn batch_get_products(
+        &self,
+        request: BatchGetProducts,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        if request.ids.is_empty() {
+            return Err(ApiError::BadRequest("No product IDs provided".to_string()));
+        }
+        if request.ids.len() > MAX_BATCH_GET_IDS {
+            return Err(ApiError::BadRequest(format!(
+                "At most {} product IDs can be fetched at once",
+                MAX_BATCH_GET_IDS
+            )));
+        }
+
+        let products = self.repository.find_by_ids(&request.ids).await?;
+        Ok(Json(batch_get_body(&request.ids, products)))
+    }
+
+    /// Handler to compare products side by side
+    ///
+    /// Duplicate IDs are ignored, products come back in the order they were
+    /// requested, and any unknown ID fails the whole comparison with 404.
+    pub async fn compare_products(
+        &self,
+        request: CompareProducts,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        let mut seen = HashSet::new();
+        let ids: Vec<Uuid> = request
+            .ids
+            .into_iter()
+            .filter(|id| seen.insert(*id))
+            .collect();
+        if ids.len() > MAX_COMPARE_IDS {
+            return Err(ApiError::BadRequest(format!(
+                "At most {} products can be compared at once",
+                MAX_COMPARE_IDS
+            )));
+        }
+
+        let products = self.repository.find_by_ids(&ids).await?;
+        Ok(Json(product_comparison(&ids, products)?))
     }
 
     /// Handler to list products with optional filtering
+    ///
+    /// Renders CSV when `as_csv` is set (negotiated from the `Accept` header), JSON otherwise.
+    /// When a `cursor` is given the JSON body is `{ items, next_cursor }` instead of an array.
+    /// Anonymous callers (`user` is `None`) may list everything except `mine=true`.
     pub async fn list_products(
         &self,
-        filter: ProductFilter,
+        mut filter: ProductFilter,
+        user: Option<&CurrentUser>,
+        as_csv: bool,
     ) -> Result<impl IntoResponse + '_, ApiError> {
+        self.resolve_filter(&mut filter, user)?;
+
+        // Validate requested fields and cursor before hitting the database
+        let fields = filter.fields.as_deref().map(parse_fields).transpose()?;
+        let cursor_mode = filter.cursor.is_some();
+        if let Some(cursor) = filter.cursor.as_deref().filter(|cursor| !cursor.is_empty()) {
+            filter.after = Some(decode_cursor(cursor)?);
+        }
+        if cursor_mode {
+            filter.limit = Some(
+                filter
+                    .limit
+                    .unwrap_or(DEFAULT_CURSOR_LIMIT)
+                    .clamp(1, MAX_CURSOR_LIMIT),
+            );
+        }
+
         let products = self.repository.list(&filter).await?;
+
+        if cursor_mode && !as_csv {
+            // A full page means there may be more rows after the last item
+            let next_cursor = products
+                .last()
+                .filter(|_| products.len() as i64 == filter.limit.unwrap_or_default())
+                .map(|product| encode_cursor(&product.name, product.id));
+            let items = match fields {
+                Some(fields) => json!(select_fields(&products, &fields)?),
+                None => json!(products),
+            };
+
+            return Ok(Json(json!({ "items": items, "next_cursor": next_cursor })).into_response());
+        }
+
+        if as_csv {
+            let columns = fields.unwrap_or_else(|| PRODUCT_FIELDS.to_vec());
+            let csv = products_to_csv(&products, &columns)?;
+            return Ok(([(header::CONTENT_TYPE, CSV_CONTENT_TYPE)], csv).into_response());
+        }
+
+        match fields {
+            Some(fields) => Ok(Json(select_fields(&products, &fields)?).into_response()),
+            None => Ok(Json(products).into_response()),
+        }
+    }
+
+    /// Handler to count the products matching the list filters, without fetching them
+    pub async fn count_products(
+        &self,
+        mut filter: ProductFilter,
+        user: Option<&CurrentUser>,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        self.resolve_filter(&mut filter, user)?;
+
+        let count = self.repository.count(&filter).await?;
+        Ok(Json(json!({ "count": count })))
+    }
+
+    /// Handler to list active featured products, most recently updated first
+    pub async fn featured_products(
+        &self,
+        query: LimitQuery,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        let limit = query.resolve(DEFAULT_FEATURED_LIMIT, MAX_FEATURED_LIMIT);
+
+        let products = self.repository.list_featured(limit).await?;
+        Ok(Json(products))
+    }
+
+    /// Handler to list the most recently updated products
+    pub async fn recent_products(
+        &self,
+        query: LimitQuery,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        let limit = query.resolve(DEFAULT_RECENT_LIMIT, MAX_RECENT_LIMIT);
+
+        let products = self.repository.list_recent(limit).await?;
+        Ok(Json(products))
+    }
+
+    /// Handler to search products ranked by relevance
+    ///
+    /// An empty or missing search term returns an empty list.
+    pub async fn search_products(
+        &self,
+        query: SearchQuery,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        let Some(term) = query
+            .q
+            .as_deref()
+            .map(str::trim)
+            .filter(|term| !term.is_empty())
+        else {
+            return Ok(Json(Vec::new()));
+        };
+
+        let limit = query
+            .limit
+            .unwrap_or(DEFAULT_SEARCH_LIMIT)
+            .clamp(1, MAX_SEARCH_LIMIT);
+
+        let products = self.repository.search_ranked(term, limit).await?;
+        Ok(Json(products))
+    }
+
+    /// Handler to get aggregate product statistics
+    pub async fn product_stats(&self) -> Result<impl IntoResponse + '_, ApiError> {
+        let stats = self.repository.stats().await?;
+        Ok(Json(stats))
+    }
+
+    /// Handler to return a histogram of active product prices
+    pub async fn price_buckets(
+        &self,
+        query: PriceBucketQuery,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        let buckets = query
+            .buckets
+            .unwrap_or(DEFAULT_PRICE_BUCKETS)
+            .clamp(1, MAX_PRICE_BUCKETS);
+
+        let buckets = self.repository.price_buckets(buckets).await?;
+        Ok(Json(buckets))
+    }
+
+    /// Handler to summarize the products owned by the caller
+    pub async fn my_summary(&self, user: &CurrentUser) -> Result<impl IntoResponse + '_, ApiError> {
+        let summary = self.repository.owner_summary(user.id).await?;
+        Ok(Json(summary))
+    }
+
+    /// Handler to list products related to the given product
+    pub async fn related_products(
+        &self,
+        id: Uuid,
+        query: LimitQuery,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        let limit = query.resolve(DEFAULT_RELATED_LIMIT, MAX_RELATED_LIMIT);
+
+        let products = self.repository.find_related(id, limit).await?;
         Ok(Json(products))
     }
 
     /// Handler to update a product
+    ///
+    /// With `dry_run` the merged result is computed and returned together with
+    /// the list of changed fields, but nothing is saved or published.
     pub async fn update_product(
         &self,
         id: Uuid,
-        update: UpdateProduct,
-    ) -> Result<impl IntoResponse + '_, ApiError> {
+        mut update: UpdateProduct,
+        query: UpdateProductQuery,
+        user: &CurrentUser,
+    ) -> Result<Response, ApiError> {
+        normalize_update_product(&mut update)?;
+        if let Some(category) = update.category.take() {
+            update.category = Some(self.check_category(category)?);
+        }
+        self.validate_update_lengths(&update)?;
+
         // Validate price if provided
         if let Some(price) = update.price {
             self.validate_price(price)?;
         }
 
-        let updated_product = self.repository.update(id, &update).await?;
+        let product = self.repository.find_by_id(id).await?;
+        ensure_can_modify(&product, user)?;
+
+        if query.dry_run == Some(true) {
+            let (current, preview) = self.repository.preview_update(id, &update).await?;
+            return Ok(Json(json!({
+                "dry_run": true,
+                "changed_fields": changed_fields(&current, &preview),
+                "product": preview,
+            }))
+            .into_response());
+        }
+
+        let updated_product = self.repository.update(id, &update, Some(user.id)).await?;
+        self.publish(ProductEvent::Updated {
+            product: updated_product.clone(),
+        });
+
+        Ok(Json(updated_product).into_response())
+    }
+
+    /// Handler to apply an RFC 6902 JSON patch to a product
+    ///
+    /// The operations run against the product's JSON representation; the
+    /// result goes through the same normalization and validation as a regular update.
+    pub async fn patch_product(
+        &self,
+        id: Uuid,
+        patch: Patch,
+        user: &CurrentUser,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        validate_patch(&patch)?;
+
+        let product = self.repository.find_by_id(id).await?;
+        ensure_can_modify(&product, user)?;
+
+        let mut update = apply_patch(&product, &patch)?;
+        normalize_update_product(&mut update)?;
+        if let Some(category) = update.category.take() {
+            update.category = Some(self.check_category(category)?);
+        }
+        self.validate_update_lengths(&update)?;
+        if let Some(price) = update.price {
+            self.validate_price(price)?;
+        }
+
+        let updated_product = self.repository.update(id, &update, Some(user.id)).await?;
+        self.publish(ProductEvent::Updated {
+            product: updated_product.clone(),
+        });
+
         Ok(Json(updated_product))
     }
 
+    /// Handler to list the per-field change history of a product, newest first (admin only)
+    pub async fn product_audit(
+        &self,
+        id: Uuid,
+        page: PageParams,
+        user: &CurrentUser,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        if !user.is_admin() {
+            return Err(ApiError::Forbidden(
+                "Only admins can view the product audit log".to_string(),
+            ));
+        }
+
+        // 404 for unknown products rather than an empty history
+        self.repository.find_by_id(id).await?;
+
+        let entries = self
+            .repository
+            .list_audit(id, page.limit, page.offset)
+            .await?;
+        Ok(Json(Page::new(entries, page)))
+    }
+
+    /// Handler to add a product to the caller's favorites
+    ///
+    /// Idempotent: favoriting a product twice keeps a single entry.
+    pub async fn favorite_product(
+        &self,
+        id: Uuid,
+        user: &CurrentUser,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        // 404 for unknown products rather than a foreign key error
+        self.repository.find_by_id(id).await?;
+
+        self.repository.add_favorite(user.id, id).await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    /// Handler to remove a product from the caller's favorites (no-op if it isn't one)
+    pub async fn unfavorite_product(
+        &self,
+        id: Uuid,
+        user: &CurrentUser,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        self.repository.remove_favorite(user.id, id).await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    /// Handler to list the caller's favorite products, most recently favorited first
+    pub async fn list_favorites(
+        &self,
+        page: PageParams,
+        user: &CurrentUser,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        let products = self
+            .repository
+            .list_favorites(user.id, page.limit, page.offset)
+            .await?;
+        Ok(Json(Page::new(products, page)))
+    }
+
+    /// Handler to attach tags to a product, creating unknown tags on demand
+    pub async fn add_product_tags(
+        &self,
+        id: Uuid,
+        request: AddProductTags,
+        user: &CurrentUser,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        let tags = normalize_tags(&request.tags)?;
+
+        let product = self.repository.find_by_id(id).await?;
+        ensure_can_modify(&product, user)?;
+
+        let tagged = self.repository.add_tags(id, &tags).await?;
+        self.publish(ProductEvent::Updated {
+            product: tagged.clone(),
+        });
+
+        Ok(Json(tagged))
+    }
+
+    /// Handler to detach a tag from a product
+    pub async fn remove_product_tag(
+        &self,
+        id: Uuid,
+        tag: String,
+        user: &CurrentUser,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        let tag = normalize_tag(&tag)?;
+
+        let product = self.repository.find_by_id(id).await?;
+        ensure_can_modify(&product, user)?;
+
+        let untagged = self.repository.remove_tag(id, &tag).await?;
+        self.publish(ProductEvent::Updated {
+            product: untagged.clone(),
+        });
+
+        Ok(Json(untagged))
+    }
+
+    /// Handler to reactivate an inactive product (admin only)
+    pub async fn restore_product(
+        &self,
+        id: Uuid,
+        user: &CurrentUser,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        if !user.is_admin() {
+            return Err(ApiError::Forbidden(
+                "Only admins can restore products".to_string(),
+            ));
+        }
+
+        let restored = self.repository.restore(id).await?;
+        self.publish(ProductEvent::Updated {
+            product: restored.clone(),
+        });
+
+        Ok(Json(restored))
+    }
+
+    /// Handler to move a product's `updated_at` forward without changing anything else
+    ///
+    /// Useful to force caches keyed on the ETag or `updated_at` to refresh.
+    pub async fn touch_product(
+        &self,
+        id: Uuid,
+        user: &CurrentUser,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        let product = self.repository.find_by_id(id).await?;
+        ensure_can_modify(&product, user)?;
+
+        let touched = self.repository.touch(id).await?;
+        self.publish(ProductEvent::Updated {
+            product: touched.clone(),
+        });
+
+        Ok(Json(touched))
+    }
+
+    /// Handler to move stock between two products the caller may modify
+    pub async fn transfer_stock(
+        &self,
+        request: TransferStock,
+        user: &CurrentUser,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        validate_stock_transfer(&request)?;
+
+        for id in [request.from, request.to] {
+            let product = self.repository.find_by_id(id).await?;
+            ensure_can_modify(&product, user)?;
+        }
+
+        let (from, to) = self
+            .repository
+            .transfer_stock(request.from, request.to, request.quantity)
+            .await?;
+        self.publish(ProductEvent::Updated {
+            product: from.clone(),
+        });
+        self.publish(ProductEvent::Updated {
+            product: to.clone(),
+        });
+
+        Ok(Json(json!({ "from": from, "to": to })))
+    }
+
     /// Handler to delete a product
-    pub async fn delete_product(&self, id: Uuid) -> Result<impl IntoResponse + '_, ApiError> {
-        self.repository.delete(id).await?;
+    pub async fn delete_product(
+        &self,
+        id: Uuid,
+        user: &CurrentUser,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        let product = self.repository.find_by_id(id).await?;
+        ensure_can_modify(&product, user)?;
+
+        let undo_id = self.repository.delete(id).await?;
+        let expires_at = chrono::Utc::now() + self.undo_window;
+        self.publish(ProductEvent::Deleted { id });
+
+        let undo = UndoDelete {
+            product_id: id,
+            undo_id,
+            deleted_by: user.id,
+        };
+        let undo_token = generate_undo_token(&undo, expires_at)?;
 
         Ok((
             StatusCode::OK,
             Json(json!({
                 "success": true,
-                "message": format!("Product with ID {} successfully deleted", id)
+                "message": format!("Product with ID {} successfully deleted", id),
+                "undo_token": undo_token,
+                "undo_expires_at": expires_at,
             })),
         ))
     }
 
-    // Helper method to validate price
-    fn validate_price(&self, price: f64) -> Result<(), ApiError> {
-        if price < 0.0 {
-            return Err(ApiError::BadRequest("Price cannot be negative".to_string()));
+    /// Handler to restore a deleted product from its undo token
+    pub async fn undo_delete(
+        &self,
+        request: UndoDeleteProduct,
+        user: &CurrentUser,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        let undo = verify_undo_token(&request.undo_token)?;
+        ensure_can_undo(&undo, user)?;
+
+        let product = self
+            .repository
+            .undo_delete(undo.undo_id, undo.product_id)
+            .await?;
+        self.publish(ProductEvent::Created {
+            product: product.clone(),
+        });
+
+        Ok(Json(product))
+    }
+
+    /// Handler to delete several products at once (admin only)
+    pub async fn bulk_delete_products(
+        &self,
+        request: BulkDeleteProducts,
+        user: &CurrentUser,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        if !user.is_admin() {
+            return Err(ApiError::Forbidden(
+                "Only admins can bulk delete products".to_string(),
+            ));
+        }
+
+        if request.ids.is_empty() {
+            return Err(ApiError::BadRequest("No product IDs provided".to_string()));
+        }
+
+        let deleted = self.repository.delete_many(&request.ids).await?;
+        for id in &deleted {
+            self.publish(ProductEvent::Deleted { id: *id });
+        }
+
+        Ok(Json(json!({
+            "deleted": deleted.len(),
+            "not_found": missing_ids(&request.ids, &deleted),
+        })))
+    }
+
+    /// Handler to move all products in one category to another (admin only)
+    pub async fn recategorize_products(
+        &self,
+        mut request: RecategorizeProducts,
+        user: &CurrentUser,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        if !user.is_admin() {
+            return Err(ApiError::Forbidden(
+                "Only admins can recategorize products".to_string(),
+            ));
+        }
+
+        request.from = normalize_whitespace(&request.from);
+        request.to = normalize_whitespace(&request.to);
+        validate_recategorize(&request)?;
+        request.to = resolve_category(&self.category_mode, &request.to)?;
+
+        let updated = self
+            .repository
+            .rename_category(&request.from, &request.to)
+            .await?;
+        let count = updated.len();
+        for product in updated {
+            self.publish(ProductEvent::Updated { product });
         }
+
+        Ok(Json(json!({
+            "from": request.from,
+            "to": request.to,
+            "updated": count,
+        })))
+    }
+
+    /// Handler to change the price of every product in a category by a percentage (admin only)
+    pub async fn adjust_prices(
+        &self,
+        mut request: AdjustPrices,
+        user: &CurrentUser,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        if !user.is_admin() {
+            return Err(ApiError::Forbidden(
+                "Only admins can adjust product prices".to_string(),
+            ));
+        }
+
+        request.category = require_non_empty(normalize_whitespace(&request.category), "Category")?;
+        request.category = resolve_category(&self.category_mode, &request.category)?;
+        validate_price_adjustment(request.percent)?;
+
+        let updated = self
+            .repository
+            .adjust_prices(&request.category, request.percent)
+            .await?;
+        let count = updated.len();
+        for product in updated {
+            self.publish(ProductEvent::Updated { product });
+        }
+
+        Ok(Json(json!({
+            "category": request.category,
+            "percent": request.percent,
+            "updated": count,
+        })))
+    }
+
+    // Helper method to resolve `mine`, the tag and the category of a list filter
+    fn resolve_filter(
+        &self,
+        filter: &mut ProductFilter,
+        user: Option<&CurrentUser>,
+    ) -> Result<(), ApiError> {
+        if filter.mine == Some(true) {
+            let user = user.ok_or_else(|| {
+                ApiError::Unauthorized("Authentication required for mine=true".to_string())
+            })?;
+            filter.owner_id = Some(user.id);
+        }
+        filter.tag = filter.tag.as_deref().map(normalize_tag).transpose()?;
+        filter.category = self.check_category(filter.category.take())?;
         Ok(())
     }
+
+    // Helper method to map a category to its configured spelling, rejecting unknown ones in closed mode
+    fn check_category(&self, category: Option<String>) -> Result<Option<String>, ApiError> {
+        category
+            .map(|category| resolve_category(&self.category_mode, &category))
+            .transpose()
+    }
+
+    // Helper method to normalize and validate a product before it is created
+    fn validate_new_product(&self, product: &mut CreateProduct) -> Result<(), ApiError> {
+        match self.new_product_problems(product).into_iter().next() {
+            Some((_, error)) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    // Helper method to normalize a new product and collect every failed check, in field order
+    fn new_product_problems(&self, product: &mut CreateProduct) -> Vec<(&'static str, ApiError)> {
+        let mut problems = Vec::new();
+        product.apply_default_stock(self.default_initial_stock);
+
+        match normalize_name(&product.name)
+            .and_then(|name| ensure_max_chars(name, "Name", self.name_max))
+        {
+            Ok(name) => product.name = name,
+            Err(error) => problems.push(("name", error)),
+        }
+        if let Some(description) = &product.description
+            && let Err(error) = ensure_max_chars(description, "Description", self.description_max)
+        {
+            problems.push(("description", error));
+        }
+        match normalize_category(product.category.take())
+            .and_then(|category| self.check_category(category))
+        {
+            Ok(category) => product.category = category,
+            Err(error) => problems.push(("category", error)),
+        }
+
+        // Validate price and initial stock
+        if let Err(error) = self.validate_price(product.price) {
+            problems.push(("price", error));
+        }
+        if let Err(error) = validate_initial_stock(product.stock, self.min_initial_stock) {
+            problems.push(("stock", error));
+        }
+
+        problems
+    }
+
+    // Helper method to reject names and descriptions over the configured lengths
+    fn validate_lengths(
+        &self,
+        name: Option<&str>,
+        description: Option<&str>,
+    ) -> Result<(), ApiError> {
+        if let Some(name) = name {
+            ensure_max_chars(name, "Name", self.name_max)?;
+        }
+        if let Some(description) = description {
+            ensure_max_chars(description, "Description", self.description_max)?;
+        }
+        Ok(())
+    }
+
+    // Helper method to apply `validate_lengths` to the fields present in an update
+    fn validate_update_lengths(&self, update: &UpdateProduct) -> Result<(), ApiError> {
+        self.validate_lengths(
+            update.name.as_deref(),
+            update
+                .description
+                .as_ref()
+                .and_then(|description| description.as_deref()),
+        )
+    }
+
+    // Helper method to validate price
+    fn validate_price(&self, price: f64) -> Result<(), ApiError> {
+        if price < 0.0 {
+            return Err(ApiError::BadRequest("Price cannot be negative".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Only the owner of a product or an admin may modify it
+fn ensure_can_modify(product: &Product, user: &CurrentUser) -> Result<(), ApiError> {
+    if user.is_admin() || product.owner_id == Some(user.id) {
+        return Ok(());
+    }
+
+    Err(ApiError::Forbidden(
+        "You do not have permission to modify this product".to_string(),
+    ))
+}
+
+/// Only the user who deleted a product, or an admin, may undo the delete
+fn ensure_can_undo(undo: &UndoDelete, user: &CurrentUser) -> Result<(), ApiError> {
+    if user.is_admin() || undo.deleted_by == user.id {
+        return Ok(());
+    }
+
+    Err(ApiError::Forbidden(
+        "You do not have permission to undo this delete".to_string(),
+    ))
+}
+
+/// A transfer needs two different products and a positive quantity
+fn validate_stock_transfer(request: &TransferStock) -> Result<(), ApiError> {
+    if request.from == request.to {
+        return Err(ApiError::BadRequest(
+            "Cannot transfer stock to the same product".to_string(),
+        ));
+    }
+
+    if request.quantity <= 0 {
+        return Err(ApiError::BadRequest(
+            "Quantity must be greater than zero".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Resolve a category against the configured mode
+fn resolve_category(mode: &CategoryMode, category: &str) -> Result<String, ApiError> {
+    match mode {
+        CategoryMode::Open => Ok(category.to_string()),
+        CategoryMode::Closed(allowed) => mode.resolve(category).ok_or_else(|| {
+            ApiError::BadRequest(format!(
+                "Unknown category '{}'; allowed categories: {}",
+                category,
+                allowed.join(", ")
+            ))
+        }),
+    }
+}
+
+/// Reject a new product once its category already holds `cap` active products
+fn ensure_below_category_cap(count: i64, cap: i64, category: &str) -> Result<(), ApiError> {
+    if count >= cap {
+        return Err(ApiError::Conflict(format!(
+            "Category '{}' already has the maximum of {} products",
+            category, cap
+        )));
+    }
+    Ok(())
+}
+
+/// A new product must start with at least the configured minimum stock
+fn validate_initial_stock(stock: Option<i32>, min_initial_stock: i32) -> Result<(), ApiError> {
+    if stock.unwrap_or(0) < min_initial_stock {
+        return Err(ApiError::BadRequest(
+            "Initial stock below minimum".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Reject a text field longer than `max` characters (not bytes)
+fn ensure_max_chars<T: AsRef<str>>(value: T, field: &str, max: usize) -> Result<T, ApiError> {
+    if value.as_ref().chars().count() > max {
+        return Err(ApiError::BadRequest(format!(
+            "{} must be at most {} characters",
+            field, max
+        )));
+    }
+    Ok(value)
+}
+
+/// Trim the name and category of a new product, collapsing internal whitespace
+fn normalize_create_product(product: &mut CreateProduct) -> Result<(), ApiError> {
+    product.name = normalize_name(&product.name)?;
+    product.category = normalize_category(product.category.take())?;
+    Ok(())
+}
+
+/// Collapse whitespace in a product name, which must not end up empty
+fn normalize_name(name: &str) -> Result<String, ApiError> {
+    require_non_empty(normalize_whitespace(name), "Name")
+}
+
+/// Collapse whitespace in an optional category, which must not end up empty when present
+fn normalize_category(category: Option<String>) -> Result<Option<String>, ApiError> {
+    category
+        .map(|category| require_non_empty(normalize_whitespace(&category), "Category"))
+        .transpose()
+}
+
+/// Same normalization as `normalize_create_product`, for the fields present in an update
+fn normalize_update_product(update: &mut UpdateProduct) -> Result<(), ApiError> {
+    update.name = update
+        .name
+        .as_deref()
+        .map(|name| require_non_empty(normalize_whitespace(name), "Name"))
+        .transpose()?;
+    if let Some(Some(category)) = &update.category {
+        update.category = Some(Some(require_non_empty(
+            normalize_whitespace(category),
+            "Category",
+        )?));
+    }
+    Ok(())
+}
+
+/// Only `add`, `replace` and `remove` on editable top-level fields are allowed
+fn validate_patch(patch: &Patch) -> Result<(), ApiError> {
+    if patch.0.is_empty() {
+        return Err(ApiError::BadRequest(
+            "Patch contains no operations".to_string(),
+        ));
+    }
+
+    for operation in &patch.0 {
+        if !matches!(
+            operation,
+            PatchOperation::Add(_) | PatchOperation::Replace(_) | PatchOperation::Remove(_)
+        ) {
+            return Err(ApiError::BadRequest(
+                "Only add, replace and remove operations are supported".to_string(),
+            ));
+        }
+
+        let field = operation
+            .path()
+            .first()
+            .map(|token| token.decoded().into_owned())
+            .unwrap_or_default();
+
+        if !PATCHABLE_FIELDS.contains(&field.as_str()) {
+            let reason = if PRODUCT_FIELDS.contains(&field.as_str()) {
+                format!("Field '{}' cannot be modified", field)
+            } else {
+                format!("Unknown field: '{}'", operation.path())
+            };
+            return Err(ApiError::BadRequest(reason));
+        }
+    }
+
+    Ok(())
+}
+
+/// Apply a patch to a product's JSON form and turn the result into a full update
+fn apply_patch(product: &Product, patch: &Patch) -> Result<UpdateProduct, ApiError> {
+    let mut document = serde_json::to_value(product)
+        .map_err(|e| ApiError::Internal(format!("Serialization error: {}", e)))?;
+
+    json_patch::patch(&mut document, &patch.0)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid patch: {}", e)))?;
+
+    let patched: Product = serde_json::from_value(document)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid patched product: {}", e)))?;
+
+    Ok(UpdateProduct {
+        sku: Some(patched.sku),
+        name: Some(patched.name),
+        description: Some(patched.description),
+        price: Some(patched.price),
+        stock: Some(patched.stock),
+        category: Some(patched.category),
+        is_active: Some(patched.is_active),
+        is_featured: Some(patched.is_featured),
+        version: product.version,
+    })
+}
+
+/// Tags are compared case-insensitively, so they are stored lowercased with single spaces
+fn normalize_tag(tag: &str) -> Result<String, ApiError> {
+    let tag = require_non_empty(normalize_whitespace(tag).to_lowercase(), "Tag")?;
+    if tag.chars().count() > MAX_TAG_LENGTH {
+        return Err(ApiError::BadRequest(format!(
+            "Tag cannot be longer than {} characters",
+            MAX_TAG_LENGTH
+        )));
+    }
+    Ok(tag)
+}
+
+/// Normalize a list of tags, dropping duplicates while keeping the first occurrence
+fn normalize_tags(tags: &[String]) -> Result<Vec<String>, ApiError> {
+    if tags.is_empty() {
+        return Err(ApiError::BadRequest("No tags provided".to_string()));
+    }
+
+    let mut seen = HashSet::new();
+    let mut normalized = Vec::new();
+    for tag in tags {
+        let tag = normalize_tag(tag)?;
+        if seen.insert(tag.clone()) {
+            normalized.push(tag);
+        }
+    }
+    Ok(normalized)
+}
+
+/// Both category names must be non-empty and different
+fn validate_recategorize(request: &RecategorizeProducts) -> Result<(), ApiError> {
+    if request.from.is_empty() || request.to.is_empty() {
+        return Err(ApiError::BadRequest(
+            "Both 'from' and 'to' categories are required".to_string(),
+        ));
+    }
+
+    if request.from == request.to {
+        return Err(ApiError::BadRequest(
+            "'from' and 'to' categories must be different".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// The percentage must be a non-zero number that cannot push prices below zero
+fn validate_price_adjustment(percent: f64) -> Result<(), ApiError> {
+    if !percent.is_finite() || percent == 0.0 {
+        return Err(ApiError::BadRequest(
+            "Percent must be a non-zero number".to_string(),
+        ));
+    }
+
+    // Prices are never negative, so only a cut of more than 100% can make them so
+    if adjusted_price(1.0, percent) < 0.0 {
+        return Err(ApiError::BadRequest(
+            "Price adjustment would result in negative prices".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Price after a percentage change, matching the repository's `price * (1 + percent / 100)`
+fn adjusted_price(price: f64, percent: f64) -> f64 {
+    price * (1.0 + percent / 100.0)
+}
+
+/// Names of the editable fields that differ between two versions of a product
+fn changed_fields(before: &Product, after: &Product) -> Vec<&'static str> {
+    after
+        .changes_from(before)
+        .into_iter()
+        .map(|change| change.field)
+        .collect()
+}
+
+/// Encode the last seen `(name, id)` pair as an opaque cursor
+fn encode_cursor(name: &str, id: Uuid) -> String {
+    URL_SAFE_NO_PAD.encode(json!([name, id]).to_string())
+}
+
+/// Decode a cursor produced by `encode_cursor`
+fn decode_cursor(cursor: &str) -> Result<(String, Uuid), ApiError> {
+    URL_SAFE_NO_PAD
+        .decode(cursor)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .ok_or_else(|| ApiError::BadRequest("Invalid cursor".to_string()))
+}
+
+/// Parse and validate a comma-separated `fields` parameter
+fn parse_fields(fields: &str) -> Result<Vec<&str>, ApiError> {
+    let fields: Vec<&str> = fields
+        .split(',')
+        .map(str::trim)
+        .filter(|field| !field.is_empty())
+        .collect();
+
+    if fields.is_empty() {
+        return Err(ApiError::BadRequest("No fields requested".to_string()));
+    }
+
+    if let Some(unknown) = fields.iter().find(|field| !PRODUCT_FIELDS.contains(field)) {
+        return Err(ApiError::BadRequest(format!("Unknown field: {}", unknown)));
+    }
+
+    Ok(fields)
+}
+
+/// Serialize products keeping only the requested fields
+fn select_fields(products: &[Product], fields: &[&str]) -> Result<Vec<Value>, ApiError> {
+    products
+        .iter()
+        .map(|product| {
+            let Value::Object(full) = serde_json::to_value(product)
+                .map_err(|e| ApiError::Internal(format!("Serialization error: {}", e)))?
+            else {
+                return Err(ApiError::Internal(
+                    "Product is not a JSON object".to_string(),
+                ));
+            };
+
+            let pruned: Map<String, Value> = full
+                .into_iter()
+                .filter(|(key, _)| fields.contains(&key.as_str()))
+                .collect();
+
+            Ok(Value::Object(pruned))
+        })
+        .collect()
+}
+
+/// IDs that were requested but not found, deduplicated and in request order
+fn missing_ids(requested: &[Uuid], found: &[Uuid]) -> Vec<Uuid> {
+    let found: HashSet<&Uuid> = found.iter().collect();
+    let mut seen = HashSet::new();
+
+    requested
+        .iter()
+        .filter(|id| !found.contains(id) && seen.insert(**id))
+        .copied()
+        .collect()
+}
+
+/// Response body for a batch get: the found products plus the requested IDs that weren't found
+/// 201 when every product was created, 207 Multi-Status when some failed
+fn bulk_create_response(created: Vec<Product>, mut failed: Vec<BulkCreateFailure>) -> Response {
+    failed.sort_by_key(|failure| failure.index);
+    let status = if failed.is_empty() {
+        StatusCode::CREATED
+    } else {
+        StatusCode::MULTI_STATUS
+    };
+
+    (
+        status,
+        Json(json!({ "created": created, "failed": failed })),
+    )
+        .into_response()
+}
+
+/// Turn failed create checks into the validate endpoint's response
+fn product_validation(problems: Vec<(&'static str, ApiError)>) -> ProductValidation {
+    let problems: Vec<ValidationProblem> = problems
+        .into_iter()
+        .map(|(field, error)| ValidationProblem {
+            field,
+            message: match error {
+                ApiError::BadRequest(message) => message,
+                other => other.to_string(),
+            },
+        })
+        .collect();
+
+    ProductValidation {
+        valid: problems.is_empty(),
+        problems,
+    }
+}
+
+/// Report a failed bulk-create product without leaking database details
+fn bulk_failure(index: usize, error: &ApiError) -> BulkCreateFailure {
+    let reason = match error {
+        ApiError::Database(err) if is_sku_conflict(err) => "SKU already in use".to_string(),
+        ApiError::Database(_) => "Database error".to_string(),
+        ApiError::BadRequest(message)
+        | ApiError::Conflict(message)
+        | ApiError::NotFound(message)
+        | ApiError::Forbidden(message) => message.clone(),
+        other => other.to_string(),
+    };
+    BulkCreateFailure { index, reason }
+}
+
+/// Name the failing product in an all-or-nothing bulk-create error
+fn bulk_item_error(index: usize, error: ApiError) -> ApiError {
+    let label = |message: &str| format!("Product #{}: {}", index, message);
+    match error {
+        ApiError::Database(err) if is_sku_conflict(&err) => {
+            ApiError::Conflict(label("SKU already in use"))
+        }
+        ApiError::BadRequest(message) => ApiError::BadRequest(label(&message)),
+        ApiError::Conflict(message) => ApiError::Conflict(label(&message)),
+        other => other,
+    }
+}
+
+/// Whether a database error is the unique SKU constraint
+fn is_sku_conflict(err: &sqlx::Error) -> bool {
+    err.as_database_error()
+        .and_then(|err| err.constraint())
+        .is_some_and(|constraint| constraint == "idx_products_sku")
+}
+
+/// Order found products like `requested` and summarize them, rejecting unknown IDs
+fn product_comparison(
+    requested: &[Uuid],
+    mut products: Vec<Product>,
+) -> Result<ProductComparison, ApiError> {
+    if requested.len() < 2 {
+        return Err(ApiError::BadRequest(TOO_FEW_TO_COMPARE.to_string()));
+    }
+
+    let found: Vec<Uuid> = products.iter().map(|product| product.id).collect();
+    let missing = missing_ids(requested, &found);
+    if !missing.is_empty() {
+        let missing: Vec<String> = missing.iter().map(Uuid::to_string).collect();
+        return Err(ApiError::NotFound(format!(
+            "Products not found: {}",
+            missing.join(", ")
+        )));
+    }
+
+    products.sort_by_key(|product| requested.iter().position(|id| *id == product.id));
+    let summary = ComparisonSummary::from_products(&products)
+        .ok_or_else(|| ApiError::BadRequest(TOO_FEW_TO_COMPARE.to_string()))?;
+
+    Ok(ProductComparison { products, summary })
+}
+
+fn batch_get_body(requested: &[Uuid], products: Vec<Product>) -> serde_json::Value {
+    let found: Vec<Uuid> = products.iter().map(|product| product.id).collect();
+    json!({
+        "products": products,
+        "missing": missing_ids(requested, &found),
+    })
+}
+
+/// `201 Created` when an upsert inserted the product, `200 OK` when it updated one
+fn upsert_status(created: bool) -> StatusCode {
+    if created {
+        StatusCode::CREATED
+    } else {
+        StatusCode::OK
+    }
+}
+
+/// Compute a strong ETag from the product's version
+///
+/// `updated_at` is only stored to the second, so it cannot tell two writes in
+/// the same second apart; every mutation bumps `version` instead.
+fn product_etag(product: &Product) -> String {
+    format!("\"{}\"", product.version)
+}
+
+/// Check whether an `If-None-Match` header matches the given ETag
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match.split(',').map(str::trim).any(|candidate| {
+        candidate == "*" || candidate.strip_prefix("W/").unwrap_or(candidate) == etag
+    })
+}
+
+/// Decide from an `Accept` header whether the client prefers CSV over JSON
+///
+/// The first media range naming CSV, JSON or a wildcard wins; JSON is the default.
+pub fn prefers_csv(accept: Option<&str>) -> bool {
+    let Some(accept) = accept else {
+        return false;
+    };
+
+    accept
+        .split(',')
+        .map(|range| {
+            range
+                .split(';')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_lowercase()
+        })
+        .find_map(|media_type| match media_type.as_str() {
+            "text/csv" => Some(true),
+            "application/json" | "application/*" | "*/*" => Some(false),
+            _ => None,
+        })
+        .unwrap_or(false)
+}
+
+/// Render products as CSV with the given columns
+fn products_to_csv(products: &[Product], columns: &[&str]) -> Result<String, ApiError> {
+    let mut csv = columns.join(",");
+    csv.push('\n');
+
+    for row in select_fields(products, columns)? {
+        let cells: Vec<String> = columns
+            .iter()
+            .map(|column| match row.get(*column) {
+                Some(Value::String(value)) => csv_escape(value),
+                Some(Value::Array(items)) => csv_escape(
+                    &items
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .collect::<Vec<_>>()
+                        .join(";"),
+                ),
+                Some(Value::Null) | None => String::new(),
+                Some(value) => csv_escape(&value.to_string()),
+            })
+            .collect();
+
+        csv.push_str(&cells.join(","));
+        csv.push('\n');
+    }
+
+    Ok(csv)
+}
+
+/// Quote a CSV cell when it contains separators, quotes or line breaks
+fn csv_escape(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::authentication::Role;
+    use chrono::Utc;
+
+    fn sample_product() -> Product {
+        let now = Utc::now();
+        Product {
+            id: Uuid::new_v4(),
+            sku: None,
+            name: "Webcam 4K".to_string(),
+            description: Some("Webcam resolusi 4K".to_string()),
+            price: 1300000.0,
+            formatted_price: None,
+            stock: 20,
+            category: Some("Aksesoris".to_string()),
+            is_active: true,
+            is_featured: false,
+            version: 1,
+            owner_id: None,
+            tags: vec!["webcam".to_string()],
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn test_select_fields_keeps_requested_subset() {
+        let product = sample_product();
+        let fields = parse_fields("id, name,price").unwrap();
+        let selected = select_fields(std::slice::from_ref(&product), &fields).unwrap();
+
+        let object = selected[0].as_object().unwrap();
+        assert_eq!(object.len(), 3);
+        assert_eq!(object["id"], json!(product.id));
+        assert_eq!(object["name"], json!("Webcam 4K"));
+        assert_eq!(object["price"], json!(1300000.0));
+    }
+
+    fn patch_from(operations: Value) -> Patch {
+        serde_json::from_value(operations).unwrap()
+    }
+
+    #[test]
+    fn test_apply_patch_replaces_price() {
+        let product = sample_product();
+        let patch = patch_from(json!([
+            { "op": "replace", "path": "/price", "value": 1250000.0 },
+            { "op": "remove", "path": "/description" }
+        ]));
+        validate_patch(&patch).unwrap();
+
+        let update = apply_patch(&product, &patch).unwrap();
+        assert_eq!(update.price, Some(1250000.0));
+        assert_eq!(update.description, Some(None));
+        assert_eq!(update.name.as_deref(), Some("Webcam 4K"));
+        assert_eq!(update.version, product.version);
+    }
+
+    #[test]
+    fn test_validate_patch_rejects_immutable_and_unsupported() {
+        let patch =
+            patch_from(json!([{ "op": "replace", "path": "/id", "value": Uuid::new_v4() }]));
+        assert!(matches!(
+            validate_patch(&patch),
+            Err(ApiError::BadRequest(message)) if message == "Field 'id' cannot be modified"
+        ));
+
+        let patch = patch_from(
+            json!([{ "op": "add", "path": "/created_at", "value": "2020-01-01T00:00:00Z" }]),
+        );
+        assert!(matches!(
+            validate_patch(&patch),
+            Err(ApiError::BadRequest(_))
+        ));
+
+        let patch = patch_from(json!([{ "op": "add", "path": "/color", "value": "red" }]));
+        assert!(matches!(
+            validate_patch(&patch),
+            Err(ApiError::BadRequest(_))
+        ));
+
+        let patch = patch_from(json!([{ "op": "move", "from": "/name", "path": "/sku" }]));
+        assert!(matches!(
+            validate_patch(&patch),
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_apply_patch_rejects_removing_required_field() {
+        let patch = patch_from(json!([{ "op": "remove", "path": "/name" }]));
+        assert!(matches!(
+            apply_patch(&sample_product(), &patch),
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_normalize_tags_lowercases_and_deduplicates() {
+        let tags = vec![
+            " Gaming ".to_string(),
+            "gaming".to_string(),
+            "Work  From   Home".to_string(),
+        ];
+        assert_eq!(
+            normalize_tags(&tags).unwrap(),
+            vec!["gaming".to_string(), "work from home".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_normalize_tags_rejects_invalid_input() {
+        assert!(matches!(normalize_tags(&[]), Err(ApiError::BadRequest(_))));
+        assert!(matches!(
+            normalize_tags(&["  ".to_string()]),
+            Err(ApiError::BadRequest(_))
+        ));
+        assert!(matches!(
+            normalize_tag(&"x".repeat(MAX_TAG_LENGTH + 1)),
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_resolve_category_open_mode_passes_through() {
+        assert_eq!(
+            resolve_category(&CategoryMode::Open, "Mainan").unwrap(),
+            "Mainan"
+        );
+    }
+
+    #[test]
+    fn test_resolve_category_closed_mode_rejects_unknown() {
+        let mode = CategoryMode::parse("closed", "Audio,Elektronik");
+
+        assert_eq!(resolve_category(&mode, "audio").unwrap(), "Audio");
+        assert!(matches!(
+            resolve_category(&mode, "Mainan"),
+            Err(ApiError::BadRequest(message))
+                if message == "Unknown category 'Mainan'; allowed categories: Audio, Elektronik"
+        ));
+    }
+
+    #[test]
+    fn test_price_adjustment_increase_by_ten_percent() {
+        assert!(validate_price_adjustment(10.0).is_ok());
+        assert!((adjusted_price(1500.0, 10.0) - 1650.0).abs() < 1e-9);
+        assert!((adjusted_price(1500.0, -10.0) - 1350.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_price_adjustment_rejects_negative_result() {
+        assert!(matches!(
+            validate_price_adjustment(-150.0),
+            Err(ApiError::BadRequest(message))
+                if message == "Price adjustment would result in negative prices"
+        ));
+        // A 100% cut makes products free, which is still allowed
+        assert!(validate_price_adjustment(-100.0).is_ok());
+        assert!(validate_price_adjustment(0.0).is_err());
+        assert!(validate_price_adjustment(f64::NAN).is_err());
+    }
+
+    #[test]
+    fn test_validate_stock_transfer() {
+        let id = Uuid::new_v4();
+        let same = TransferStock {
+            from: id,
+            to: id,
+            quantity: 1,
+        };
+        assert!(matches!(
+            validate_stock_transfer(&same),
+            Err(ApiError::BadRequest(message)) if message == "Cannot transfer stock to the same product"
+        ));
+
+        let zero = TransferStock {
+            from: id,
+            to: Uuid::new_v4(),
+            quantity: 0,
+        };
+        assert!(validate_stock_transfer(&zero).is_err());
+
+        let valid = TransferStock {
+            quantity: 5,
+            ..zero
+        };
+        assert!(validate_stock_transfer(&valid).is_ok());
+    }
+
+    #[test]
+    fn test_category_cap_rejects_when_reached() {
+        assert!(ensure_below_category_cap(2, 3, "Audio").is_ok());
+        assert!(matches!(
+            ensure_below_category_cap(3, 3, "Audio"),
+            Err(ApiError::Conflict(message))
+                if message == "Category 'Audio' already has the maximum of 3 products"
+        ));
+    }
+
+    /// Service over a pool that is never connected, for checks that stay off the database
+    fn service_with(flags: &FeatureFlags) -> ProductService {
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .connect_lazy("postgres://postgres@127.0.0.1:1/unreachable")
+            .unwrap();
+        ProductService::new(
+            ProductRepository::new(crate::core::db::DbPools::single(pool), flags),
+            ProductCache::default(),
+            flags,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_blank_search_term_returns_empty_list_without_query() {
+        let service = service_with(&FeatureFlags::default());
+
+        for q in [None, Some("   ".to_string())] {
+            let response = service
+                .search_products(SearchQuery { q, limit: None })
+                .await
+                .map(IntoResponse::into_response)
+                .unwrap();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            assert_eq!(&body[..], b"[]");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_without_stock_uses_configured_default() {
+        let service = service_with(&FeatureFlags {
+            default_initial_stock: 7,
+            ..FeatureFlags::default()
+        });
+        let mut product: CreateProduct =
+            serde_json::from_str(r#"{"name": "Laptop", "price": 100.0}"#).unwrap();
+        service.validate_new_product(&mut product).unwrap();
+        assert_eq!(product.stock, Some(7));
+
+        // An explicit stock, even 0, is kept
+        let mut product: CreateProduct =
+            serde_json::from_str(r#"{"name": "Laptop", "price": 100.0, "stock": 0}"#).unwrap();
+        service.validate_new_product(&mut product).unwrap();
+        assert_eq!(product.stock, Some(0));
+
+        // Built-in default
+        let mut product: CreateProduct =
+            serde_json::from_str(r#"{"name": "Laptop", "price": 100.0}"#).unwrap();
+        service_with(&FeatureFlags::default())
+            .validate_new_product(&mut product)
+            .unwrap();
+        assert_eq!(product.stock, Some(0));
+    }
+
+    #[test]
+    fn test_validate_initial_stock() {
+        // Default minimum allows products without stock
+        assert!(validate_initial_stock(None, FeatureFlags::default().min_initial_stock).is_ok());
+
+        assert!(matches!(
+            validate_initial_stock(Some(4), 5),
+            Err(ApiError::BadRequest(message)) if message == "Initial stock below minimum"
+        ));
+        assert!(matches!(
+            validate_initial_stock(None, 1),
+            Err(ApiError::BadRequest(_))
+        ));
+        assert!(validate_initial_stock(Some(5), 5).is_ok());
+    }
+
+    #[test]
+    fn test_ensure_max_chars_allows_the_limit_and_rejects_one_more() {
+        assert_eq!(ensure_max_chars("a".repeat(5), "Name", 5).unwrap(), "aaaaa");
+        assert!(matches!(
+            ensure_max_chars("a".repeat(6), "Name", 5),
+            Err(ApiError::BadRequest(message)) if message == "Name must be at most 5 characters"
+        ));
+    }
+
+    #[test]
+    fn test_ensure_max_chars_counts_characters_not_bytes() {
+        // 5 characters, 15 bytes
+        let name = "日本語テキ";
+        assert!(ensure_max_chars(name, "Name", 5).is_ok());
+        assert!(ensure_max_chars(format!("{name}ス"), "Name", 5).is_err());
+
+        let description = "🚀".repeat(3);
+        assert!(ensure_max_chars(&description, "Description", 3).is_ok());
+        assert!(ensure_max_chars(&description, "Description", 2).is_err());
+    }
+
+    #[test]
+    fn test_normalize_create_product_trims_fields() {
+        let mut product = CreateProduct {
+            sku: None,
+            name: "  iPhone   15 ".to_string(),
+            description: None,
+            price: 100.0,
+            stock: None,
+            category: Some(" Electronics ".to_string()),
+            is_featured: None,
+        };
+        normalize_create_product(&mut product).unwrap();
+
+        assert_eq!(product.name, "iPhone 15");
+        assert_eq!(product.category.as_deref(), Some("Electronics"));
+    }
+
+    #[test]
+    fn test_normalize_rejects_fields_empty_after_trim() {
+        let mut product = CreateProduct {
+            sku: None,
+            name: "   ".to_string(),
+            description: None,
+            price: 100.0,
+            stock: None,
+            category: None,
+            is_featured: None,
+        };
+        assert!(matches!(
+            normalize_create_product(&mut product),
+            Err(ApiError::BadRequest(_))
+        ));
+
+        let mut update: UpdateProduct =
+            serde_json::from_str(r#"{"category": " ", "version": 1}"#).unwrap();
+        assert!(matches!(
+            normalize_update_product(&mut update),
+            Err(ApiError::BadRequest(_))
+        ));
+
+        let mut update: UpdateProduct =
+            serde_json::from_str(r#"{"category": null, "version": 1}"#).unwrap();
+        normalize_update_product(&mut update).unwrap();
+        assert_eq!(update.category, Some(None));
+    }
+
+    #[test]
+    fn test_validate_recategorize_rejects_empty_or_equal() {
+        let request = |from: &str, to: &str| RecategorizeProducts {
+            from: from.to_string(),
+            to: to.to_string(),
+        };
+
+        assert!(validate_recategorize(&request("Electronic", "Electronics")).is_ok());
+        assert!(matches!(
+            validate_recategorize(&request("", "Electronics")),
+            Err(ApiError::BadRequest(_))
+        ));
+        assert!(matches!(
+            validate_recategorize(&request("Electronic", "")),
+            Err(ApiError::BadRequest(_))
+        ));
+        assert!(matches!(
+            validate_recategorize(&request("Electronic", "Electronic")),
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_ensure_can_modify_owner_or_admin() {
+        let owner = CurrentUser {
+            id: Uuid::new_v4(),
+            role: Role::User,
+        };
+        let other = CurrentUser {
+            id: Uuid::new_v4(),
+            role: Role::User,
+        };
+        let admin = CurrentUser {
+            id: Uuid::new_v4(),
+            role: Role::Admin,
+        };
+        let product = Product {
+            owner_id: Some(owner.id),
+            ..sample_product()
+        };
+
+        assert!(ensure_can_modify(&product, &owner).is_ok());
+        assert!(ensure_can_modify(&product, &admin).is_ok());
+        assert!(matches!(
+            ensure_can_modify(&product, &other),
+            Err(ApiError::Forbidden(_))
+        ));
+
+        // Legacy products without an owner can only be modified by admins
+        assert!(ensure_can_modify(&sample_product(), &owner).is_err());
+        assert!(ensure_can_modify(&sample_product(), &admin).is_ok());
+    }
+
+    #[test]
+    fn test_ensure_can_undo_deleter_or_admin() {
+        let deleter = CurrentUser {
+            id: Uuid::new_v4(),
+            role: Role::User,
+        };
+        let other = CurrentUser {
+            id: Uuid::new_v4(),
+            role: Role::User,
+        };
+        let admin = CurrentUser {
+            id: Uuid::new_v4(),
+            role: Role::Admin,
+        };
+        let undo = UndoDelete {
+            product_id: Uuid::new_v4(),
+            undo_id: Uuid::new_v4(),
+            deleted_by: deleter.id,
+        };
+
+        assert!(ensure_can_undo(&undo, &deleter).is_ok());
+        assert!(ensure_can_undo(&undo, &admin).is_ok());
+        assert!(matches!(
+            ensure_can_undo(&undo, &other),
+            Err(ApiError::Forbidden(_))
+        ));
+    }
+
+    #[test]
+    fn test_changed_fields_lists_only_modified_fields() {
+        let before = sample_product();
+        let after = Product {
+            price: 1250000.0,
+            category: None,
+            version: 2,
+            updated_at: Utc::now(),
+            ..before.clone()
+        };
+
+        assert_eq!(changed_fields(&before, &after), vec!["price", "category"]);
+        assert!(changed_fields(&before, &before).is_empty());
+    }
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let id = Uuid::new_v4();
+        let cursor = encode_cursor("Monitor Gaming 27\"", id);
+
+        assert_eq!(
+            decode_cursor(&cursor).unwrap(),
+            ("Monitor Gaming 27\"".to_string(), id)
+        );
+    }
+
+    #[test]
+    fn test_decode_cursor_rejects_malformed_input() {
+        for cursor in [
+            "not base64!",
+            "bm90IGpzb24",
+            &URL_SAFE_NO_PAD.encode(r#"["x", 1]"#),
+        ] {
+            assert!(matches!(
+                decode_cursor(cursor),
+                Err(ApiError::BadRequest(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_missing_ids_reports_unknown_ids_once() {
+        let existing = Uuid::new_v4();
+        let missing = Uuid::new_v4();
+
+        let result = missing_ids(&[existing, missing, missing], &[existing]);
+        assert_eq!(result, vec![missing]);
+    }
+
+    #[test]
+    fn test_product_comparison_picks_cheapest_and_most_stock() {
+        let product = |price: f64, stock: i32| Product {
+            id: Uuid::new_v4(),
+            price,
+            stock,
+            ..sample_product()
+        };
+        let webcam = product(1300000.0, 20);
+        let keyboard = product(450000.0, 5);
+        let monitor = product(2500000.0, 40);
+        let requested = [monitor.id, webcam.id, keyboard.id];
+
+        // The database returns rows in any order
+        let comparison = product_comparison(
+            &requested,
+            vec![keyboard.clone(), monitor.clone(), webcam.clone()],
+        )
+        .unwrap();
+
+        let order: Vec<Uuid> = comparison
+            .products
+            .iter()
+            .map(|product| product.id)
+            .collect();
+        assert_eq!(order, requested);
+        assert_eq!(
+            comparison.summary,
+            ComparisonSummary {
+                cheapest: keyboard.id,
+                most_stock: monitor.id,
+                price_spread: 2050000.0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_product_comparison_rejects_too_few_or_unknown_ids() {
+        let existing = sample_product();
+        assert!(matches!(
+            product_comparison(&[existing.id], vec![existing.clone()]),
+            Err(ApiError::BadRequest(_))
+        ));
+
+        let missing = Uuid::new_v4();
+        assert!(matches!(
+            product_comparison(&[existing.id, missing], vec![existing.clone()]),
+            Err(ApiError::NotFound(message)) if message.contains(&missing.to_string())
+        ));
+    }
+
+    #[test]
+    fn test_batch_get_body_lists_found_products_and_missing_ids() {
+        let existing = sample_product();
+        let missing = Uuid::new_v4();
+
+        let body = batch_get_body(&[missing, existing.id, missing], vec![existing.clone()]);
+        assert_eq!(body["products"].as_array().unwrap().len(), 1);
+        assert_eq!(body["products"][0]["id"], existing.id.to_string());
+        assert_eq!(body["missing"], json!([missing]));
+    }
+
+    #[test]
+    fn test_etag_matches_if_none_match() {
+        let product = sample_product();
+        let etag = product_etag(&product);
+
+        assert_eq!(etag, "\"1\"");
+        assert!(etag_matches(&etag, &etag));
+        assert!(etag_matches(&format!("\"other\", W/{}", etag), &etag));
+        assert!(etag_matches("*", &etag));
+        assert!(!etag_matches("\"2\"", &etag));
+    }
+
+    #[test]
+    fn test_etag_changes_after_tag_change_in_same_second() {
+        let before = sample_product();
+        // What a tag change returns: new tags and version, updated_at in the same (truncated) second
+        let after = Product {
+            tags: vec!["camera".to_string(), "webcam".to_string()],
+            version: before.version + 1,
+            ..before.clone()
+        };
+
+        assert_ne!(product_etag(&after), product_etag(&before));
+        assert!(!etag_matches(&product_etag(&before), &product_etag(&after)));
+    }
+
+    #[test]
+    fn test_upsert_maps_created_and_updated() {
+        let product = sample_product();
+
+        let created = ProductEvent::upserted(product.clone(), true);
+        assert_eq!(upsert_status(true), StatusCode::CREATED);
+        assert!(matches!(created, ProductEvent::Created { .. }));
+        assert_eq!(created.aggregate_id(), product.id);
+
+        let updated = ProductEvent::upserted(product.clone(), false);
+        assert_eq!(upsert_status(false), StatusCode::OK);
+        assert!(matches!(updated, ProductEvent::Updated { .. }));
+        assert_eq!(updated.aggregate_id(), product.id);
+    }
+
+    #[test]
+    fn test_recent_limit_defaults_and_clamps() {
+        let recent = |limit| LimitQuery { limit }.resolve(DEFAULT_RECENT_LIMIT, MAX_RECENT_LIMIT);
+
+        assert_eq!(recent(None), DEFAULT_RECENT_LIMIT);
+        assert_eq!(recent(Some(10)), 10);
+        assert_eq!(recent(Some(0)), 1);
+        assert_eq!(recent(Some(MAX_RECENT_LIMIT + 1)), MAX_RECENT_LIMIT);
+    }
+
+    #[test]
+    fn test_featured_limit_defaults_and_clamps() {
+        let featured =
+            |limit| LimitQuery { limit }.resolve(DEFAULT_FEATURED_LIMIT, MAX_FEATURED_LIMIT);
+
+        assert_eq!(featured(None), DEFAULT_FEATURED_LIMIT);
+        assert_eq!(featured(Some(10)), 10);
+        assert_eq!(featured(Some(0)), 1);
+        assert_eq!(featured(Some(MAX_FEATURED_LIMIT + 1)), MAX_FEATURED_LIMIT);
+    }
+
+    #[test]
+    fn test_related_limit_defaults_and_clamps() {
+        let related =
+            |limit| LimitQuery { limit }.resolve(DEFAULT_RELATED_LIMIT, MAX_RELATED_LIMIT);
+
+        assert_eq!(related(None), DEFAULT_RELATED_LIMIT);
+        assert_eq!(related(Some(3)), 3);
+        assert_eq!(related(Some(0)), 1);
+        assert_eq!(related(Some(-5)), 1);
+        assert_eq!(related(Some(1_000)), MAX_RELATED_LIMIT);
+    }
+
+    #[test]
+    fn test_prefers_csv_from_accept_header() {
+        assert!(prefers_csv(Some("text/csv")));
+        assert!(prefers_csv(Some("text/csv;q=0.9, application/json;q=0.5")));
+        assert!(!prefers_csv(Some("application/json")));
+        assert!(!prefers_csv(Some("*/*")));
+        assert!(!prefers_csv(None));
+    }
+
+    #[test]
+    fn test_products_to_csv_renders_header_and_escaped_rows() {
+        let mut product = sample_product();
+        product.name = "Webcam, \"4K\"".to_string();
+        product.category = None;
+
+        let csv = products_to_csv(&[product], &["name", "price", "category"]).unwrap();
+        assert_eq!(
+            csv,
+            "name,price,category\n\"Webcam, \"\"4K\"\"\",1300000.0,\n"
+        );
+    }
+
+    #[test]
+    fn test_formatted_price_is_a_selectable_csv_column() {
+        assert_eq!(
+            parse_fields("name,formatted_price").unwrap(),
+            ["name", "formatted_price"]
+        );
+
+        let mut product = sample_product();
+        product.formatted_price = Some("Rp 1.300.000".to_string());
+        let unformatted = sample_product();
+
+        let csv = products_to_csv(&[product, unformatted], &PRODUCT_FIELDS).unwrap();
+        let mut lines = csv.lines();
+        assert!(lines.next().unwrap().contains(",price,formatted_price,"));
+        assert!(lines.next().unwrap().contains(",1300000.0,Rp 1.300.000,"));
+        // Without DEFAULT_CURRENCY the column is left empty
+        assert!(lines.next().unwrap().contains(",1300000.0,,"));
+    }
+
+    #[test]
+    fn test_parse_fields_rejects_unknown_field() {
+        match parse_fields("id,password") {
+            Err(ApiError::BadRequest(msg)) => assert_eq!(msg, "Unknown field: password"),
+            other => panic!("expected bad request, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bulk_create_response_is_multi_status_with_one_failure() {
+        let created = vec![sample_product(), sample_product()];
+        let failed = vec![bulk_failure(
+            1,
+            &ApiError::BadRequest("Price cannot be negative".to_string()),
+        )];
+
+        let response = bulk_create_response(created, failed);
+        assert_eq!(response.status(), StatusCode::MULTI_STATUS);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["created"].as_array().unwrap().len(), 2);
+        assert_eq!(
+            body["failed"],
+            json!([{ "index": 1, "reason": "Price cannot be negative" }])
+        );
+
+        let response = bulk_create_response(vec![sample_product()], Vec::new());
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+
+    #[test]
+    fn test_bulk_failure_hides_database_details() {
+        let failure = bulk_failure(3, &ApiError::Database(sqlx::Error::RowNotFound));
+        assert_eq!(failure.index, 3);
+        assert_eq!(failure.reason, "Database error");
+
+        assert!(matches!(
+            bulk_item_error(2, ApiError::Conflict("Category full".to_string())),
+            ApiError::Conflict(message) if message == "Product #2: Category full"
+        ));
+    }
 }
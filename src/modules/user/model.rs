@@ -1,7 +1,58 @@
 use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
 use uuid::Uuid;
 
+use crate::core::{authentication::Role, pagination::SortOrder};
+use crate::modules::{auth_event::model::AuthEvent, product::model::Product};
+use crate::utils::timestamp;
+
+/// Shared email format check used by `Email`
+pub static EMAIL_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^[a-zA-Z0-9._%+-]+@[a-zA-Z0-9.-]+\.[a-zA-Z]{2,}$").unwrap());
+
+/// Email address that has been trimmed and validated against `EMAIL_REGEX`
+///
+/// Deserializing goes through `TryFrom<String>`, so request bodies with a
+/// malformed address are rejected before reaching the service.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Email(String);
+
+impl Email {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for Email {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let trimmed = value.trim();
+        if !EMAIL_REGEX.is_match(trimmed) {
+            return Err("Invalid email format".to_string());
+        }
+        Ok(Self(trimmed.to_string()))
+    }
+}
+
+impl FromStr for Email {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from(s.to_string())
+    }
+}
+
+impl fmt::Display for Email {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
 /// User model representing a user in the database
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct User {
@@ -10,15 +61,33 @@ pub struct User {
     pub email: String,    // Email address
     #[serde(skip_serializing)] // Don't include password in JSON responses
     pub password: String, // Hashed password
+    pub role: Role,       // Access role
+    #[serde(skip_serializing)]
+    pub failed_login_count: i32, // Consecutive failed login attempts
+    #[serde(skip_serializing)]
+    pub locked_until: Option<DateTime<Utc>>, // Login is blocked until this time
+    #[serde(skip_serializing)]
+    pub deleted_at: Option<DateTime<Utc>>, // Soft delete timestamp
+    #[serde(skip_serializing)]
+    pub last_login_at: Option<DateTime<Utc>>, // Last successful login
+    #[serde(with = "timestamp")]
     pub created_at: DateTime<Utc>, // Creation timestamp
+    #[serde(with = "timestamp")]
     pub updated_at: DateTime<Utc>, // Last update timestamp
 }
 
+impl User {
+    /// Whether the account is locked at the given point in time
+    pub fn is_locked_at(&self, now: DateTime<Utc>) -> bool {
+        self.locked_until.is_some_and(|until| until > now)
+    }
+}
+
 /// DTO for creating a new user (registration)
 #[derive(Debug, Deserialize)]
 pub struct CreateUser {
     pub username: String, // Required username
-    pub email: String,    // Required email
+    pub email: Email,     // Required email
     pub password: String, // Required password (will be hashed)
 }
 
@@ -33,17 +102,72 @@ pub struct LoginUser {
 #[derive(Debug, Deserialize)]
 pub struct UpdateUser {
     pub username: Option<String>, // Optional username update
-    pub email: Option<String>,    // Optional email update
+    pub email: Option<Email>,     // Optional email update
     pub password: Option<String>, // Optional password update
 }
 
 /// Filter criteria for querying users
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct UserFilter {
     pub username: Option<String>, // Filter by username (partial match)
     pub email: Option<String>,    // Filter by email (partial match)
-    pub limit: Option<i64>,       // Maximum number of results
-    pub offset: Option<i64>,      // Number of results to skip
+    pub role: Option<String>,     // Filter by role (exact match)
+    #[serde(default, with = "timestamp::option")]
+    pub created_after: Option<DateTime<Utc>>, // Registered at or after this time (RFC 3339)
+    #[serde(default, with = "timestamp::option")]
+    pub created_before: Option<DateTime<Utc>>, // Registered before this time (RFC 3339)
+    pub sort_by: Option<String>,  // Sort column, one of `USER_LIST_SORT_COLUMNS`
+    pub sort_dir: Option<String>, // `asc` (default) or `desc`
+    #[serde(skip)]
+    pub sort: Option<SortOrder>, // Order resolved from `sort_by`/`sort_dir`
+    #[serde(skip)]
+    pub limit: Option<i64>, // Maximum number of results (set from `PageParams`)
+    #[serde(skip)]
+    pub offset: Option<i64>, // Number of results to skip (set from `PageParams`)
+}
+
+/// Columns the admin user list can be sorted by with `sort_by`
+pub const USER_LIST_SORT_COLUMNS: [&str; 4] = ["username", "email", "created_at", "last_login_at"];
+
+impl UserFilter {
+    /// Resolve `sort_by`/`sort_dir` into a sort order, `None` meaning the configured default
+    ///
+    /// `sort_dir` alone sorts by username, the default column.
+    pub fn resolve_sort(&self) -> Result<Option<SortOrder>, String> {
+        if self.sort_by.is_none() && self.sort_dir.is_none() {
+            return Ok(None);
+        }
+
+        let column = self.sort_by.as_deref().unwrap_or("username");
+        let direction = self.sort_dir.as_deref().unwrap_or("asc");
+        SortOrder::parse(
+            &format!("{}:{}", column, direction),
+            &USER_LIST_SORT_COLUMNS,
+        )
+        .map(Some)
+    }
+
+    /// Reject a created_at window that cannot contain any user
+    pub fn validate_created_range(&self) -> Result<(), String> {
+        match (self.created_after, self.created_before) {
+            (Some(after), Some(before)) if after >= before => {
+                Err("created_after must be earlier than created_before".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Query parameters for deleting a user
+#[derive(Debug, Deserialize)]
+pub struct DeleteUserQuery {
+    pub hard: Option<bool>, // Permanently delete instead of soft delete
+}
+
+/// Query parameters for login
+#[derive(Debug, Deserialize)]
+pub struct LoginQuery {
+    pub cookie: Option<bool>, // Also set the token as an HttpOnly cookie
 }
 
 /// Response for successful login
@@ -56,21 +180,200 @@ pub struct AuthResponse {
 /// User response without sensitive information
 #[derive(Debug, Serialize)]
 pub struct UserResponse {
-    pub id: Uuid,                  // User ID
-    pub username: String,          // Username
-    pub email: String,             // Email
+    pub id: Uuid,         // User ID
+    pub username: String, // Username
+    pub email: String,    // Email
+    pub role: Role,       // Access role
+    #[serde(with = "timestamp::option")]
+    pub last_login_at: Option<DateTime<Utc>>, // Last successful login (null if never)
+    #[serde(with = "timestamp")]
     pub created_at: DateTime<Utc>, // Creation timestamp
+    #[serde(with = "timestamp")]
     pub updated_at: DateTime<Utc>, // Last update timestamp
 }
 
+/// Soft-deleted user as listed for admins, with the time it was deactivated
+#[derive(Debug, Serialize)]
+pub struct InactiveUserResponse {
+    #[serde(flatten)]
+    pub user: UserResponse, // Profile without the password hash
+    #[serde(with = "timestamp::option")]
+    pub deleted_at: Option<DateTime<Utc>>, // When the account was deactivated
+}
+
+/// Everything stored about one user, for data-subject export requests
+#[derive(Debug, Serialize)]
+pub struct UserExport {
+    pub user: UserResponse,          // Profile without the password hash
+    pub products: Vec<Product>,      // Products owned by the user
+    pub auth_events: Vec<AuthEvent>, // Login attempts recorded for the user
+    #[serde(with = "timestamp")]
+    pub exported_at: DateTime<Utc>, // When the export was generated
+}
+
 impl From<User> for UserResponse {
     fn from(user: User) -> Self {
         Self {
             id: user.id,
             username: user.username,
             email: user.email,
+            role: user.role,
+            last_login_at: user.last_login_at,
             created_at: user.created_at,
             updated_at: user.updated_at,
         }
     }
 }
+
+impl From<User> for InactiveUserResponse {
+    fn from(user: User) -> Self {
+        Self {
+            deleted_at: user.deleted_at,
+            user: UserResponse::from(user),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn user_locked_until(locked_until: Option<DateTime<Utc>>) -> User {
+        let now = Utc::now();
+        User {
+            id: Uuid::new_v4(),
+            username: "tester".to_string(),
+            email: "tester@example.com".to_string(),
+            password: String::new(),
+            role: Role::User,
+            failed_login_count: 0,
+            locked_until,
+            deleted_at: None,
+            last_login_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn test_inactive_user_response_keeps_deleted_at_and_hides_password() {
+        let deleted_at = Utc::now();
+        let mut user = user_locked_until(None);
+        user.password = "secret-hash".to_string();
+        user.deleted_at = Some(deleted_at);
+
+        let json = serde_json::to_value(InactiveUserResponse::from(user)).unwrap();
+        assert_eq!(json["username"], "tester");
+        assert_eq!(json["deleted_at"], timestamp::format(&deleted_at));
+        assert!(json.get("password").is_none());
+    }
+
+    #[test]
+    fn test_email_accepts_valid_addresses() {
+        let email: Email = "user@example.com".parse().unwrap();
+        assert_eq!(email.as_str(), "user@example.com");
+
+        let email = Email::try_from("  first.last+tag@mail.example.co.id \n".to_string()).unwrap();
+        assert_eq!(email.as_str(), "first.last+tag@mail.example.co.id");
+    }
+
+    #[test]
+    fn test_email_rejects_invalid_addresses() {
+        for invalid in [
+            "",
+            "plainaddress",
+            "@example.com",
+            "user@",
+            "user@example",
+            "a b@c.com",
+        ] {
+            assert_eq!(
+                invalid.parse::<Email>(),
+                Err("Invalid email format".to_string()),
+                "{invalid:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_create_user_deserializes_validated_email() {
+        let user: CreateUser = serde_json::from_str(
+            r#"{"username": "alice", "email": " alice@example.com ", "password": "secret123"}"#,
+        )
+        .unwrap();
+        assert_eq!(user.email.as_str(), "alice@example.com");
+
+        let error = serde_json::from_str::<CreateUser>(
+            r#"{"username": "alice", "email": "not-an-email", "password": "secret123"}"#,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("Invalid email format"));
+    }
+
+    #[test]
+    fn test_user_locked_within_window() {
+        let now = Utc::now();
+        let user = user_locked_until(Some(now + Duration::minutes(5)));
+        assert!(user.is_locked_at(now));
+    }
+
+    #[test]
+    fn test_user_lock_clears_after_window() {
+        let now = Utc::now();
+        let user = user_locked_until(Some(now - Duration::minutes(1)));
+        assert!(!user.is_locked_at(now));
+        assert!(!user_locked_until(None).is_locked_at(now));
+    }
+
+    #[test]
+    fn test_resolve_sort_allowlists_columns() {
+        let filter = |sort_by: Option<&str>, sort_dir: Option<&str>| UserFilter {
+            sort_by: sort_by.map(str::to_string),
+            sort_dir: sort_dir.map(str::to_string),
+            ..UserFilter::default()
+        };
+
+        assert_eq!(filter(None, None).resolve_sort(), Ok(None));
+        assert_eq!(
+            filter(Some("last_login_at"), None).resolve_sort(),
+            Ok(Some(SortOrder::asc("last_login_at")))
+        );
+        assert_eq!(
+            filter(None, Some("desc")).resolve_sort(),
+            Ok(Some(SortOrder {
+                column: "username",
+                descending: true
+            }))
+        );
+        assert!(filter(Some("password"), None).resolve_sort().is_err());
+        assert!(filter(Some("updated_at"), None).resolve_sort().is_err());
+        assert!(
+            filter(Some("email"), Some("sideways"))
+                .resolve_sort()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_created_range_must_not_be_empty() {
+        let now = Utc::now();
+        let range = |after, before| UserFilter {
+            created_after: after,
+            created_before: before,
+            ..UserFilter::default()
+        };
+
+        assert!(
+            range(Some(now - Duration::days(1)), Some(now))
+                .validate_created_range()
+                .is_ok()
+        );
+        assert!(range(Some(now), None).validate_created_range().is_ok());
+        assert!(
+            range(Some(now), Some(now))
+                .validate_created_range()
+                .is_err()
+        );
+    }
+}
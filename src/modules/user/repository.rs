@@ -1,32 +1,67 @@
 use crate::{
-    core::{db::DbPool, error::ApiError},
-    modules::user::model::{CreateUser, UpdateUser, User, UserFilter},
+    core::{
+        authentication::Role,
+        config::FeatureFlags,
+        db::{DbPool, DbPools, timed, with_transaction},
+        error::ApiError,
+        pagination::SortOrder,
+    },
+    modules::user::model::{CreateUser, Email, UpdateUser, User, UserFilter},
     utils::offset_to_chrono,
 };
 use argon2::{
     Argon2,
     password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString, rand_core::OsRng},
 };
-use sqlx::{FromRow, QueryBuilder, Row, postgres::PgRow, query, query_as};
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, QueryBuilder, Row, postgres::PgRow, query, query_as, query_scalar};
 use tracing::info;
 use uuid::Uuid;
 
 // Konstanta SQL - menggunakan static str untuk menghindari alokasi
-// const SELECT_USER_FIELDS: &str = "id, username, email, password, created_at, updated_at";
-const SELECT_USER_BASE: &str =
-    "SELECT id, username, email, password, created_at, updated_at FROM users";
-const QUERY_FIND_BY_ID: &str =
-    "SELECT id, username, email, password, created_at, updated_at FROM users WHERE id = $1";
-const QUERY_FIND_BY_EMAIL: &str =
-    "SELECT id, username, email, password, created_at, updated_at FROM users WHERE email = $1";
+// const SELECT_USER_FIELDS: &str = "id, username, email, password, role, failed_login_count, locked_until, deleted_at, last_login_at, created_at, updated_at";
+const SELECT_USER_BASE: &str = "SELECT id, username, email, password, role, failed_login_count, locked_until, deleted_at, last_login_at, created_at, updated_at FROM users";
+const QUERY_FIND_BY_ID: &str = "SELECT id, username, email, password, role, failed_login_count, locked_until, deleted_at, last_login_at, created_at, updated_at FROM users WHERE id = $1 AND deleted_at IS NULL";
+const QUERY_FIND_BY_EMAIL: &str = "SELECT id, username, email, password, role, failed_login_count, locked_until, deleted_at, last_login_at, created_at, updated_at FROM users WHERE email = $1 AND deleted_at IS NULL";
 const QUERY_DELETE: &str = "DELETE FROM users WHERE id = $1";
-const QUERY_CREATE: &str = "INSERT INTO users (username, email, password) VALUES ($1, $2, $3) RETURNING id, username, email, password, created_at, updated_at";
-const QUERY_FOR_UPDATE: &str = "SELECT id, username, email, password, created_at, updated_at FROM users WHERE id = $1 FOR UPDATE";
-const QUERY_UPDATE: &str = "UPDATE users SET username = $1, email = $2, password = $3, updated_at = NOW() WHERE id = $4 RETURNING id, username, email, password, created_at, updated_at";
+const QUERY_SOFT_DELETE: &str =
+    "UPDATE users SET deleted_at = NOW(), updated_at = NOW() WHERE id = $1 AND deleted_at IS NULL";
+const QUERY_LIST_INACTIVE: &str = "SELECT id, username, email, password, role, failed_login_count, locked_until, deleted_at, last_login_at, created_at, updated_at FROM users WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC, id LIMIT $1 OFFSET $2";
+const QUERY_INACTIVE_FOR_UPDATE: &str = "SELECT id, username, email, password, role, failed_login_count, locked_until, deleted_at, last_login_at, created_at, updated_at FROM users WHERE id = $1 AND deleted_at IS NOT NULL FOR UPDATE";
+const QUERY_ACTIVE_EMAIL_EXISTS: &str =
+    "SELECT EXISTS(SELECT 1 FROM users WHERE email = $1 AND deleted_at IS NULL)";
+const QUERY_REACTIVATE: &str = "UPDATE users SET deleted_at = NULL, failed_login_count = 0, locked_until = NULL, updated_at = NOW() WHERE id = $1 RETURNING id, username, email, password, role, failed_login_count, locked_until, deleted_at, last_login_at, created_at, updated_at";
+const QUERY_DELETED_EMAIL_EXISTS: &str =
+    "SELECT EXISTS(SELECT 1 FROM users WHERE email = $1 AND deleted_at IS NOT NULL)";
+// Cek keberadaan memakai pencocokan yang sama dengan filter daftar (ILIKE sebagian)
+const QUERY_USERNAME_EXISTS: &str =
+    "SELECT EXISTS(SELECT 1 FROM users WHERE username ILIKE $1 AND deleted_at IS NULL)";
+const QUERY_EMAIL_EXISTS: &str =
+    "SELECT EXISTS(SELECT 1 FROM users WHERE email ILIKE $1 AND deleted_at IS NULL)";
+const QUERY_CREATE: &str = "INSERT INTO users (username, email, password) VALUES ($1, $2, $3) RETURNING id, username, email, password, role, failed_login_count, locked_until, deleted_at, last_login_at, created_at, updated_at";
+const QUERY_FOR_UPDATE: &str = "SELECT id, username, email, password, role, failed_login_count, locked_until, deleted_at, last_login_at, created_at, updated_at FROM users WHERE id = $1 AND deleted_at IS NULL FOR UPDATE";
+const QUERY_RECORD_FAILED_LOGIN: &str = "UPDATE users SET
+    failed_login_count = CASE WHEN failed_login_count + 1 >= $2 THEN 0 ELSE failed_login_count + 1 END,
+    locked_until = CASE WHEN failed_login_count + 1 >= $2 THEN NOW() + make_interval(mins => $3) ELSE locked_until END
+    WHERE id = $1";
+const QUERY_RECORD_LOGIN: &str = "UPDATE users SET failed_login_count = 0, locked_until = NULL, last_login_at = NOW() WHERE id = $1 RETURNING last_login_at";
+const QUERY_SET_ROLE: &str = "UPDATE users SET role = $2, updated_at = NOW() WHERE id = $1";
+const QUERY_UPDATE: &str = "UPDATE users SET username = $1, email = $2, password = $3, updated_at = NOW() WHERE id = $4 RETURNING id, username, email, password, role, failed_login_count, locked_until, deleted_at, last_login_at, created_at, updated_at";
+
+// Kolom yang boleh dipakai untuk USERS_DEFAULT_SORT
+pub(crate) const USER_SORT_COLUMNS: [&str; 5] = [
+    "username",
+    "email",
+    "created_at",
+    "updated_at",
+    "last_login_at",
+];
 
 /// Repository untuk operasi database pengguna
 pub struct UserRepository {
-    pool: DbPool,
+    pool: DbPool,            // Pool utama untuk operasi tulis
+    read_pool: DbPool,       // Pool untuk query baca (replica jika dikonfigurasi)
+    default_sort: SortOrder, // Urutan daftar pengguna (USERS_DEFAULT_SORT)
 }
 
 // Implementasi FromRow untuk User untuk menangani konversi tipe khusus
@@ -37,6 +72,20 @@ impl<'r> FromRow<'r, PgRow> for User {
             username: row.try_get("username")?,
             email: row.try_get("email")?,
             password: row.try_get("password")?,
+            role: row
+                .try_get::<String, _>("role")?
+                .parse()
+                .map_err(|err: String| sqlx::Error::Decode(err.into()))?,
+            failed_login_count: row.try_get("failed_login_count")?,
+            locked_until: row
+                .try_get::<Option<_>, _>("locked_until")?
+                .map(offset_to_chrono),
+            deleted_at: row
+                .try_get::<Option<_>, _>("deleted_at")?
+                .map(offset_to_chrono),
+            last_login_at: row
+                .try_get::<Option<_>, _>("last_login_at")?
+                .map(offset_to_chrono),
             created_at: offset_to_chrono(row.try_get("created_at")?),
             updated_at: offset_to_chrono(row.try_get("updated_at")?),
         })
@@ -45,8 +94,12 @@ impl<'r> FromRow<'r, PgRow> for User {
 
 impl UserRepository {
     /// Membuat repository pengguna baru
-    pub fn new(pool: DbPool) -> Self {
-        Self { pool }
+    pub fn new(pools: DbPools, flags: &FeatureFlags) -> Self {
+        Self {
+            pool: pools.write,
+            read_pool: pools.read,
+            default_sort: flags.users_default_sort,
+        }
     }
 
     /// Membuat pengguna baru di database (registrasi)
@@ -56,7 +109,7 @@ impl UserRepository {
 
         let created_user = query_as::<_, User>(QUERY_CREATE)
             .bind(&user.username)
-            .bind(&user.email)
+            .bind(user.email.as_str())
             .bind(&password_hash)
             .fetch_one(&self.pool)
             .await
@@ -68,80 +121,194 @@ impl UserRepository {
 
     /// Mencari pengguna berdasarkan ID
     pub async fn find_by_id(&self, id: Uuid) -> Result<User, ApiError> {
-        query_as::<_, User>(QUERY_FIND_BY_ID)
-            .bind(id)
-            .fetch_optional(&self.pool)
-            .await
-            .map_err(ApiError::Database)?
-            .ok_or_else(|| ApiError::NotFound(format!("User with ID {} not found", id)))
+        timed(
+            "users.find_by_id",
+            query_as::<_, User>(QUERY_FIND_BY_ID)
+                .bind(id)
+                .fetch_optional(&self.read_pool),
+        )
+        .await
+        .map_err(ApiError::Database)?
+        .ok_or_else(|| ApiError::NotFound(format!("User with ID {} not found", id)))
     }
 
     /// Mencari pengguna berdasarkan email (untuk login)
     pub async fn find_by_email(&self, email: &str) -> Result<User, ApiError> {
-        query_as::<_, User>(QUERY_FIND_BY_EMAIL)
-            .bind(email)
-            .fetch_optional(&self.pool)
-            .await
-            .map_err(ApiError::Database)?
-            .ok_or_else(|| ApiError::NotFound("Invalid email or password".to_string()))
+        timed(
+            "users.find_by_email",
+            query_as::<_, User>(QUERY_FIND_BY_EMAIL)
+                .bind(email)
+                .fetch_optional(&self.pool),
+        )
+        .await
+        .map_err(ApiError::Database)?
+        .ok_or_else(|| ApiError::NotFound("Invalid email or password".to_string()))
     }
 
     /// Daftar pengguna dengan filter opsional
     pub async fn list(&self, filter: &UserFilter) -> Result<Vec<User>, ApiError> {
         // Mulai membangun query SQL dinamis
         let mut query_builder = QueryBuilder::new(SELECT_USER_BASE);
-        query_builder.push(" WHERE 1=1");
+        query_builder.push(" WHERE deleted_at IS NULL");
 
         // Terapkan filter
         self.apply_filters(&mut query_builder, filter);
 
         // Eksekusi query dan konversi hasil
-        query_builder
-            .build_query_as::<User>()
-            .fetch_all(&self.pool)
-            .await
-            .map_err(ApiError::Database)
+        timed(
+            "users.list",
+            query_builder
+                .build_query_as::<User>()
+                .fetch_all(&self.read_pool),
+        )
+        .await
+        .map_err(ApiError::Database)
     }
 
     /// Memperbarui pengguna yang ada
     pub async fn update(&self, id: Uuid, update: &UpdateUser) -> Result<User, ApiError> {
+        let updated = with_transaction(&self.pool, |tx, _| {
+            Box::pin(async move {
+                // Periksa apakah pengguna ada dan dapatkan nilai saat ini
+                let current = query_as::<_, User>(QUERY_FOR_UPDATE)
+                    .bind(id)
+                    .fetch_optional(&mut **tx)
+                    .await
+                    .map_err(ApiError::Database)?
+                    .ok_or_else(|| ApiError::NotFound(format!("User with ID {} not found", id)))?;
+
+                // Siapkan nilai pembaruan
+                let username = update.username.as_ref().unwrap_or(&current.username);
+                let email = update
+                    .email
+                    .as_ref()
+                    .map_or(current.email.as_str(), Email::as_str);
+
+                // Hash password jika disediakan
+                let password = match &update.password {
+                    Some(new_password) => self.hash_password(new_password)?,
+                    None => current.password.clone(),
+                };
+
+                // Eksekusi pembaruan
+                query_as::<_, User>(QUERY_UPDATE)
+                    .bind(username)
+                    .bind(email)
+                    .bind(&password)
+                    .bind(id)
+                    .fetch_one(&mut **tx)
+                    .await
+                    .map_err(ApiError::Database)
+            })
+        })
+        .await?;
+
+        info!("User with ID: {} successfully updated", id);
+
+        Ok(updated)
+    }
+
+    /// Menandai pengguna sebagai terhapus tanpa menghapus barisnya
+    pub async fn soft_delete(&self, id: Uuid) -> Result<(), ApiError> {
+        let result = query(QUERY_SOFT_DELETE)
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(ApiError::Database)?;
+
+        if result.rows_affected() == 0 {
+            return Err(ApiError::NotFound(format!("User with ID {} not found", id)));
+        }
+
+        info!("User with ID: {} successfully soft deleted", id);
+        Ok(())
+    }
+
+    /// Daftar pengguna yang sudah di-soft delete, yang terbaru dinonaktifkan lebih dulu
+    pub async fn list_inactive(&self, limit: i64, offset: i64) -> Result<Vec<User>, ApiError> {
+        timed(
+            "users.list_inactive",
+            query_as::<_, User>(QUERY_LIST_INACTIVE)
+                .bind(limit)
+                .bind(offset)
+                .fetch_all(&self.read_pool),
+        )
+        .await
+        .map_err(ApiError::Database)
+    }
+
+    /// Mengaktifkan kembali pengguna yang sudah di-soft delete
+    ///
+    /// Ditolak kalo email-nya sudah dipakai pengguna aktif lain (lihat
+    /// `REUSE_DELETED_USER_EMAILS`), supaya login tetap merujuk ke satu akun.
+    pub async fn reactivate(&self, id: Uuid) -> Result<User, ApiError> {
         let mut tx = self.pool.begin().await.map_err(ApiError::Database)?;
 
-        // Periksa apakah pengguna ada dan dapatkan nilai saat ini
-        let current = query_as::<_, User>(QUERY_FOR_UPDATE)
+        let inactive = query_as::<_, User>(QUERY_INACTIVE_FOR_UPDATE)
             .bind(id)
             .fetch_optional(&mut *tx)
             .await
             .map_err(ApiError::Database)?
-            .ok_or_else(|| ApiError::NotFound(format!("User with ID {} not found", id)))?;
-
-        // Siapkan nilai pembaruan
-        let username = update.username.as_ref().unwrap_or(&current.username);
-        let email = update.email.as_ref().unwrap_or(&current.email);
+            .ok_or_else(|| ApiError::NotFound(format!("Inactive user with ID {} not found", id)))?;
 
-        // Hash password jika disediakan
-        let password = match &update.password {
-            Some(new_password) => self.hash_password(new_password)?,
-            None => current.password.clone(),
-        };
+        let email_taken: bool = query_scalar(QUERY_ACTIVE_EMAIL_EXISTS)
+            .bind(&inactive.email)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(ApiError::Database)?;
+        if email_taken {
+            return Err(ApiError::Conflict(format!(
+                "Email {} is already used by an active user",
+                inactive.email
+            )));
+        }
 
-        // Eksekusi pembaruan
-        let updated = query_as::<_, User>(QUERY_UPDATE)
-            .bind(username)
-            .bind(email)
-            .bind(&password)
+        let reactivated = query_as::<_, User>(QUERY_REACTIVATE)
             .bind(id)
             .fetch_one(&mut *tx)
             .await
             .map_err(ApiError::Database)?;
 
         tx.commit().await.map_err(ApiError::Database)?;
-        info!("User with ID: {} successfully updated", id);
+        info!("User with ID: {} successfully reactivated", id);
 
-        Ok(updated)
+        Ok(reactivated)
+    }
+
+    /// Cek apakah username sudah dipakai pengguna aktif, tanpa mengambil baris penggunanya
+    pub async fn exists_by_username(&self, username: &str) -> Result<bool, ApiError> {
+        timed(
+            "users.exists_by_username",
+            query_scalar(QUERY_USERNAME_EXISTS)
+                .bind(format!("%{}%", username))
+                .fetch_one(&self.read_pool),
+        )
+        .await
+        .map_err(ApiError::Database)
     }
 
-    /// Menghapus pengguna berdasarkan ID
+    /// Cek apakah email sudah dipakai pengguna aktif, tanpa mengambil baris penggunanya
+    pub async fn exists_by_email(&self, email: &str) -> Result<bool, ApiError> {
+        timed(
+            "users.exists_by_email",
+            query_scalar(QUERY_EMAIL_EXISTS)
+                .bind(format!("%{}%", email))
+                .fetch_one(&self.read_pool),
+        )
+        .await
+        .map_err(ApiError::Database)
+    }
+
+    /// Cek apakah email masih dipakai oleh pengguna yang sudah di-soft delete
+    pub async fn deleted_email_exists(&self, email: &str) -> Result<bool, ApiError> {
+        query_scalar(QUERY_DELETED_EMAIL_EXISTS)
+            .bind(email)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(ApiError::Database)
+    }
+
+    /// Menghapus pengguna secara permanen berdasarkan ID
     pub async fn delete(&self, id: Uuid) -> Result<(), ApiError> {
         let result = query(QUERY_DELETE)
             .bind(id)
@@ -157,6 +324,47 @@ impl UserRepository {
         Ok(())
     }
 
+    /// Mencatat login gagal dan mengunci akun jika batas percobaan tercapai
+    pub async fn record_failed_login(
+        &self,
+        id: Uuid,
+        max_failures: i32,
+        lockout_minutes: i32,
+    ) -> Result<(), ApiError> {
+        query(QUERY_RECORD_FAILED_LOGIN)
+            .bind(id)
+            .bind(max_failures)
+            .bind(lockout_minutes)
+            .execute(&self.pool)
+            .await
+            .map_err(ApiError::Database)?;
+
+        Ok(())
+    }
+
+    /// Mereset penghitung login gagal dan mencatat waktu login yang berhasil
+    pub async fn record_login(&self, id: Uuid) -> Result<DateTime<Utc>, ApiError> {
+        query_scalar::<_, time::OffsetDateTime>(QUERY_RECORD_LOGIN)
+            .bind(id)
+            .fetch_one(&self.pool)
+            .await
+            .map(offset_to_chrono)
+            .map_err(ApiError::Database)
+    }
+
+    /// Mengubah role pengguna
+    pub async fn set_role(&self, id: Uuid, role: Role) -> Result<(), ApiError> {
+        query(QUERY_SET_ROLE)
+            .bind(id)
+            .bind(role.as_str())
+            .execute(&self.pool)
+            .await
+            .map_err(ApiError::Database)?;
+
+        info!("Role pengguna {} diubah menjadi {}", id, role);
+        Ok(())
+    }
+
     /// Hash password menggunakan Argon2
     fn hash_password(&self, password: &str) -> Result<String, ApiError> {
         let salt = SaltString::generate(&mut OsRng);
@@ -195,8 +403,25 @@ impl UserRepository {
             query_builder.push_bind(format!("%{}%", email));
         }
 
-        // Terapkan pengurutan, limit dan offset
-        query_builder.push(" ORDER BY username ASC");
+        if let Some(role) = &filter.role {
+            query_builder.push(" AND role = ");
+            query_builder.push_bind(role);
+        }
+
+        if let Some(created_after) = filter.created_after {
+            query_builder.push(" AND created_at >= ");
+            query_builder.push_bind(created_after);
+        }
+
+        if let Some(created_before) = filter.created_before {
+            query_builder.push(" AND created_at < ");
+            query_builder.push_bind(created_before);
+        }
+
+        // Terapkan pengurutan, limit dan offset; pengguna yang belum pernah login selalu di akhir
+        let sort = filter.sort.unwrap_or(self.default_sort);
+        query_builder.push(" ORDER BY ");
+        query_builder.push(sort.to_sql_nulls_last());
 
         if let Some(limit) = filter.limit {
             query_builder.push(" LIMIT ");
@@ -209,3 +434,49 @@ impl UserRepository {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The lazy pool needs a Tokio runtime but never connects, since only SQL is built
+    fn repository() -> UserRepository {
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .connect_lazy("postgres://postgres@127.0.0.1:1/unreachable")
+            .unwrap();
+        UserRepository::new(DbPools::single(pool), &FeatureFlags::default())
+    }
+
+    fn list_sql(filter: &UserFilter) -> String {
+        let repository = repository();
+        let mut query_builder = QueryBuilder::new(SELECT_USER_BASE);
+        query_builder.push(" WHERE deleted_at IS NULL");
+        repository.apply_filters(&mut query_builder, filter);
+        query_builder.into_sql()
+    }
+
+    #[tokio::test]
+    async fn test_created_range_filter_bounds_created_at() {
+        let filter: UserFilter = serde_json::from_value(serde_json::json!({
+            "created_after": "2025-01-01T00:00:00Z",
+            "created_before": "2025-02-01T00:00:00Z",
+        }))
+        .unwrap();
+        let sql = list_sql(&filter);
+
+        assert!(sql.contains("AND created_at >= $1 AND created_at < $2"));
+        assert!(sql.contains("ORDER BY username ASC NULLS LAST, id ASC"));
+    }
+
+    #[tokio::test]
+    async fn test_sort_by_created_at_desc() {
+        let mut filter: UserFilter = serde_json::from_value(serde_json::json!({
+            "sort_by": "created_at",
+            "sort_dir": "DESC",
+        }))
+        .unwrap();
+        filter.sort = filter.resolve_sort().unwrap();
+
+        assert!(list_sql(&filter).ends_with("ORDER BY created_at DESC NULLS LAST, id ASC"));
+    }
+}
@@ -1,10 +1,22 @@
-use crate::core::db::DbPool;
-use crate::modules::user::model::{CreateUser, LoginUser, UpdateUser, UserFilter};
+use crate::core::{
+    authentication::{AuthUser, CurrentUser, identify_user, require_admin, require_user},
+    client_ip::ClientIp,
+    config::FeatureFlags,
+    db::DbPools,
+    json::{ApiJson, ApiJsonOrForm},
+    pagination::PageParams,
+    query::ApiQuery,
+};
+use crate::modules::product::repository::ProductRepository;
+use crate::modules::user::model::{
+    CreateUser, DeleteUserQuery, LoginQuery, LoginUser, UpdateUser, UserFilter,
+};
 use crate::modules::user::repository::UserRepository;
 use crate::modules::user::service::UserService;
 use axum::{
-    Json, Router,
-    extract::{Path, Query, State},
+    Router,
+    extract::{Extension, Path, State},
+    middleware,
     response::IntoResponse,
     routing::{get, post},
 };
@@ -15,28 +27,83 @@ use uuid::Uuid;
 type SharedHandler = Arc<UserService>;
 
 /// Create user routes
-pub fn user_routes(pool: DbPool) -> Router {
+pub fn user_routes(pools: DbPools, flags: &FeatureFlags) -> Router {
     // Create repository and handler
-    let repository = UserRepository::new(pool);
-    let handler = Arc::new(UserService::new(repository));
+    let audit_pool = pools.write.clone();
+    let products = ProductRepository::new(pools.clone(), flags);
+    let repository = UserRepository::new(pools, flags);
+    let handler = Arc::new(UserService::new(repository, products, audit_pool, flags));
 
     // Define routes with shared state
     Router::new()
-        .route("/api/users", get(list_users).post(register_user))
+        .route(
+            "/api/users",
+            get(list_users)
+                .route_layer(middleware::from_fn(require_admin))
+                .post(register_user),
+        )
         .route(
             "/api/users/{id}",
-            get(get_user).put(update_user).delete(delete_user),
+            get(get_user)
+                .put(update_user)
+                .delete(delete_user)
+                .route_layer(middleware::from_fn(identify_user)),
+        )
+        .route(
+            "/api/users/{id}/export",
+            get(export_user).route_layer(middleware::from_fn(require_user)),
+        )
+        .route(
+            "/api/users/{id}/reactivate",
+            post(reactivate_user).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/users/inactive",
+            get(list_inactive_users).route_layer(middleware::from_fn(require_admin)),
         )
         .route("/api/auth/login", post(login_user))
+        .route("/api/auth/logout", post(logout_user))
         .with_state(handler)
 }
 
 /// Handler functions that take the shared state
 async fn list_users(
     State(handler): State<SharedHandler>,
-    Query(filter): Query<UserFilter>,
+    ApiQuery(filter): ApiQuery<UserFilter>,
+    page: PageParams,
+) -> impl IntoResponse {
+    handler.list_users(filter, page).await.map_or_else(
+        |err| err.into_response(),
+        |response| response.into_response(),
+    )
+}
+
+async fn list_inactive_users(
+    State(handler): State<SharedHandler>,
+    page: PageParams,
+) -> impl IntoResponse {
+    handler.list_inactive_users(page).await.map_or_else(
+        |err| err.into_response(),
+        |response| response.into_response(),
+    )
+}
+
+async fn reactivate_user(
+    State(handler): State<SharedHandler>,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    handler.reactivate_user(id).await.map_or_else(
+        |err| err.into_response(),
+        |response| response.into_response(),
+    )
+}
+
+async fn export_user(
+    State(handler): State<SharedHandler>,
+    Extension(user): Extension<CurrentUser>,
+    Path(id): Path<Uuid>,
 ) -> impl IntoResponse {
-    handler.list_users(filter).await.map_or_else(
+    handler.export_user(id, &user).await.map_or_else(
         |err| err.into_response(),
         |response| response.into_response(),
     )
@@ -44,7 +111,7 @@ async fn list_users(
 
 async fn register_user(
     State(handler): State<SharedHandler>,
-    Json(user): Json<CreateUser>,
+    ApiJsonOrForm(user): ApiJsonOrForm<CreateUser>,
 ) -> impl IntoResponse {
     handler.register(user).await.map_or_else(
         |err| err.into_response(),
@@ -54,14 +121,22 @@ async fn register_user(
 
 async fn login_user(
     State(handler): State<SharedHandler>,
-    Json(login): Json<LoginUser>,
+    client_ip: Option<Extension<ClientIp>>,
+    ApiQuery(query): ApiQuery<LoginQuery>,
+    ApiJsonOrForm(login): ApiJsonOrForm<LoginUser>,
 ) -> impl IntoResponse {
-    handler.login(login).await.map_or_else(
+    let ip = client_ip.map(|Extension(ClientIp(ip))| ip);
+
+    handler.login(login, query, ip).await.map_or_else(
         |err| err.into_response(),
         |response| response.into_response(),
     )
 }
 
+async fn logout_user(State(handler): State<SharedHandler>) -> impl IntoResponse {
+    handler.logout().into_response()
+}
+
 async fn get_user(State(handler): State<SharedHandler>, Path(id): Path<Uuid>) -> impl IntoResponse {
     handler.get_user(id).await.map_or_else(
         |err| err.into_response(),
@@ -72,7 +147,7 @@ async fn get_user(State(handler): State<SharedHandler>, Path(id): Path<Uuid>) ->
 async fn update_user(
     State(handler): State<SharedHandler>,
     Path(id): Path<Uuid>,
-    Json(update): Json<UpdateUser>,
+    ApiJson(update): ApiJson<UpdateUser>,
 ) -> impl IntoResponse {
     handler.update_user(id, update).await.map_or_else(
         |err| err.into_response(),
@@ -83,9 +158,64 @@ async fn update_user(
 async fn delete_user(
     State(handler): State<SharedHandler>,
     Path(id): Path<Uuid>,
+    ApiQuery(query): ApiQuery<DeleteUserQuery>,
+    user: Option<AuthUser>,
 ) -> impl IntoResponse {
-    handler.delete_user(id).await.map_or_else(
-        |err| err.into_response(),
-        |response| response.into_response(),
-    )
+    let user = user.map(|AuthUser(user)| user);
+
+    handler
+        .delete_user(id, query, user.as_ref())
+        .await
+        .map_or_else(
+            |err| err.into_response(),
+            |response| response.into_response(),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::authentication::Role;
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use tower::ServiceExt;
+
+    /// DELETE a user with hard deletes enabled; the database is never reached
+    async fn hard_delete_as(user: Option<CurrentUser>) -> StatusCode {
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .connect_lazy("postgres://postgres@127.0.0.1:1/unreachable")
+            .unwrap();
+        let flags = FeatureFlags {
+            allow_user_hard_delete: true,
+            ..FeatureFlags::default()
+        };
+        let app = user_routes(DbPools::single(pool), &flags);
+
+        let mut request = Request::builder()
+            .method("DELETE")
+            .uri(format!("/api/users/{}?hard=true", Uuid::new_v4()))
+            .body(Body::empty())
+            .unwrap();
+        if let Some(user) = user {
+            request.extensions_mut().insert(user);
+        }
+
+        app.oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_hard_delete_forbidden_for_non_admin() {
+        let user = CurrentUser {
+            id: Uuid::new_v4(),
+            role: Role::User,
+        };
+        assert_eq!(hard_delete_as(Some(user)).await, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_hard_delete_requires_authentication() {
+        assert_eq!(hard_delete_as(None).await, StatusCode::UNAUTHORIZED);
+    }
 }
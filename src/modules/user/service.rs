@@ -1,41 +1,170 @@
+use crate::core::authentication::{CurrentUser, Role, clear_token_cookie, token_cookie};
+use crate::core::config::FeatureFlags;
+use crate::core::db::DbPool;
 use crate::core::error::ApiError;
+use crate::core::pagination::{Page, PageParams};
+use crate::modules::auth_event::{
+    model::EVENT_LOGIN,
+    repository::{AuthEventRepository, record_auth_event},
+};
+use crate::modules::product::repository::ProductRepository;
 use crate::modules::user::model::{
-    AuthResponse, CreateUser, LoginUser, UpdateUser, UserFilter, UserResponse,
+    AuthResponse, CreateUser, DeleteUserQuery, Email, InactiveUserResponse, LoginQuery, LoginUser,
+    UpdateUser, UserExport, UserFilter, UserResponse,
 };
 use crate::modules::user::repository::UserRepository;
-use crate::utils::generate_token;
-use axum::{Json, http::StatusCode, response::IntoResponse};
+use crate::utils::{generate_token, require_non_empty};
+use axum::{
+    Json,
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
 use once_cell::sync::Lazy;
-use regex::Regex;
 use serde_json::json;
+use std::collections::HashSet;
+use std::net::IpAddr;
 use uuid::Uuid;
-// Email validation regex using Lazy static
-static EMAIL_REGEX: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"^[a-zA-Z0-9._%+-]+@[a-zA-Z0-9.-]+\.[a-zA-Z]{2,}$").unwrap());
+// Common password denylist, lowercased once for case-insensitive O(1) lookups
+static COMMON_PASSWORDS: Lazy<HashSet<String>> = Lazy::new(|| {
+    include_str!("common_passwords.txt")
+        .lines()
+        .map(|line| line.trim().to_lowercase())
+        .filter(|line| !line.is_empty())
+        .collect()
+});
+
+/// Password complexity rules
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    pub min_length: usize,    // Minimum password length
+    pub require_upper: bool,  // Require at least one uppercase letter
+    pub require_digit: bool,  // Require at least one digit
+    pub require_symbol: bool, // Require at least one symbol
+    pub block_common: bool,   // Reject passwords from the common password list
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self::from_flags(&FeatureFlags::default())
+    }
+}
+
+impl PasswordPolicy {
+    /// Password rules from the `PASSWORD_*` settings
+    pub fn from_flags(flags: &FeatureFlags) -> Self {
+        Self {
+            min_length: flags.password_min_length,
+            require_upper: flags.password_require_upper,
+            require_digit: flags.password_require_digit,
+            require_symbol: flags.password_require_symbol,
+            block_common: flags.password_block_common,
+        }
+    }
+
+    /// Check a password against the configured rules
+    pub fn validate(&self, password: &str) -> Result<(), ApiError> {
+        if password.len() < self.min_length {
+            return Err(ApiError::BadRequest(format!(
+                "Password must be at least {} characters",
+                self.min_length
+            )));
+        }
+
+        if self.require_upper && !password.chars().any(char::is_uppercase) {
+            return Err(ApiError::BadRequest(
+                "Password must contain at least one uppercase letter".to_string(),
+            ));
+        }
+
+        if self.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            return Err(ApiError::BadRequest(
+                "Password must contain at least one digit".to_string(),
+            ));
+        }
+
+        if self.require_symbol
+            && !password
+                .chars()
+                .any(|c| !c.is_alphanumeric() && !c.is_whitespace())
+        {
+            return Err(ApiError::BadRequest(
+                "Password must contain at least one symbol".to_string(),
+            ));
+        }
+
+        if self.block_common && COMMON_PASSWORDS.contains(&password.to_lowercase()) {
+            return Err(ApiError::BadRequest("Password is too common".to_string()));
+        }
+
+        Ok(())
+    }
+}
+
+/// Account lockout rules for repeated failed logins
+#[derive(Debug, Clone)]
+pub struct LoginPolicy {
+    pub max_failures: i32,    // Failed attempts before the account is locked
+    pub lockout_minutes: i32, // How long a locked account stays locked
+}
+
+impl Default for LoginPolicy {
+    fn default() -> Self {
+        Self::from_flags(&FeatureFlags::default())
+    }
+}
+
+impl LoginPolicy {
+    /// Lockout rules from `LOGIN_MAX_FAILURES` and `LOGIN_LOCKOUT_MINUTES`
+    pub fn from_flags(flags: &FeatureFlags) -> Self {
+        Self {
+            max_failures: flags.login_max_failures,
+            lockout_minutes: flags.login_lockout_minutes,
+        }
+    }
+}
 
 /// User HTTP request handlers
 pub struct UserService {
-    repository: UserRepository, // User repository for database operations
+    repository: UserRepository,  // User repository for database operations
+    pool: DbPool,                // Connection pool for the authentication audit log
+    products: ProductRepository, // Products owned by users, for data exports
+    auth_events: AuthEventRepository, // Login history, for data exports
+    password_policy: PasswordPolicy, // Password complexity rules
+    login_policy: LoginPolicy,   // Lockout rules for failed logins
+    allow_hard_delete: bool,     // Whether permanent deletion is enabled
+    reuse_deleted_emails: bool,  // Whether soft-deleted users free their email
 }
 
 impl UserService {
     /// Create a new user handler
-    pub fn new(repository: UserRepository) -> Self {
-        Self { repository }
+    pub fn new(
+        repository: UserRepository,
+        products: ProductRepository,
+        pool: DbPool,
+        flags: &FeatureFlags,
+    ) -> Self {
+        Self {
+            repository,
+            auth_events: AuthEventRepository::new(pool.clone()),
+            products,
+            pool,
+            password_policy: PasswordPolicy::from_flags(flags),
+            login_policy: LoginPolicy::from_flags(flags),
+            allow_hard_delete: flags.allow_user_hard_delete,
+            reuse_deleted_emails: flags.reuse_deleted_user_emails,
+        }
     }
 
     /// Handler to register a new user
-    pub async fn register(&self, user: CreateUser) -> Result<impl IntoResponse + '_, ApiError> {
-        // Validate email format
-        if !self.is_valid_email(&user.email) {
-            return Err(ApiError::BadRequest("Invalid email format".to_string()));
-        }
+    pub async fn register(&self, mut user: CreateUser) -> Result<impl IntoResponse + '_, ApiError> {
+        user.username = require_non_empty(user.username.trim().to_string(), "Username")?;
 
         // Validate password strength
         self.validate_password(&user.password)?;
 
         // Validate username and email uniqueness
-        self.validate_unique_fields(&user.username, &user.email)
+        self.validate_unique_fields(&user.username, user.email.as_str())
             .await?;
 
         // Create user in database
@@ -48,28 +177,80 @@ impl UserService {
     }
 
     /// Handler for user login
-    pub async fn login(&self, login: LoginUser) -> Result<impl IntoResponse + '_, ApiError> {
+    ///
+    /// With `cookie=true` the token is also set as an HttpOnly cookie for browser clients.
+    pub async fn login(
+        &self,
+        login: LoginUser,
+        query: LoginQuery,
+        ip: Option<IpAddr>,
+    ) -> Result<Response, ApiError> {
         // Find user by email
-        let user = self.repository.find_by_email(&login.email).await?;
+        let user = match self.repository.find_by_email(login.email.trim()).await {
+            Ok(user) => user,
+            Err(err) => {
+                self.audit_login(None, ip, false).await;
+                return Err(err);
+            }
+        };
+
+        // Reject locked accounts before checking the password
+        if user.is_locked_at(Utc::now()) {
+            self.audit_login(Some(user.id), ip, false).await;
+            return Err(ApiError::Forbidden(
+                "Account temporarily locked".to_string(),
+            ));
+        }
 
         // Verify password
         if !self
             .repository
             .verify_password(&login.password, &user.password)?
         {
+            self.audit_login(Some(user.id), ip, false).await;
+            self.repository
+                .record_failed_login(
+                    user.id,
+                    self.login_policy.max_failures,
+                    self.login_policy.lockout_minutes,
+                )
+                .await?;
+
             return Err(ApiError::Unauthorized(
                 "Invalid email or password".to_string(),
             ));
         }
 
+        // Clear failed attempts and remember when the user last logged in
+        let mut user = user;
+        user.last_login_at = Some(self.repository.record_login(user.id).await?);
+
         // Generate JWT token and create response
-        let token = generate_token(user.id)?;
+        let token = generate_token(user.id, user.role)?;
+        self.audit_login(Some(user.id), ip, true).await;
+        let cookie = (query.cookie == Some(true)).then(|| token_cookie(&token));
         let auth_response = AuthResponse {
             user: UserResponse::from(user),
             token,
         };
 
-        Ok(Json(auth_response))
+        match cookie {
+            Some(cookie) => {
+                Ok(([(header::SET_COOKIE, cookie)], Json(auth_response)).into_response())
+            }
+            None => Ok(Json(auth_response).into_response()),
+        }
+    }
+
+    /// Handler for logout, clearing the token cookie
+    pub fn logout(&self) -> impl IntoResponse + '_ {
+        (
+            [(header::SET_COOKIE, clear_token_cookie())],
+            Json(json!({
+                "success": true,
+                "message": "Logged out"
+            })),
+        )
     }
 
     /// Handler to get a user by ID
@@ -78,34 +259,90 @@ impl UserService {
         Ok(Json(UserResponse::from(user)))
     }
 
-    /// Handler to list users with optional filtering
-    pub async fn list_users(&self, filter: UserFilter) -> Result<impl IntoResponse + '_, ApiError> {
+    /// Handler to list users with optional filtering (admin only, enforced by the route)
+    pub async fn list_users(
+        &self,
+        mut filter: UserFilter,
+        page: PageParams,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        // Only known roles can be filtered on
+        if let Some(role) = &filter.role {
+            role.parse::<Role>().map_err(ApiError::BadRequest)?;
+        }
+        filter
+            .validate_created_range()
+            .map_err(ApiError::BadRequest)?;
+        filter.sort = filter
+            .resolve_sort()
+            .map_err(|err| ApiError::BadRequest(format!("Invalid sort: {}", err)))?;
+
+        filter.limit = Some(page.limit);
+        filter.offset = Some(page.offset);
         let users = self.repository.list(&filter).await?;
 
         // Convert to UserResponse to avoid sending passwords
         let user_responses: Vec<UserResponse> = users.into_iter().map(UserResponse::from).collect();
 
-        Ok(Json(user_responses))
+        Ok(Json(Page::new(user_responses, page)))
+    }
+
+    /// Handler to list soft-deleted users (admin only, enforced by the route)
+    pub async fn list_inactive_users(
+        &self,
+        page: PageParams,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        let users = self
+            .repository
+            .list_inactive(page.limit, page.offset)
+            .await?;
+        let responses: Vec<InactiveUserResponse> =
+            users.into_iter().map(InactiveUserResponse::from).collect();
+
+        Ok(Json(Page::new(responses, page)))
+    }
+
+    /// Handler to restore a soft-deleted user so they can log in again (admin only)
+    pub async fn reactivate_user(&self, id: Uuid) -> Result<impl IntoResponse + '_, ApiError> {
+        let user = self.repository.reactivate(id).await?;
+        Ok(Json(UserResponse::from(user)))
+    }
+
+    /// Handler to export everything stored about a user (self or admin)
+    pub async fn export_user(
+        &self,
+        id: Uuid,
+        user: &CurrentUser,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        ensure_self_or_admin(id, user)?;
+
+        let profile = self.repository.find_by_id(id).await?;
+        let export = UserExport {
+            user: UserResponse::from(profile),
+            products: self.products.list_by_owner(id).await?,
+            auth_events: self.auth_events.list_for_user(id).await?,
+            exported_at: Utc::now(),
+        };
+
+        Ok(Json(export))
     }
 
     /// Handler to update a user
     pub async fn update_user(
         &self,
         id: Uuid,
-        update: UpdateUser,
+        mut update: UpdateUser,
     ) -> Result<impl IntoResponse + '_, ApiError> {
+        normalize_update_user(&mut update)?;
+
         // Get current user to compare with updates
         let current_user = self.repository.find_by_id(id).await?;
 
-        // Validate email if provided
-        if let Some(ref email) = update.email {
-            if !self.is_valid_email(email) {
-                return Err(ApiError::BadRequest("Invalid email format".to_string()));
-            }
-
+        // Validate email uniqueness if provided (the format is checked on deserialization)
+        if let Some(email) = update.email.as_ref().map(Email::as_str) {
             // Only validate uniqueness if email is changing
-            if email != &current_user.email {
-                self.check_field_exists(None, Some(email)).await?;
+            if email != current_user.email {
+                ensure_available("Email", self.repository.exists_by_email(email).await?)?;
+                self.check_deleted_email(email).await?;
             }
         }
 
@@ -113,7 +350,10 @@ impl UserService {
         if let Some(ref username) = update.username {
             // Only validate uniqueness if username is changing
             if username != &current_user.username {
-                self.check_field_exists(Some(username), None).await?;
+                ensure_available(
+                    "Username",
+                    self.repository.exists_by_username(username).await?,
+                )?;
             }
         }
 
@@ -126,9 +366,19 @@ impl UserService {
         Ok(Json(UserResponse::from(updated_user)))
     }
 
-    /// Handler to delete a user
-    pub async fn delete_user(&self, id: Uuid) -> Result<impl IntoResponse + '_, ApiError> {
-        self.repository.delete(id).await?;
+    /// Handler to delete a user (soft delete unless an admin requests a hard delete and it is enabled)
+    pub async fn delete_user(
+        &self,
+        id: Uuid,
+        query: DeleteUserQuery,
+        user: Option<&CurrentUser>,
+    ) -> Result<impl IntoResponse + '_, ApiError> {
+        if query.hard.unwrap_or(false) {
+            ensure_can_hard_delete(user, self.allow_hard_delete)?;
+            self.repository.delete(id).await?;
+        } else {
+            self.repository.soft_delete(id).await?;
+        }
 
         Ok((
             StatusCode::OK,
@@ -142,53 +392,260 @@ impl UserService {
     /// Validate that username and email are unique
     async fn validate_unique_fields(&self, username: &str, email: &str) -> Result<(), ApiError> {
         // Check username and email existence
-        self.check_field_exists(Some(username), None).await?;
-        self.check_field_exists(None, Some(email)).await?;
+        ensure_available(
+            "Username",
+            self.repository.exists_by_username(username).await?,
+        )?;
+        ensure_available("Email", self.repository.exists_by_email(email).await?)?;
+        self.check_deleted_email(email).await?;
 
         Ok(())
     }
 
-    /// Helper method to check if a field exists
-    async fn check_field_exists(
-        &self,
-        username: Option<&str>,
-        email: Option<&str>,
-    ) -> Result<(), ApiError> {
-        let filter = UserFilter {
-            username: username.map(ToString::to_string),
-            email: email.map(ToString::to_string),
-            limit: Some(1),
-            offset: None,
-        };
-
-        let exists = self.repository.list(&filter).await?;
-        if !exists.is_empty() {
-            let field_name = if username.is_some() {
-                "Username"
-            } else {
-                "Email"
-            };
-            return Err(ApiError::BadRequest(format!(
-                "{} already in use",
-                field_name
-            )));
+    /// Block emails still held by soft-deleted users unless reuse is enabled
+    async fn check_deleted_email(&self, email: &str) -> Result<(), ApiError> {
+        if !self.reuse_deleted_emails && self.repository.deleted_email_exists(email).await? {
+            return Err(ApiError::BadRequest("Email already in use".to_string()));
         }
 
         Ok(())
     }
 
+    /// Helper method to record a login attempt in the audit log
+    ///
+    /// Audit failures are logged but never block the login itself.
+    async fn audit_login(&self, user_id: Option<Uuid>, ip: Option<IpAddr>, success: bool) {
+        if let Err(err) = record_auth_event(&self.pool, user_id, EVENT_LOGIN, ip, success).await {
+            tracing::warn!("Failed to record login event: {}", err);
+        }
+    }
+
     /// Validate password strength
     fn validate_password(&self, password: &str) -> Result<(), ApiError> {
-        if password.len() < 8 {
-            return Err(ApiError::BadRequest(
-                "Password must be at least 8 characters".to_string(),
-            ));
+        self.password_policy.validate(password)
+    }
+}
+
+/// Regular users may only access their own data; admins may access anyone's
+fn ensure_self_or_admin(id: Uuid, user: &CurrentUser) -> Result<(), ApiError> {
+    if user.is_admin() || user.id == id {
+        return Ok(());
+    }
+
+    Err(ApiError::Forbidden(
+        "You can only export your own data".to_string(),
+    ))
+}
+
+/// Reject a username or email that another user already holds
+fn ensure_available(field_name: &str, exists: bool) -> Result<(), ApiError> {
+    if exists {
+        return Err(ApiError::BadRequest(format!(
+            "{} already in use",
+            field_name
+        )));
+    }
+    Ok(())
+}
+
+/// Trim the username of a user update, rejecting a value that ends up empty
+///
+/// The email is already trimmed and validated by `Email` during deserialization.
+fn normalize_update_user(update: &mut UpdateUser) -> Result<(), ApiError> {
+    update.username = update
+        .username
+        .as_deref()
+        .map(|username| require_non_empty(username.trim().to_string(), "Username"))
+        .transpose()?;
+    Ok(())
+}
+
+/// Permanent deletion needs an admin and `ALLOW_USER_HARD_DELETE`
+fn ensure_can_hard_delete(user: Option<&CurrentUser>, allowed: bool) -> Result<(), ApiError> {
+    let user = user.ok_or_else(|| ApiError::Unauthorized("Authentication required".to_string()))?;
+
+    if !user.is_admin() {
+        return Err(ApiError::Forbidden(
+            "Only admins can permanently delete users".to_string(),
+        ));
+    }
+
+    if !allowed {
+        return Err(ApiError::Forbidden(
+            "Permanent user deletion is disabled".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rejection(policy: &PasswordPolicy, password: &str) -> String {
+        match policy.validate(password) {
+            Err(ApiError::BadRequest(msg)) => msg,
+            other => panic!("expected bad request, got {:?}", other),
         }
-        Ok(())
     }
 
-    /// Helper method to validate email format
-    fn is_valid_email(&self, email: &str) -> bool {
-        EMAIL_REGEX.is_match(email)
+    #[test]
+    fn test_ensure_self_or_admin_allows_self_export() {
+        let id = Uuid::new_v4();
+        let user = CurrentUser {
+            id,
+            role: Role::User,
+        };
+        assert!(ensure_self_or_admin(id, &user).is_ok());
+
+        let admin = CurrentUser {
+            id: Uuid::new_v4(),
+            role: Role::Admin,
+        };
+        assert!(ensure_self_or_admin(id, &admin).is_ok());
+    }
+
+    #[test]
+    fn test_ensure_self_or_admin_forbids_other_users() {
+        let user = CurrentUser {
+            id: Uuid::new_v4(),
+            role: Role::User,
+        };
+        assert!(matches!(
+            ensure_self_or_admin(Uuid::new_v4(), &user),
+            Err(ApiError::Forbidden(_))
+        ));
+    }
+
+    #[test]
+    fn test_hard_delete_requires_admin_and_flag() {
+        let user = CurrentUser {
+            id: Uuid::new_v4(),
+            role: Role::User,
+        };
+        let admin = CurrentUser {
+            id: Uuid::new_v4(),
+            role: Role::Admin,
+        };
+
+        assert!(matches!(
+            ensure_can_hard_delete(None, true),
+            Err(ApiError::Unauthorized(_))
+        ));
+        assert!(matches!(
+            ensure_can_hard_delete(Some(&user), true),
+            Err(ApiError::Forbidden(_))
+        ));
+        assert!(matches!(
+            ensure_can_hard_delete(Some(&admin), false),
+            Err(ApiError::Forbidden(_))
+        ));
+        assert!(ensure_can_hard_delete(Some(&admin), true).is_ok());
+    }
+
+    #[test]
+    fn test_ensure_available_rejects_existing_value() {
+        assert!(matches!(
+            ensure_available("Username", true),
+            Err(ApiError::BadRequest(message)) if message == "Username already in use"
+        ));
+        assert!(matches!(
+            ensure_available("Email", true),
+            Err(ApiError::BadRequest(message)) if message == "Email already in use"
+        ));
+    }
+
+    #[test]
+    fn test_ensure_available_accepts_new_value() {
+        assert!(ensure_available("Username", false).is_ok());
+        assert!(ensure_available("Email", false).is_ok());
+    }
+
+    #[test]
+    fn test_normalize_update_user_trims_and_rejects_empty() {
+        let mut update = UpdateUser {
+            username: Some("  alice ".to_string()),
+            email: None,
+            password: None,
+        };
+        normalize_update_user(&mut update).unwrap();
+        assert_eq!(update.username.as_deref(), Some("alice"));
+
+        let mut update = UpdateUser {
+            username: Some("   ".to_string()),
+            email: None,
+            password: None,
+        };
+        assert!(matches!(
+            normalize_update_user(&mut update),
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_default_policy_only_checks_length() {
+        let policy = PasswordPolicy::default();
+        assert!(policy.validate("password").is_ok());
+        assert_eq!(
+            rejection(&policy, "short"),
+            "Password must be at least 8 characters"
+        );
+    }
+
+    #[test]
+    fn test_min_length_rule() {
+        let policy = PasswordPolicy {
+            min_length: 12,
+            ..PasswordPolicy::default()
+        };
+        assert!(policy.validate("longpassword").is_ok());
+        assert_eq!(
+            rejection(&policy, "password"),
+            "Password must be at least 12 characters"
+        );
+    }
+
+    #[test]
+    fn test_require_upper_rule() {
+        let policy = PasswordPolicy {
+            require_upper: true,
+            ..PasswordPolicy::default()
+        };
+        assert!(policy.validate("Password").is_ok());
+        assert!(rejection(&policy, "password").contains("uppercase"));
+    }
+
+    #[test]
+    fn test_require_digit_rule() {
+        let policy = PasswordPolicy {
+            require_digit: true,
+            ..PasswordPolicy::default()
+        };
+        assert!(policy.validate("password1").is_ok());
+        assert!(rejection(&policy, "password").contains("digit"));
+    }
+
+    #[test]
+    fn test_require_symbol_rule() {
+        let policy = PasswordPolicy {
+            require_symbol: true,
+            ..PasswordPolicy::default()
+        };
+        assert!(policy.validate("password!").is_ok());
+        assert!(rejection(&policy, "password").contains("symbol"));
+    }
+
+    #[test]
+    fn test_block_common_rule() {
+        let policy = PasswordPolicy {
+            block_common: true,
+            ..PasswordPolicy::default()
+        };
+        assert_eq!(rejection(&policy, "Password123"), "Password is too common");
+        assert!(policy.validate("correct-horse-battery").is_ok());
+
+        // Off by default for backward compatibility
+        assert!(PasswordPolicy::default().validate("password123").is_ok());
     }
 }
@@ -1,13 +1,357 @@
+use anyhow::bail;
+use serde::de::DeserializeOwned;
 use sqlx::PgPool;
+use std::{env, fmt, path::PathBuf, str::FromStr};
+use tokio::fs;
+
+use crate::core::config::{FeatureFlags, env_flag};
 
 pub mod product;
 pub mod user;
 
-/// Run all data seeders
-pub async fn run_all_seeders(pool: &PgPool) -> anyhow::Result<()> {
-    // Run product seeder
-    product::seed(pool).await?;
-    user::seed(pool).await?;
+/// Outcome of a single seeder run
+#[derive(Debug, Default)]
+pub struct SeedSummary {
+    pub inserted: usize,              // Records inserted successfully
+    pub updated: usize,               // Existing records changed in upsert mode
+    pub skipped: usize,               // Records skipped because data already exists
+    pub failed: Vec<(usize, String)>, // Index of each failed record and the reason
+}
+
+impl SeedSummary {
+    /// Whether any record failed to seed
+    pub fn has_failures(&self) -> bool {
+        !self.failed.is_empty()
+    }
+
+    /// Count a record that was seeded without errors
+    fn record(&mut self, outcome: SeedOutcome) {
+        match outcome {
+            SeedOutcome::Inserted => self.inserted += 1,
+            SeedOutcome::Updated => self.updated += 1,
+            SeedOutcome::Unchanged => self.skipped += 1,
+        }
+    }
+}
+
+/// What happened to a single seed record
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SeedOutcome {
+    Inserted,
+    Updated,
+    Unchanged,
+}
+
+impl fmt::Display for SeedSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "inserted: {}, updated: {}, skipped: {}, failed: {}",
+            self.inserted,
+            self.updated,
+            self.skipped,
+            self.failed.len()
+        )?;
+
+        for (index, reason) in &self.failed {
+            write!(f, "\n  - record #{}: {}", index, reason)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether seeding should stop at the first failed record (`SEED_STRICT`)
+pub fn strict_mode() -> bool {
+    env_flag("SEED_STRICT", false)
+}
+
+/// How seeders treat data that already exists (`SEED_MODE`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SeedMode {
+    /// Seed only into empty tables
+    #[default]
+    Skip,
+    /// Update records matching the seed data and insert the rest
+    Upsert,
+    /// Truncate the seeded tables (and everything referencing them) first
+    Force,
+}
+
+impl SeedMode {
+    /// Read `SEED_MODE`, defaulting to `skip`; unknown values are an error
+    pub fn from_env() -> anyhow::Result<Self> {
+        match env::var("SEED_MODE") {
+            Ok(value) if !value.trim().is_empty() => value.parse(),
+            _ => Ok(Self::default()),
+        }
+    }
+}
+
+impl FromStr for SeedMode {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "skip" => Ok(Self::Skip),
+            "upsert" => Ok(Self::Upsert),
+            "force" => Ok(Self::Force),
+            other => bail!(
+                "Invalid SEED_MODE '{}': expected skip, upsert or force",
+                other
+            ),
+        }
+    }
+}
+
+/// Available seeders
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Seeder {
+    Users,
+    Products,
+}
+
+impl Seeder {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Users => "users",
+            Self::Products => "products",
+        }
+    }
+
+    /// Table holding the seeded records
+    fn table(self) -> &'static str {
+        match self {
+            Self::Users => "users",
+            Self::Products => "products",
+        }
+    }
+
+    /// Seeders whose data must exist first (products reference their owners)
+    fn depends_on(self) -> &'static [Seeder] {
+        match self {
+            Self::Users => &[],
+            Self::Products => &[Self::Users],
+        }
+    }
+
+    async fn run(
+        self,
+        pool: &PgPool,
+        strict: bool,
+        mode: SeedMode,
+        flags: &FeatureFlags,
+    ) -> anyhow::Result<SeedSummary> {
+        match self {
+            Self::Users => user::seed(pool, strict, mode, flags).await,
+            Self::Products => product::seed(pool, strict, mode, flags).await,
+        }
+    }
+}
+
+/// Order seeders so each one runs after the seeders it depends on
+fn seed_order(seeders: &[Seeder]) -> Vec<Seeder> {
+    fn visit(seeder: Seeder, ordered: &mut Vec<Seeder>) {
+        if ordered.contains(&seeder) {
+            return;
+        }
+        for dependency in seeder.depends_on() {
+            visit(*dependency, ordered);
+        }
+        ordered.push(seeder);
+    }
+
+    let mut ordered = Vec::new();
+    for seeder in seeders {
+        visit(*seeder, &mut ordered);
+    }
+    ordered
+}
+
+/// Run all data seeders in dependency order, using the flags of the loaded `Config`
+pub async fn run_all_seeders(
+    pool: &PgPool,
+    flags: &FeatureFlags,
+) -> anyhow::Result<Vec<(&'static str, SeedSummary)>> {
+    let strict = strict_mode();
+    let mode = SeedMode::from_env()?;
+    let seeders = seed_order(&[Seeder::Products, Seeder::Users]);
+    let mut summaries = Vec::new();
+
+    if mode == SeedMode::Force {
+        let sql = truncate_sql(&seeders);
+        tracing::warn!("SEED_MODE=force, running: {}", sql);
+        sqlx::query(&sql).execute(pool).await?;
+    }
+
+    for seeder in seeders {
+        summaries.push((seeder.name(), seeder.run(pool, strict, mode, flags).await?));
+    }
+
+    for (name, summary) in &summaries {
+        if summary.has_failures() {
+            tracing::warn!("Seeding {} finished with failures: {}", name, summary);
+        } else {
+            tracing::info!("Seeding {} finished: {}", name, summary);
+        }
+    }
+
+    Ok(summaries)
+}
+
+/// Truncate every seeded table in one statement; CASCADE also clears rows that reference them
+fn truncate_sql(seeders: &[Seeder]) -> String {
+    let tables: Vec<&str> = seeders.iter().map(|seeder| seeder.table()).collect();
+    format!("TRUNCATE {} RESTART IDENTITY CASCADE", tables.join(", "))
+}
+
+/// Where a seeder reads its data from
+struct SeedSource {
+    inline_var: &'static str,   // Env var holding inline JSON
+    path_var: &'static str,     // Env var overriding the file path
+    default_path: &'static str, // File used when no override is given
+}
+
+impl SeedSource {
+    /// Load the seed JSON, preferring inline data over the file
+    ///
+    /// Returns `None` when neither inline data nor the file is available.
+    async fn load(&self) -> anyhow::Result<Option<String>> {
+        let inline = env::var(self.inline_var).ok();
+        let path = env::var(self.path_var)
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| PathBuf::from(self.default_path));
+
+        read_seed_data(inline, path).await
+    }
+}
+
+/// Read seed data from inline JSON or a file, skipping when neither is present
+async fn read_seed_data(inline: Option<String>, path: PathBuf) -> anyhow::Result<Option<String>> {
+    if let Some(json) = inline.filter(|json| !json.trim().is_empty()) {
+        tracing::info!("Using inline seed data");
+        return Ok(Some(json));
+    }
+
+    if !fs::try_exists(&path).await? {
+        tracing::info!("Seed file {} not found, skipping", path.display());
+        return Ok(None);
+    }
+
+    Ok(Some(fs::read_to_string(&path).await?))
+}
+
+/// Parse a JSON array record by record so one malformed entry doesn't reject the whole file
+fn parse_records<T: DeserializeOwned>(
+    json_content: &str,
+    summary: &mut SeedSummary,
+) -> anyhow::Result<Vec<(usize, T)>> {
+    let values: Vec<serde_json::Value> = serde_json::from_str(json_content)?;
+    let mut records = Vec::with_capacity(values.len());
+
+    for (index, value) in values.into_iter().enumerate() {
+        match serde_json::from_value(value) {
+            Ok(record) => records.push((index, record)),
+            Err(e) => summary
+                .failed
+                .push((index, format!("Invalid record: {}", e))),
+        }
+    }
+
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::product::model::CreateProduct;
+
+    #[test]
+    fn test_parse_records_keeps_valid_and_reports_invalid() {
+        let json = r#"[
+            {"name": "Valid", "price": 1000.0},
+            {"name": "Missing price"},
+            {"name": "Also valid", "price": 2000.0, "stock": 5}
+        ]"#;
+
+        let mut summary = SeedSummary::default();
+        let records: Vec<(usize, CreateProduct)> = parse_records(json, &mut summary).unwrap();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].0, 0);
+        assert_eq!(records[1].0, 2);
+        assert_eq!(summary.failed.len(), 1);
+        assert_eq!(summary.failed[0].0, 1);
+        assert!(summary.has_failures());
+    }
+
+    #[tokio::test]
+    async fn test_read_seed_data_prefers_inline_json() {
+        let json = r#"[{"name": "Inline", "price": 1.0}]"#.to_string();
+        let data = read_seed_data(Some(json.clone()), PathBuf::from("data/products.json"))
+            .await
+            .unwrap();
+
+        assert_eq!(data, Some(json));
+    }
+
+    #[tokio::test]
+    async fn test_read_seed_data_skips_when_absent() {
+        let data = read_seed_data(None, PathBuf::from("data/does-not-exist.json"))
+            .await
+            .unwrap();
+
+        assert_eq!(data, None);
+    }
+
+    #[test]
+    fn test_seed_order_runs_users_before_products() {
+        assert_eq!(
+            seed_order(&[Seeder::Products, Seeder::Users]),
+            vec![Seeder::Users, Seeder::Products]
+        );
+        assert_eq!(
+            seed_order(&[Seeder::Products]),
+            vec![Seeder::Users, Seeder::Products]
+        );
+    }
+
+    #[test]
+    fn test_seed_mode_parses_known_values() {
+        assert_eq!(SeedMode::default(), SeedMode::Skip);
+        assert_eq!("skip".parse::<SeedMode>().unwrap(), SeedMode::Skip);
+        assert_eq!(" Upsert ".parse::<SeedMode>().unwrap(), SeedMode::Upsert);
+        assert_eq!("FORCE".parse::<SeedMode>().unwrap(), SeedMode::Force);
+        assert!("replace".parse::<SeedMode>().is_err());
+    }
+
+    #[test]
+    fn test_truncate_sql_covers_all_seeded_tables() {
+        assert_eq!(
+            truncate_sql(&seed_order(&[Seeder::Products])),
+            "TRUNCATE users, products RESTART IDENTITY CASCADE"
+        );
+    }
+
+    #[test]
+    fn test_summary_counts_outcomes() {
+        let mut summary = SeedSummary::default();
+        summary.record(SeedOutcome::Inserted);
+        summary.record(SeedOutcome::Updated);
+        summary.record(SeedOutcome::Unchanged);
+
+        assert_eq!(
+            summary.to_string(),
+            "inserted: 1, updated: 1, skipped: 1, failed: 0"
+        );
+    }
+
+    #[test]
+    fn test_parse_records_rejects_non_array() {
+        let mut summary = SeedSummary::default();
+        let result: anyhow::Result<Vec<(usize, CreateProduct)>> =
+            parse_records(r#"{"name": "x"}"#, &mut summary);
 
-    Ok(())
+        assert!(result.is_err());
+    }
 }
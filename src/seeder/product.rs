@@ -1,50 +1,306 @@
+use serde::Deserialize;
 use sqlx::PgPool;
-use std::path::Path;
-use tokio::fs;
+use std::collections::HashMap;
+use uuid::Uuid;
 
-use crate::modules::product::model::CreateProduct;
+use super::{SeedMode, SeedOutcome, SeedSource, SeedSummary, parse_records};
+use crate::core::{config::FeatureFlags, db::DbPools, error::ApiError};
+use crate::modules::product::model::{CreateProduct, Product, UpdateProduct};
 use crate::modules::product::repository::ProductRepository;
+use crate::modules::user::repository::UserRepository;
 
-/// Seed product data from JSON file
-pub async fn seed(pool: &PgPool) -> anyhow::Result<()> {
-    let repo = ProductRepository::new(pool.clone());
+const SEED_SOURCE: SeedSource = SeedSource {
+    inline_var: "SEED_PRODUCTS_JSON",
+    path_var: "SEED_PRODUCTS_PATH",
+    default_path: "data/products.json",
+};
+
+/// Seed record: a regular product payload plus the email of its owner
+#[derive(Debug, Deserialize)]
+struct SeedProduct {
+    #[serde(flatten)]
+    product: CreateProduct,
+    owner_email: Option<String>,
+}
+
+/// Resolve owner emails to user IDs, remembering each lookup
+///
+/// Unknown emails (e.g. when no users were seeded) leave the product unowned.
+struct OwnerResolver {
+    users: UserRepository,
+    cache: HashMap<String, Option<Uuid>>,
+}
+
+impl OwnerResolver {
+    async fn resolve(&mut self, email: Option<&str>) -> Option<Uuid> {
+        let email = email?;
+
+        if let Some(owner_id) = self.cache.get(email) {
+            return *owner_id;
+        }
+
+        let owner_id = match self.users.find_by_email(email).await {
+            Ok(user) => Some(user.id),
+            Err(_) => {
+                tracing::warn!("Seed owner {} not found, leaving product unowned", email);
+                None
+            }
+        };
+        self.cache.insert(email.to_string(), owner_id);
+        owner_id
+    }
+}
+
+/// Insert a seed product, or in upsert mode update the product it matches
+///
+/// Products are matched by SKU when the record has one, otherwise by name.
+/// New products without a stock get `default_stock`; existing ones keep theirs.
+async fn seed_one(
+    repo: &ProductRepository,
+    product: &mut CreateProduct,
+    owner_id: Option<Uuid>,
+    mode: SeedMode,
+    default_stock: i32,
+) -> Result<SeedOutcome, ApiError> {
+    if mode == SeedMode::Upsert {
+        let existing = match &product.sku {
+            Some(sku) => repo.find_by_sku(sku).await?,
+            None => repo.find_by_name(&product.name).await?,
+        };
+
+        if let Some(existing) = existing {
+            return match seed_update(&existing, product) {
+                Some(update) => {
+                    repo.update(existing.id, &update, None).await?;
+                    Ok(SeedOutcome::Updated)
+                }
+                None => Ok(SeedOutcome::Unchanged),
+            };
+        }
+    }
+
+    product.apply_default_stock(default_stock);
+    repo.create(product, owner_id).await?;
+    Ok(SeedOutcome::Inserted)
+}
+
+/// Update that makes an existing product match its seed record, or `None` if it already does
+///
+/// Optional fields left out of the seed record are not touched, and neither is the owner.
+fn seed_update(existing: &Product, seed: &CreateProduct) -> Option<UpdateProduct> {
+    // Some(new value) when the seed value differs from the current one
+    fn changed<T: PartialEq>(current: &T, seeded: T) -> Option<T> {
+        (*current != seeded).then_some(seeded)
+    }
+
+    let update = UpdateProduct {
+        sku: seed
+            .sku
+            .clone()
+            .and_then(|sku| changed(&existing.sku, Some(sku))),
+        name: changed(&existing.name, seed.name.clone()),
+        description: seed
+            .description
+            .clone()
+            .and_then(|description| changed(&existing.description, Some(description))),
+        price: changed(&existing.price, seed.price),
+        stock: seed.stock.and_then(|stock| changed(&existing.stock, stock)),
+        category: seed
+            .category
+            .clone()
+            .and_then(|category| changed(&existing.category, Some(category))),
+        is_active: None,
+        is_featured: seed
+            .is_featured
+            .and_then(|featured| changed(&existing.is_featured, featured)),
+        version: existing.version,
+    };
+
+    let has_changes = update.sku.is_some()
+        || update.name.is_some()
+        || update.description.is_some()
+        || update.price.is_some()
+        || update.stock.is_some()
+        || update.category.is_some()
+        || update.is_featured.is_some();
+    has_changes.then_some(update)
+}
+
+/// Seed product data from inline JSON or a JSON file
+pub async fn seed(
+    pool: &PgPool,
+    strict: bool,
+    mode: SeedMode,
+    flags: &FeatureFlags,
+) -> anyhow::Result<SeedSummary> {
+    let repo = ProductRepository::new(DbPools::single(pool.clone()), flags);
+    let mut owners = OwnerResolver {
+        users: UserRepository::new(DbPools::single(pool.clone()), flags),
+        cache: HashMap::new(),
+    };
+    let mut summary = SeedSummary::default();
+
+    // Read seed data from inline JSON or file
+    let Some(json_content) = SEED_SOURCE.load().await? else {
+        return Ok(summary);
+    };
+
+    let products: Vec<(usize, SeedProduct)> = parse_records(&json_content, &mut summary)?;
 
     // Check if we need to seed by looking for existing products
     let filter = crate::modules::product::model::ProductFilter {
         name: None,
         category: None,
+        tag: None,
         min_price: None,
         max_price: None,
         is_active: None,
+        featured: None,
         limit: Some(1),
         offset: None,
+        fields: None,
+        mine: None,
+        cursor: None,
+        owner_id: None,
+        after: None,
     };
 
     let existing = repo.list(&filter).await?;
-    if !existing.is_empty() {
+    if mode != SeedMode::Upsert && !existing.is_empty() {
         tracing::info!("Products already exist, skipping seed");
-        return Ok(());
+        summary.skipped = products.len();
+        return Ok(summary);
+    }
+
+    if strict && summary.has_failures() {
+        return Ok(summary);
     }
 
     tracing::info!("Seeding products from JSON data...");
 
-    // Path to JSON file
-    let json_path = Path::new("data/products.json");
+    // Insert products, continuing past failures unless in strict mode
+    let default_stock = flags.default_initial_stock;
+    for (index, mut seed_product) in products {
+        let owner_id = owners.resolve(seed_product.owner_email.as_deref()).await;
 
-    // Read and parse JSON file
-    let json_content = fs::read_to_string(json_path).await?;
-    let products: Vec<CreateProduct> = serde_json::from_str(&json_content)?;
+        match seed_one(
+            &repo,
+            &mut seed_product.product,
+            owner_id,
+            mode,
+            default_stock,
+        )
+        .await
+        {
+            Ok(outcome) => summary.record(outcome),
+            Err(e) => {
+                tracing::warn!("Failed to seed product #{}: {}", index, e);
+                summary.failed.push((index, e.to_string()));
 
-    // Insert all products
-    // In the seed function, change:
-    let products_count = products.len();
-    for product in products {
-        repo.create(&product).await?;
+                if strict {
+                    break;
+                }
+            }
+        }
     }
 
     tracing::info!(
-        "Product seeding completed successfully. Added {} products",
-        products_count
+        "Product seeding completed. Added {} and updated {} products",
+        summary.inserted,
+        summary.updated
     );
-    Ok(())
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn seeded_product() -> Product {
+        let now = Utc::now();
+        Product {
+            id: Uuid::new_v4(),
+            sku: Some("LAPTOP-001".to_string()),
+            name: "Laptop".to_string(),
+            description: Some("14 inch".to_string()),
+            price: 15000000.0,
+            formatted_price: None,
+            stock: 10,
+            category: Some("Electronics".to_string()),
+            is_active: true,
+            is_featured: false,
+            version: 3,
+            owner_id: None,
+            tags: Vec::new(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn seed_record(price: f64) -> CreateProduct {
+        CreateProduct {
+            sku: Some("LAPTOP-001".to_string()),
+            name: "Laptop".to_string(),
+            description: Some("14 inch".to_string()),
+            price,
+            stock: Some(10),
+            category: Some("Electronics".to_string()),
+            is_featured: None,
+        }
+    }
+
+    #[test]
+    fn test_seed_update_changes_only_the_price() {
+        let existing = seeded_product();
+
+        let update = seed_update(&existing, &seed_record(13500000.0)).unwrap();
+        assert_eq!(update.price, Some(13500000.0));
+        assert_eq!(update.version, existing.version);
+        assert!(update.sku.is_none());
+        assert!(update.name.is_none());
+        assert!(update.description.is_none());
+        assert!(update.stock.is_none());
+        assert!(update.category.is_none());
+        assert!(update.is_featured.is_none());
+    }
+
+    #[test]
+    fn test_seed_update_skips_unchanged_products() {
+        assert!(seed_update(&seeded_product(), &seed_record(15000000.0)).is_none());
+    }
+
+    #[test]
+    fn test_seed_update_ignores_fields_missing_from_the_record() {
+        let record = CreateProduct {
+            sku: None,
+            description: None,
+            stock: None,
+            category: None,
+            ..seed_record(15000000.0)
+        };
+
+        assert!(seed_update(&seeded_product(), &record).is_none());
+    }
+
+    #[test]
+    fn test_seed_product_reads_optional_owner_email() {
+        let mut summary = SeedSummary::default();
+        let records: Vec<(usize, SeedProduct)> = parse_records(
+            r#"[
+                {"name": "Owned", "price": 1000.0, "owner_email": "admin@example.com"},
+                {"name": "Unowned", "price": 2000.0}
+            ]"#,
+            &mut summary,
+        )
+        .unwrap();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].1.product.name, "Owned");
+        assert_eq!(
+            records[0].1.owner_email.as_deref(),
+            Some("admin@example.com")
+        );
+        assert_eq!(records[1].1.owner_email, None);
+    }
 }
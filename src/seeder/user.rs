@@ -1,46 +1,185 @@
+use serde::Deserialize;
 use sqlx::PgPool;
-use std::path::Path;
-use tokio::fs;
 
-use crate::modules::user::model::CreateUser;
+use super::{SeedMode, SeedOutcome, SeedSource, SeedSummary, parse_records};
+use crate::core::{authentication::Role, config::FeatureFlags, db::DbPools, error::ApiError};
+use crate::modules::user::model::{CreateUser, UpdateUser, User};
 use crate::modules::user::repository::UserRepository;
 
-/// Seed user data from JSON file
-pub async fn seed(pool: &PgPool) -> anyhow::Result<()> {
-    let repo = UserRepository::new(pool.clone());
+const SEED_SOURCE: SeedSource = SeedSource {
+    inline_var: "SEED_USERS_JSON",
+    path_var: "SEED_USERS_PATH",
+    default_path: "data/users.json",
+};
+
+/// Seed record: a regular registration payload plus an optional role
+#[derive(Debug, Deserialize)]
+struct SeedUser {
+    #[serde(flatten)]
+    user: CreateUser,
+    #[serde(default)]
+    role: Role,
+}
+
+/// Insert a seed user, or in upsert mode update the user with the same email
+async fn seed_one(
+    repo: &UserRepository,
+    seed_user: &SeedUser,
+    mode: SeedMode,
+) -> Result<SeedOutcome, ApiError> {
+    if mode == SeedMode::Upsert {
+        match repo.find_by_email(seed_user.user.email.as_str()).await {
+            Ok(existing) => return update_existing(repo, &existing, seed_user).await,
+            Err(ApiError::NotFound(_)) => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    let user = repo.create(&seed_user.user).await?;
+    if seed_user.role != Role::User {
+        repo.set_role(user.id, seed_user.role).await?;
+    }
+    Ok(SeedOutcome::Inserted)
+}
+
+/// Bring an existing user's username, password and role in line with the seed record
+async fn update_existing(
+    repo: &UserRepository,
+    existing: &User,
+    seed_user: &SeedUser,
+) -> Result<SeedOutcome, ApiError> {
+    let password_matches = repo.verify_password(&seed_user.user.password, &existing.password)?;
+    let update = seed_update(existing, &seed_user.user, password_matches);
+    let role_changed = existing.role != seed_user.role;
+
+    if update.is_none() && !role_changed {
+        return Ok(SeedOutcome::Unchanged);
+    }
+
+    if let Some(update) = update {
+        repo.update(existing.id, &update).await?;
+    }
+    if role_changed {
+        repo.set_role(existing.id, seed_user.role).await?;
+    }
+    Ok(SeedOutcome::Updated)
+}
+
+/// Profile update that makes an existing user match its seed record, or `None` if it already does
+fn seed_update(existing: &User, seed: &CreateUser, password_matches: bool) -> Option<UpdateUser> {
+    let username = (existing.username != seed.username).then(|| seed.username.clone());
+    let password = (!password_matches).then(|| seed.password.clone());
+
+    (username.is_some() || password.is_some()).then_some(UpdateUser {
+        username,
+        email: None,
+        password,
+    })
+}
+
+/// Seed user data from inline JSON or a JSON file
+pub async fn seed(
+    pool: &PgPool,
+    strict: bool,
+    mode: SeedMode,
+    flags: &FeatureFlags,
+) -> anyhow::Result<SeedSummary> {
+    let repo = UserRepository::new(DbPools::single(pool.clone()), flags);
+    let mut summary = SeedSummary::default();
+
+    // Read seed data from inline JSON or file
+    let Some(json_content) = SEED_SOURCE.load().await? else {
+        return Ok(summary);
+    };
+
+    let users: Vec<(usize, SeedUser)> = parse_records(&json_content, &mut summary)?;
 
     // Check if we need to seed by looking for existing users
     let filter = crate::modules::user::model::UserFilter {
-        username: None,
-        email: None,
         limit: Some(1),
-        offset: None,
+        ..Default::default()
     };
 
     let existing = repo.list(&filter).await?;
-    if !existing.is_empty() {
+    if mode != SeedMode::Upsert && !existing.is_empty() {
         tracing::info!("Users already exist, skipping seed");
-        return Ok(());
+        summary.skipped = users.len();
+        return Ok(summary);
     }
 
-    tracing::info!("Seeding users from JSON data...");
+    if strict && summary.has_failures() {
+        return Ok(summary);
+    }
 
-    // Path to JSON file
-    let json_path = Path::new("data/users.json");
+    tracing::info!("Seeding users from JSON data...");
 
-    // Read and parse JSON file
-    let json_content = fs::read_to_string(json_path).await?;
-    let users: Vec<CreateUser> = serde_json::from_str(&json_content)?;
+    // Insert users, continuing past failures unless in strict mode
+    for (index, seed_user) in users {
+        match seed_one(&repo, &seed_user, mode).await {
+            Ok(outcome) => summary.record(outcome),
+            Err(e) => {
+                tracing::warn!("Failed to seed user #{}: {}", index, e);
+                summary.failed.push((index, e.to_string()));
 
-    // Insert all users
-    let users_count = users.len();
-    for user in users {
-        repo.create(&user).await?;
+                if strict {
+                    break;
+                }
+            }
+        }
     }
 
     tracing::info!(
-        "User seeding completed successfully. Added {} users",
-        users_count
+        "User seeding completed. Added {} and updated {} users",
+        summary.inserted,
+        summary.updated
     );
-    Ok(())
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    fn existing_user() -> User {
+        let now = Utc::now();
+        User {
+            id: Uuid::new_v4(),
+            username: "admin".to_string(),
+            email: "admin@example.com".to_string(),
+            password: "hash".to_string(),
+            role: Role::Admin,
+            failed_login_count: 0,
+            locked_until: None,
+            deleted_at: None,
+            last_login_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn seed_record(username: &str) -> CreateUser {
+        CreateUser {
+            username: username.to_string(),
+            email: "admin@example.com".to_string().try_into().unwrap(),
+            password: "password123".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_seed_update_changes_username_and_password_only_when_needed() {
+        let existing = existing_user();
+
+        assert!(seed_update(&existing, &seed_record("admin"), true).is_none());
+
+        let update = seed_update(&existing, &seed_record("root"), true).unwrap();
+        assert_eq!(update.username.as_deref(), Some("root"));
+        assert!(update.password.is_none());
+        assert!(update.email.is_none());
+
+        let update = seed_update(&existing, &seed_record("admin"), false).unwrap();
+        assert!(update.username.is_none());
+        assert_eq!(update.password.as_deref(), Some("password123"));
+    }
 }
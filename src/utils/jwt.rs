@@ -1,63 +1,462 @@
-use crate::core::error::ApiError;
-use chrono::{Duration, Utc};
+use crate::core::{
+    authentication::{CurrentUser, Role},
+    config::{Config, ConfigError},
+    error::ApiError,
+};
+use chrono::{DateTime, Duration, Utc};
 use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, encode};
+use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
-use std::env;
+use std::{env, fs};
 use tracing::error;
 use uuid::Uuid;
 
+/// Masa berlaku token dalam jam
+pub const TOKEN_TTL_HOURS: i64 = 24;
+
+/// Pengaturan JWT, dimuat sekali saat startup lewat `init_jwt`
+static JWT_SETTINGS: OnceCell<Result<JwtSettings, ConfigError>> = OnceCell::new();
+
 #[derive(Debug, Serialize, Deserialize)]
 struct Claims {
     sub: String, // Subject (user ID)
     exp: usize,  // Expiration time
     iat: usize,  // Issued at
+    iss: String, // Issuer
+    aud: String, // Audience
+    #[serde(default)]
+    role: Role, // User role (tokens without it are treated as regular users)
+}
+
+/// Akhiran audience token undo, supaya tidak bisa dipakai sebagai token login (dan sebaliknya)
+const UNDO_DELETE_AUDIENCE_SUFFIX: &str = ":undo-delete";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct UndoDeleteClaims {
+    sub: String, // Product ID
+    jti: String, // products.delete_id, hanya bisa dipakai sekali
+    uid: String, // User yang menghapus
+    exp: usize,  // Akhir jendela undo
+    iat: usize,  // Issued at
+    iss: String, // Issuer
+    aud: String, // Audience + UNDO_DELETE_AUDIENCE_SUFFIX
+}
+
+/// Isi token undo untuk produk yang baru dihapus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UndoDelete {
+    pub product_id: Uuid,
+    pub undo_id: Uuid,    // products.delete_id dari penghapusan ini
+    pub deleted_by: Uuid, // User yang menghapus
+}
+
+/// Algoritma, key, issuer dan audience untuk token JWT
+struct JwtSettings {
+    algorithm: Algorithm,
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    issuer: String,
+    audience: String,
+    leeway: u64,
+}
+
+impl JwtSettings {
+    /// Muat pengaturan JWT: secret dan key dari environment, sisanya dari `Config`
+    ///
+    /// HS256 (default) memakai `JWT_SECRET`. RS256 memakai private key dari
+    /// `JWT_PRIVATE_KEY_PATH` untuk signing dan public key dari
+    /// `JWT_PUBLIC_KEY_PATH` untuk verifikasi.
+    ///
+    /// `JWT_LEEWAY_SECS` menerima token yang baru saja expired (atau belum
+    /// berlaku) selama selisihnya masih di bawah nilai ini. Makin besar leeway,
+    /// makin lama token yang bocor atau sudah logout tetap bisa dipakai.
+    fn load(config: &Config) -> Result<Self, ConfigError> {
+        let algorithm = env::var("JWT_ALGORITHM").unwrap_or_else(|_| "HS256".to_string());
+
+        let (algorithm, encoding_key, decoding_key) = match algorithm.to_uppercase().as_str() {
+            "HS256" => {
+                let secret = env::var("JWT_SECRET")
+                    .ok()
+                    .filter(|secret| !secret.is_empty())
+                    .ok_or(ConfigError::Missing("JWT_SECRET"))?;
+
+                (
+                    Algorithm::HS256,
+                    EncodingKey::from_secret(secret.as_bytes()),
+                    DecodingKey::from_secret(secret.as_bytes()),
+                )
+            }
+            "RS256" => {
+                let (private_path, private_pem) = read_key_file("JWT_PRIVATE_KEY_PATH")?;
+                let (public_path, public_pem) = read_key_file("JWT_PUBLIC_KEY_PATH")?;
+
+                (
+                    Algorithm::RS256,
+                    EncodingKey::from_rsa_pem(&private_pem)
+                        .map_err(|e| key_error("JWT_PRIVATE_KEY_PATH", private_path, e))?,
+                    DecodingKey::from_rsa_pem(&public_pem)
+                        .map_err(|e| key_error("JWT_PUBLIC_KEY_PATH", public_path, e))?,
+                )
+            }
+            _ => {
+                return Err(ConfigError::Invalid {
+                    name: "JWT_ALGORITHM",
+                    value: algorithm,
+                    reason: "must be HS256 or RS256",
+                });
+            }
+        };
+
+        Ok(Self {
+            algorithm,
+            encoding_key,
+            decoding_key,
+            leeway: config.jwt_leeway_secs,
+            issuer: config.jwt_issuer.clone(),
+            audience: config.jwt_audience.clone(),
+        })
+    }
+}
+
+/// Baca isi file key yang path-nya ada di env var `name`
+fn read_key_file(name: &'static str) -> Result<(String, Vec<u8>), ConfigError> {
+    let path = env::var(name)
+        .ok()
+        .filter(|path| !path.is_empty())
+        .ok_or(ConfigError::Missing(name))?;
+
+    let contents = fs::read(&path).map_err(|e| ConfigError::KeyFile {
+        name,
+        path: path.clone(),
+        reason: e.to_string(),
+    })?;
+
+    Ok((path, contents))
+}
+
+fn key_error(name: &'static str, path: String, err: jsonwebtoken::errors::Error) -> ConfigError {
+    ConfigError::KeyFile {
+        name,
+        path,
+        reason: err.to_string(),
+    }
 }
 
-pub fn generate_token(user_id: Uuid) -> Result<String, ApiError> {
-    // Get JWT secret from environment or use default
-    let jwt_secret = env::var("JWT_SECRET").unwrap_or_else(|_| "".to_string());
+/// Muat pengaturan JWT saat startup supaya key yang hilang langsung ketahuan
+pub fn init_jwt(config: &Config) -> Result<(), ConfigError> {
+    JWT_SETTINGS
+        .get_or_init(|| JwtSettings::load(config))
+        .as_ref()
+        .map(|_| ())
+        .map_err(Clone::clone)
+}
+
+/// Ambil pengaturan JWT, atau error konfigurasi kalo gagal (atau belum) dimuat
+fn settings() -> Result<&'static JwtSettings, ApiError> {
+    let Some(settings) = JWT_SETTINGS.get() else {
+        error!("JWT configuration error: init_jwt was not called");
+        return Err(ApiError::Internal("Server configuration error".to_string()));
+    };
+
+    settings.as_ref().map_err(|err| {
+        error!("JWT configuration error: {}", err);
+        ApiError::Internal("Server configuration error".to_string())
+    })
+}
+
+pub fn generate_token(user_id: Uuid, role: Role) -> Result<String, ApiError> {
+    encode_token(settings()?, user_id, role)
+}
+
+/// Verifikasi token JWT dan ekstrak user ID beserta role
+pub fn verify_token(token: &str) -> Result<CurrentUser, ApiError> {
+    decode_token(settings()?, token)
+}
+
+/// Buat token undo yang berlaku sampai `expires_at`
+pub fn generate_undo_token(
+    undo: &UndoDelete,
+    expires_at: DateTime<Utc>,
+) -> Result<String, ApiError> {
+    encode_undo_token(settings()?, undo, expires_at)
+}
+
+/// Verifikasi token undo; token yang expired atau bukan token undo ditolak
+pub fn verify_undo_token(token: &str) -> Result<UndoDelete, ApiError> {
+    decode_undo_token(settings()?, token)
+}
 
+fn encode_token(settings: &JwtSettings, user_id: Uuid, role: Role) -> Result<String, ApiError> {
     // Create claims
     let now = Utc::now();
     let claims = Claims {
         sub: user_id.to_string(),
         iat: now.timestamp() as usize,
-        exp: (now + Duration::hours(24)).timestamp() as usize, // Token valid for 24 hours
+        exp: (now + Duration::hours(TOKEN_TTL_HOURS)).timestamp() as usize,
+        iss: settings.issuer.clone(),
+        aud: settings.audience.clone(),
+        role,
     };
 
     // Encode token
     encode(
-        &Header::new(Algorithm::HS256),
+        &Header::new(settings.algorithm),
         &claims,
-        &EncodingKey::from_secret(jwt_secret.as_bytes()),
+        &settings.encoding_key,
     )
     .map_err(|e| ApiError::Internal(format!("Token generation error: {}", e)))
 }
 
-/// Verifikasi token JWT dan ekstrak user ID
-pub fn verify_token(token: &str) -> Result<Uuid, ApiError> {
-    // Ambil JWT secret dari environment
-    let jwt_secret = env::var("JWT_SECRET").unwrap_or_else(|_| "".to_string());
-
-    if jwt_secret.is_empty() {
-        error!("JWT_SECRET not set in environment");
-        return Err(ApiError::Internal("Server configuration error".to_string()));
-    }
+fn decode_token(settings: &JwtSettings, token: &str) -> Result<CurrentUser, ApiError> {
+    // Wajibkan issuer dan audience yang sesuai, bukan cuma signature dan expiry
+    let mut validation = Validation::new(settings.algorithm);
+    validation.set_issuer(&[&settings.issuer]);
+    validation.set_audience(&[&settings.audience]);
+    validation.set_required_spec_claims(&["exp", "iss", "aud"]);
+    // Toleransi clock skew berlaku untuk exp dan nbf (kalo ada)
+    validation.leeway = settings.leeway;
+    validation.validate_nbf = true;
 
     // Decode token
-    let token_data = decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(jwt_secret.as_bytes()),
-        &Validation::new(Algorithm::HS256),
-    )
-    .map_err(|err| {
-        error!("Token validation error: {}", err);
-        ApiError::Unauthorized("Invalid token".to_string())
-    })?;
+    let token_data =
+        decode::<Claims>(token, &settings.decoding_key, &validation).map_err(|err| {
+            error!("Token validation error: {}", err);
+            ApiError::Unauthorized("Invalid token".to_string())
+        })?;
 
     // Parse user ID dari subject token
     let user_id = Uuid::parse_str(&token_data.claims.sub)
         .map_err(|_| ApiError::Unauthorized("Invalid user ID in token".to_string()))?;
 
-    Ok(user_id)
+    Ok(CurrentUser {
+        id: user_id,
+        role: token_data.claims.role,
+    })
+}
+
+fn encode_undo_token(
+    settings: &JwtSettings,
+    undo: &UndoDelete,
+    expires_at: DateTime<Utc>,
+) -> Result<String, ApiError> {
+    let now = Utc::now();
+    let claims = UndoDeleteClaims {
+        sub: undo.product_id.to_string(),
+        jti: undo.undo_id.to_string(),
+        uid: undo.deleted_by.to_string(),
+        iat: now.timestamp() as usize,
+        exp: expires_at.timestamp() as usize,
+        iss: settings.issuer.clone(),
+        aud: format!("{}{}", settings.audience, UNDO_DELETE_AUDIENCE_SUFFIX),
+    };
+
+    encode(
+        &Header::new(settings.algorithm),
+        &claims,
+        &settings.encoding_key,
+    )
+    .map_err(|e| ApiError::Internal(format!("Token generation error: {}", e)))
+}
+
+fn decode_undo_token(settings: &JwtSettings, token: &str) -> Result<UndoDelete, ApiError> {
+    let invalid = || ApiError::BadRequest("Undo token is invalid or has expired".to_string());
+
+    let mut validation = Validation::new(settings.algorithm);
+    validation.set_issuer(&[&settings.issuer]);
+    validation.set_audience(&[format!(
+        "{}{}",
+        settings.audience, UNDO_DELETE_AUDIENCE_SUFFIX
+    )]);
+    validation.set_required_spec_claims(&["exp", "iss", "aud", "sub", "jti"]);
+    // Jendela undo sudah pendek, jadi tidak pakai leeway
+    validation.leeway = 0;
+
+    let claims = decode::<UndoDeleteClaims>(token, &settings.decoding_key, &validation)
+        .map_err(|_| invalid())?
+        .claims;
+
+    let parse = |value: &str| Uuid::parse_str(value).map_err(|_| invalid());
+    Ok(UndoDelete {
+        product_id: parse(&claims.sub)?,
+        undo_id: parse(&claims.jti)?,
+        deleted_by: parse(&claims.uid)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RS256_PRIVATE_KEY: &str = include_str!("testdata/jwt_rs256_private.pem");
+    const RS256_PUBLIC_KEY: &str = include_str!("testdata/jwt_rs256_public.pem");
+    const ISSUER: &str = "learning-rust";
+    const AUDIENCE: &str = "learning-rust-api";
+
+    fn settings(issuer: &str, audience: &str) -> JwtSettings {
+        JwtSettings {
+            algorithm: Algorithm::HS256,
+            encoding_key: EncodingKey::from_secret(b"test-secret"),
+            decoding_key: DecodingKey::from_secret(b"test-secret"),
+            issuer: issuer.to_string(),
+            audience: audience.to_string(),
+            leeway: 0,
+        }
+    }
+
+    // Token HS256 dengan exp relatif terhadap sekarang (negatif berarti sudah expired)
+    fn token_expiring_in(settings: &JwtSettings, seconds: i64) -> String {
+        let now = Utc::now().timestamp();
+        let claims = Claims {
+            sub: Uuid::new_v4().to_string(),
+            iat: (now - 3600) as usize,
+            exp: (now + seconds) as usize,
+            iss: settings.issuer.clone(),
+            aud: settings.audience.clone(),
+            role: Role::User,
+        };
+        encode(
+            &Header::new(settings.algorithm),
+            &claims,
+            &settings.encoding_key,
+        )
+        .unwrap()
+    }
+
+    fn rs256_settings() -> JwtSettings {
+        JwtSettings {
+            algorithm: Algorithm::RS256,
+            encoding_key: EncodingKey::from_rsa_pem(RS256_PRIVATE_KEY.as_bytes()).unwrap(),
+            decoding_key: DecodingKey::from_rsa_pem(RS256_PUBLIC_KEY.as_bytes()).unwrap(),
+            issuer: ISSUER.to_string(),
+            audience: AUDIENCE.to_string(),
+            leeway: 0,
+        }
+    }
+
+    #[test]
+    fn test_token_with_matching_issuer_and_audience() {
+        let settings = settings("learning-rust", "learning-rust-api");
+        let user_id = Uuid::new_v4();
+        let token = encode_token(&settings, user_id, Role::Admin).unwrap();
+
+        let user = decode_token(&settings, &token).unwrap();
+        assert_eq!(user.id, user_id);
+        assert_eq!(user.role, Role::Admin);
+    }
+
+    #[test]
+    fn test_token_with_wrong_issuer_is_rejected() {
+        let token = encode_token(
+            &settings("other-service", "learning-rust-api"),
+            Uuid::new_v4(),
+            Role::User,
+        )
+        .unwrap();
+
+        let result = decode_token(&settings("learning-rust", "learning-rust-api"), &token);
+        assert!(matches!(result, Err(ApiError::Unauthorized(_))));
+    }
+
+    #[test]
+    fn test_token_with_wrong_audience_is_rejected() {
+        let token = encode_token(
+            &settings("learning-rust", "other-api"),
+            Uuid::new_v4(),
+            Role::User,
+        )
+        .unwrap();
+
+        let result = decode_token(&settings("learning-rust", "learning-rust-api"), &token);
+        assert!(matches!(result, Err(ApiError::Unauthorized(_))));
+    }
+
+    #[test]
+    fn test_expired_token_accepted_within_leeway() {
+        let mut settings = settings(ISSUER, AUDIENCE);
+        settings.leeway = 30;
+
+        let token = token_expiring_in(&settings, -10);
+        assert!(decode_token(&settings, &token).is_ok());
+    }
+
+    #[test]
+    fn test_expired_token_rejected_beyond_leeway() {
+        let mut settings = settings(ISSUER, AUDIENCE);
+        settings.leeway = 30;
+
+        let token = token_expiring_in(&settings, -60);
+        assert!(matches!(
+            decode_token(&settings, &token),
+            Err(ApiError::Unauthorized(_))
+        ));
+
+        // Tanpa leeway, token yang baru saja expired langsung ditolak
+        settings.leeway = 0;
+        let token = token_expiring_in(&settings, -10);
+        assert!(decode_token(&settings, &token).is_err());
+    }
+
+    #[test]
+    fn test_rs256_token_round_trip() {
+        let settings = rs256_settings();
+        let user_id = Uuid::new_v4();
+        let token = encode_token(&settings, user_id, Role::User).unwrap();
+
+        let user = decode_token(&settings, &token).unwrap();
+        assert_eq!(user.id, user_id);
+    }
+
+    #[test]
+    fn test_rs256_rejects_hs256_token() {
+        let token = encode_token(&settings(ISSUER, AUDIENCE), Uuid::new_v4(), Role::User).unwrap();
+
+        let result = decode_token(&rs256_settings(), &token);
+        assert!(matches!(result, Err(ApiError::Unauthorized(_))));
+    }
+
+    fn undo_delete() -> UndoDelete {
+        UndoDelete {
+            product_id: Uuid::new_v4(),
+            undo_id: Uuid::new_v4(),
+            deleted_by: Uuid::new_v4(),
+        }
+    }
+
+    #[test]
+    fn test_undo_token_round_trip() {
+        let settings = settings(ISSUER, AUDIENCE);
+        let undo = undo_delete();
+
+        let token =
+            encode_undo_token(&settings, &undo, Utc::now() + Duration::seconds(60)).unwrap();
+        assert_eq!(decode_undo_token(&settings, &token).unwrap(), undo);
+    }
+
+    #[test]
+    fn test_expired_undo_token_is_rejected() {
+        let settings = settings(ISSUER, AUDIENCE);
+        let token = encode_undo_token(&settings, &undo_delete(), Utc::now() - Duration::seconds(5))
+            .unwrap();
+
+        assert!(matches!(
+            decode_undo_token(&settings, &token),
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_undo_and_access_tokens_are_not_interchangeable() {
+        let settings = settings(ISSUER, AUDIENCE);
+
+        let undo_token = encode_undo_token(
+            &settings,
+            &undo_delete(),
+            Utc::now() + Duration::seconds(60),
+        )
+        .unwrap();
+        assert!(decode_token(&settings, &undo_token).is_err());
+
+        let access_token = encode_token(&settings, Uuid::new_v4(), Role::Admin).unwrap();
+        assert!(decode_undo_token(&settings, &access_token).is_err());
+    }
 }
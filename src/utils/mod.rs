@@ -1,33 +1,108 @@
 use ::time::OffsetDateTime;
 /// Utilities module for common functions
 use chrono::{DateTime, TimeZone, Utc};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Deserializer};
+
+use crate::core::error::ApiError;
 
 mod jwt;
-pub use jwt::{generate_token, verify_token};
+pub mod number;
+pub mod timestamp;
+pub use jwt::{
+    TOKEN_TTL_HOURS, UndoDelete, generate_token, generate_undo_token, init_jwt, verify_token,
+    verify_undo_token,
+};
 
-/// Convert OffsetDateTime to chrono's DateTime<Utc>
+/// Convert OffsetDateTime to chrono's DateTime<Utc>, truncated to whole seconds
+///
+/// Postgres stores microseconds; dropping them keeps values read back from the
+/// database equal to what API responses show.
 pub fn offset_to_chrono(dt: OffsetDateTime) -> DateTime<Utc> {
-    let unix_timestamp = dt.unix_timestamp();
-    let nanos = dt.nanosecond();
-    Utc.timestamp_opt(unix_timestamp, nanos).unwrap()
+    Utc.timestamp_opt(dt.unix_timestamp(), 0).unwrap()
 }
 
-/// Format currency to Indonesian Rupiah format
-#[allow(dead_code)]
-pub fn format_rupiah(amount: f64) -> String {
+/// Deserialize a field that distinguishes an absent key from an explicit `null`
+///
+/// Use together with `#[serde(default)]`: an absent key yields `None`,
+/// `null` yields `Some(None)` and a value yields `Some(Some(value))`.
+pub fn deserialize_nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Currency used for formatted prices (`DEFAULT_CURRENCY`), set once at startup
+static DEFAULT_CURRENCY: OnceCell<Option<String>> = OnceCell::new();
+
+/// Set the currency used for formatted prices; later calls are ignored
+pub fn set_default_currency(currency: Option<String>) {
+    let _ = DEFAULT_CURRENCY.set(currency);
+}
+
+/// The configured currency, `None` (no formatting) when none was set
+pub fn default_currency() -> Option<&'static str> {
+    DEFAULT_CURRENCY
+        .get()
+        .and_then(|currency| currency.as_deref())
+}
+
+/// Format an amount in the given ISO currency code
+///
+/// IDR, USD and EUR use their usual symbol and separators; other codes fall
+/// back to two decimals followed by the code.
+pub fn format_currency(amount: f64, currency: &str) -> String {
+    let currency = currency.to_uppercase();
+    let (symbol, decimals, thousands, decimal) = match currency.as_str() {
+        "IDR" => ("Rp ", 0, '.', ','),
+        "USD" => ("$", 2, ',', '.'),
+        "EUR" => ("€", 2, '.', ','),
+        _ => return format!("{:.2} {}", amount, currency),
+    };
+
+    let sign = if amount < 0.0 { "-" } else { "" };
+    let amount_str = format!("{:.*}", decimals, amount.abs());
+    let (whole, fraction) = match amount_str.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (amount_str.as_str(), None),
+    };
+
     let mut s = String::new();
-    let amount_str = format!("{:.0}", amount);
-    let chars: Vec<char> = amount_str.chars().collect();
-    let len = chars.len();
-
-    for (i, c) in chars.iter().enumerate() {
-        s.push(*c);
-        if (len - i - 1) % 3 == 0 && i < len - 1 {
-            s.push('.');
+    let len = whole.len();
+    for (i, c) in whole.chars().enumerate() {
+        s.push(c);
+        if (len - i - 1).is_multiple_of(3) && i < len - 1 {
+            s.push(thousands);
         }
     }
 
-    format!("Rp {}", s)
+    if let Some(fraction) = fraction {
+        s.push(decimal);
+        s.push_str(fraction);
+    }
+
+    format!("{}{}{}", sign, symbol, s)
+}
+
+/// Format currency to Indonesian Rupiah format
+#[allow(dead_code)]
+pub fn format_rupiah(amount: f64) -> String {
+    format_currency(amount, "IDR")
+}
+
+/// Trim a string and collapse runs of internal whitespace into single spaces
+pub fn normalize_whitespace(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Reject a normalized text field that ended up empty
+pub fn require_non_empty(value: String, field: &str) -> Result<String, ApiError> {
+    if value.is_empty() {
+        return Err(ApiError::BadRequest(format!("{} cannot be empty", field)));
+    }
+    Ok(value)
 }
 
 /// Validate that a price is not negative
@@ -50,6 +125,15 @@ pub fn truncate_string(s: &str, max_len: usize) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_offset_to_chrono_truncates_subseconds() {
+        let dt = OffsetDateTime::from_unix_timestamp_nanos(1_700_000_000_999_999_000).unwrap();
+        assert_eq!(
+            offset_to_chrono(dt),
+            Utc.timestamp_opt(1_700_000_000, 0).unwrap()
+        );
+    }
+
     #[test]
     fn test_format_rupiah() {
         assert_eq!(format_rupiah(1000000.0), "Rp 1.000.000");
@@ -57,6 +141,51 @@ mod tests {
         assert_eq!(format_rupiah(0.0), "Rp 0");
     }
 
+    #[test]
+    fn test_format_currency_idr() {
+        assert_eq!(format_currency(0.0, "IDR"), "Rp 0");
+        assert_eq!(format_currency(1300000.0, "idr"), "Rp 1.300.000");
+        assert_eq!(format_currency(1234567890.0, "IDR"), "Rp 1.234.567.890");
+    }
+
+    #[test]
+    fn test_format_currency_usd() {
+        assert_eq!(format_currency(0.0, "USD"), "$0.00");
+        assert_eq!(format_currency(999.5, "USD"), "$999.50");
+        assert_eq!(format_currency(1234567.891, "USD"), "$1,234,567.89");
+        assert_eq!(format_currency(-1500.0, "USD"), "-$1,500.00");
+    }
+
+    #[test]
+    fn test_format_currency_eur() {
+        assert_eq!(format_currency(0.0, "EUR"), "€0,00");
+        assert_eq!(format_currency(1234567.5, "EUR"), "€1.234.567,50");
+    }
+
+    #[test]
+    fn test_format_currency_unknown_code() {
+        assert_eq!(format_currency(12.5, "JPY"), "12.50 JPY");
+    }
+
+    #[test]
+    fn test_normalize_whitespace() {
+        assert_eq!(normalize_whitespace(" iPhone "), "iPhone");
+        assert_eq!(normalize_whitespace("Webcam \t  4K\n"), "Webcam 4K");
+        assert_eq!(normalize_whitespace("   "), "");
+    }
+
+    #[test]
+    fn test_require_non_empty() {
+        assert_eq!(
+            require_non_empty("iPhone".to_string(), "Name").unwrap(),
+            "iPhone"
+        );
+        assert!(matches!(
+            require_non_empty(String::new(), "Name"),
+            Err(ApiError::BadRequest(message)) if message == "Name cannot be empty"
+        ));
+    }
+
     #[test]
     fn test_validate_price() {
         assert!(validate_price(100.0));
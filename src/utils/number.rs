@@ -0,0 +1,188 @@
+//! Locale-tolerant parsing of decimal numbers from query strings
+//!
+//! Clients in comma-decimal locales send `min_price=19,99`. Both `.` and `,`
+//! are accepted as the decimal separator; `NUMBER_LOCALE` only decides the
+//! ambiguous case of a single separator followed by exactly three digits
+//! (`1,299` is 1299 with the default `en`, but 1.299 with `id`).
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Deserializer, Serialize, de::Error};
+
+/// Decimal separator convention used to resolve ambiguous numbers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NumberLocale {
+    /// `1,299.99` (`NUMBER_LOCALE=en` or `dot`)
+    #[default]
+    Dot,
+    /// `1.299,99` (`NUMBER_LOCALE=id`, `de` or `comma`)
+    Comma,
+}
+
+impl NumberLocale {
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "en" | "dot" => Some(Self::Dot),
+            "id" | "de" | "comma" => Some(Self::Comma),
+            _ => None,
+        }
+    }
+
+    fn decimal_separator(self) -> char {
+        match self {
+            Self::Dot => '.',
+            Self::Comma => ',',
+        }
+    }
+}
+
+/// Locale from `NUMBER_LOCALE`, set once when the server starts
+static NUMBER_LOCALE: OnceCell<NumberLocale> = OnceCell::new();
+
+/// Set the locale used by [`deserialize_decimal`]; later calls are ignored
+pub fn set_number_locale(locale: NumberLocale) {
+    let _ = NUMBER_LOCALE.set(locale);
+}
+
+/// The configured locale, `en` when none was set
+fn number_locale() -> NumberLocale {
+    NUMBER_LOCALE.get().copied().unwrap_or_default()
+}
+
+/// Parse a decimal number written with `.` or `,` as the decimal separator
+///
+/// When both separators appear, the last one is the decimal separator and the
+/// other groups thousands (`1.299,99`, `1,299.99`). A separator repeated on its
+/// own only groups thousands (`1.000.000`). Exponents, `inf` and `NaN` are rejected.
+pub fn parse_decimal(value: &str, locale: NumberLocale) -> Result<f64, String> {
+    let invalid = || format!("invalid number '{}'", value);
+    let trimmed = value.trim();
+
+    let (sign, digits) = match trimmed.strip_prefix(['-', '+']) {
+        Some(rest) => (&trimmed[..1], rest),
+        None => ("", trimmed),
+    };
+    if !digits.chars().any(|c| c.is_ascii_digit())
+        || !digits
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == '.' || c == ',')
+    {
+        return Err(invalid());
+    }
+
+    let decimal = decimal_separator(digits, locale);
+    let mut normalized = String::with_capacity(trimmed.len());
+    normalized.push_str(sign);
+    for c in digits.chars() {
+        match c {
+            c if Some(c) == decimal => normalized.push('.'),
+            '.' | ',' => {}
+            c => normalized.push(c),
+        }
+    }
+
+    normalized.parse().map_err(|_| invalid())
+}
+
+/// Which separator in `digits` marks the decimals, if any
+fn decimal_separator(digits: &str, locale: NumberLocale) -> Option<char> {
+    let last = digits.rfind(['.', ','])?;
+    let separator = digits[last..].chars().next()?;
+
+    let other = if separator == '.' { ',' } else { '.' };
+    if digits.contains(other) {
+        return Some(separator);
+    }
+    if digits.matches(separator).count() > 1 {
+        return None;
+    }
+
+    // A lone separator before exactly three digits is grouping, unless the locale says otherwise
+    let decimals = &digits[last + 1..];
+    if separator != locale.decimal_separator() && decimals.len() == 3 && last > 0 {
+        None
+    } else {
+        Some(separator)
+    }
+}
+
+/// A number as it arrives: text from a query string, or an actual number from JSON
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawDecimal {
+    Number(f64),
+    Text(String),
+}
+
+/// Deserialize an optional query parameter with [`parse_decimal`] and `NUMBER_LOCALE`
+///
+/// Plain numbers (from JSON) are taken as they are. Use together with `#[serde(default)]`.
+pub fn deserialize_decimal<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<f64>, D::Error> {
+    match Option::<RawDecimal>::deserialize(deserializer)? {
+        Some(RawDecimal::Number(value)) => Ok(Some(value)),
+        Some(RawDecimal::Text(value)) => parse_decimal(&value, number_locale())
+            .map(Some)
+            .map_err(D::Error::custom),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_decimal_accepts_comma_and_dot() {
+        assert_eq!(parse_decimal("19,99", NumberLocale::Dot), Ok(19.99));
+        assert_eq!(parse_decimal("19.99", NumberLocale::Dot), Ok(19.99));
+        assert_eq!(parse_decimal("19,99", NumberLocale::Comma), Ok(19.99));
+        assert_eq!(parse_decimal("19.99", NumberLocale::Comma), Ok(19.99));
+        assert_eq!(parse_decimal(" 100 ", NumberLocale::Dot), Ok(100.0));
+        assert_eq!(parse_decimal("-0,5", NumberLocale::Dot), Ok(-0.5));
+    }
+
+    #[test]
+    fn test_parse_decimal_rejects_invalid_values() {
+        for value in [
+            "abc",
+            "",
+            "19,99abc",
+            "1e5",
+            "NaN",
+            "inf",
+            "-",
+            ",",
+            "1.2.3,4.5",
+        ] {
+            assert!(
+                parse_decimal(value, NumberLocale::Dot).is_err(),
+                "{value} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_decimal_thousands_grouping() {
+        assert_eq!(parse_decimal("1.299,99", NumberLocale::Dot), Ok(1299.99));
+        assert_eq!(parse_decimal("1,299.99", NumberLocale::Comma), Ok(1299.99));
+        assert_eq!(
+            parse_decimal("1.000.000", NumberLocale::Dot),
+            Ok(1_000_000.0)
+        );
+
+        // A lone separator before three digits depends on the locale
+        assert_eq!(parse_decimal("1,299", NumberLocale::Dot), Ok(1299.0));
+        assert_eq!(parse_decimal("1,299", NumberLocale::Comma), Ok(1.299));
+        assert_eq!(parse_decimal("1.299", NumberLocale::Comma), Ok(1299.0));
+        assert_eq!(parse_decimal(",299", NumberLocale::Dot), Ok(0.299));
+    }
+
+    #[test]
+    fn test_number_locale_parse() {
+        assert_eq!(NumberLocale::parse("EN"), Some(NumberLocale::Dot));
+        assert_eq!(NumberLocale::parse(" id "), Some(NumberLocale::Comma));
+        assert_eq!(NumberLocale::parse("klingon"), None);
+    }
+}
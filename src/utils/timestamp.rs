@@ -0,0 +1,71 @@
+//! Serde helpers that write timestamps as `YYYY-MM-DDTHH:MM:SSZ`
+//!
+//! Use with `#[serde(with = "timestamp")]` on `DateTime<Utc>` fields and
+//! `#[serde(with = "timestamp::option")]` on `Option<DateTime<Utc>>` fields.
+//! Deserializing accepts any RFC 3339 timestamp and drops sub-second digits.
+
+use chrono::{DateTime, SecondsFormat, SubsecRound, Utc};
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+/// Format a timestamp with second precision and a trailing `Z`
+pub fn format(dt: &DateTime<Utc>) -> String {
+    dt.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Parse an RFC 3339 timestamp, truncated to whole seconds
+pub fn parse(value: &str) -> Result<DateTime<Utc>, chrono::ParseError> {
+    DateTime::parse_from_rfc3339(value).map(|dt| dt.with_timezone(&Utc).trunc_subsecs(0))
+}
+
+pub fn serialize<S: Serializer>(dt: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format(dt))
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Utc>, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    parse(&value).map_err(D::Error::custom)
+}
+
+/// Same format for optional timestamps; `None` is written as `null`
+pub mod option {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        dt: &Option<DateTime<Utc>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match dt {
+            Some(dt) => super::serialize(dt, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<DateTime<Utc>>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|value| parse(&value).map_err(D::Error::custom))
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_format_uses_second_precision_and_z() {
+        let dt = Utc.timestamp_opt(1_700_000_000, 123_456_789).unwrap();
+        assert_eq!(format(&dt), "2023-11-14T22:13:20Z");
+    }
+
+    #[test]
+    fn test_parse_accepts_offsets_and_fractions() {
+        let expected = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        assert_eq!(parse("2023-11-14T22:13:20Z").unwrap(), expected);
+        assert_eq!(parse("2023-11-14T22:13:20.987654Z").unwrap(), expected);
+        assert_eq!(parse("2023-11-15T05:13:20+07:00").unwrap(), expected);
+        assert!(parse("14/11/2023").is_err());
+    }
+}